@@ -1,9 +1,27 @@
-use anyhow::{Context, Result};
+use crate::error::{Context, Error, Result};
 use chrono::{DateTime, Utc};
 use libsql::{Builder, Connection, Database};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::process::Command;
+use octocrab::models::issues::Issue as GitHubIssue;
+use octocrab::models::{IssueState, Label as GitHubLabel};
+
+use crate::kanban::BoardDefinition;
+use crate::git_ops::{CommitInfo, FileChange, GitHubMutation, GitHubOps, GitOps};
+use crate::query::{Filter, Query};
+
+pub mod dependencies;
+pub mod events;
+pub mod hierarchy;
+mod migrations;
+pub mod rank;
+pub mod search;
+
+pub use migrations::Migration;
+use migrations::{MigrationStep, MIGRATIONS};
+use dependencies::{find_path, link_value, parse_link_value, DependencyKind, IssueLink};
+use events::{Change, EventField, IssueEvent, Operation, OPERATION};
+use hierarchy::Progress;
+use search::{CommitHit, IssueHit, SearchResults, Snippet};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GitCommit {
@@ -13,11 +31,50 @@ pub struct GitCommit {
     pub author_email: String,
     pub commit_date: DateTime<Utc>,
     pub message: String,
+    #[serde(default)]
+    pub parent_hashes: Vec<String>,
     pub files_changed: Vec<String>,
+    #[serde(default)]
+    pub file_stats: Vec<FileChange>,
     pub insertions: i32,
     pub deletions: i32,
 }
 
+impl From<CommitInfo> for GitCommit {
+    fn from(info: CommitInfo) -> Self {
+        Self {
+            id: None,
+            hash: info.hash,
+            author_name: info.author_name,
+            author_email: info.author_email,
+            commit_date: info.commit_date,
+            message: info.message,
+            parent_hashes: info.parent_hashes,
+            files_changed: info.files_changed,
+            file_stats: info.file_stats,
+            insertions: info.insertions,
+            deletions: info.deletions,
+        }
+    }
+}
+
+/// Narrows `TaskDatabase::get_commits`; unset fields match every commit
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct CommitFilter {
+    /// Case-insensitive substring of the author's name or email
+    pub author: Option<String>,
+    /// Only commits made at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only commits made at or before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only commits touching this file, or any file below this directory
+    pub path: Option<String>,
+    /// Only commits that reference this issue
+    pub issue: Option<i64>,
+    /// Return at most this many commits (newest first)
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Issue {
     pub id: Option<i64>,
@@ -29,6 +86,143 @@ pub struct Issue {
     pub updated_at: DateTime<Utc>,
     pub assignee: Option<String>,
     pub labels: Vec<String>,
+    /// Where the issue was imported from, if it mirrors an issue in another tracker
+    #[serde(default)]
+    pub external_ref: Option<ExternalRef>,
+    /// The issue this one is a sub-task of
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+}
+
+/// Identifies an issue or comment in an external tracker, e.g. GitHub issue #42
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExternalRef {
+    /// Tracker name, e.g. `github`
+    pub source: String,
+    /// Identifier within the tracker, e.g. the GitHub issue number or comment id
+    pub id: String,
+    pub url: Option<String>,
+}
+
+impl ExternalRef {
+    /// Reference to a GitHub issue by number, or a GitHub comment by id
+    pub fn github(number: u64, url: Option<String>) -> Self {
+        Self {
+            source: "github".to_string(),
+            id: number.to_string(),
+            url,
+        }
+    }
+}
+
+/// Result of `TaskDatabase::upsert_external_issue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    Created(i64),
+    Updated(i64),
+    /// The stored copy is at least as recent as the incoming one
+    Unchanged(i64),
+    /// The stored copy is in the trash and was left there
+    Trashed,
+}
+
+/// The fields of a GitHub-linked issue as both sides last agreed on them; see `atask sync`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncSnapshot {
+    pub issue_id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub closed: bool,
+    pub labels: Vec<String>,
+    pub assignee: Option<String>,
+    /// Local `updated_at` right after the last sync
+    pub local_updated_at: DateTime<Utc>,
+    /// GitHub's `updated_at` right after the last sync
+    pub remote_updated_at: DateTime<Utc>,
+}
+
+/// A GitHub mutation waiting in the outbox
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboxEntry {
+    pub id: i64,
+    /// `owner/repo` the mutation is for
+    pub repository: String,
+    pub mutation: GitHubMutation,
+    pub created_at: DateTime<Utc>,
+    pub attempts: i64,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+}
+
+/// Result of `TaskDatabase::submit_github_mutation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Sent,
+    /// Queued in the outbox under this id, to be sent by `flush_outbox`
+    Queued(i64),
+}
+
+/// Result of `TaskDatabase::flush_outbox`
+#[derive(Debug, Default)]
+pub struct FlushReport {
+    pub sent: usize,
+    /// Label changes dropped because a later change to the same label superseded them
+    pub coalesced: usize,
+    /// Entries still queued: ones that failed, and ones waiting behind a failure on the same issue
+    pub remaining: Vec<OutboxEntry>,
+    /// Entries GitHub refused (e.g. the issue is gone), dropped from the outbox with their error
+    pub rejected: Vec<OutboxEntry>,
+}
+
+/// One column of the Kanban board definition
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BoardColumn {
+    pub id: String,
+    pub title: String,
+    pub color: String,
+    /// Workflow label that puts an issue in this column
+    pub label: Option<String>,
+    /// Issue status that puts an issue in this column (and is set when moving into it)
+    pub status: Option<IssueStatus>,
+    /// Columns are shown in ascending order
+    pub position: i64,
+    /// Most cards the column should hold; moves past it are refused unless forced
+    #[serde(default)]
+    pub wip_limit: Option<u32>,
+}
+
+/// Where a card was placed by hand within a board column
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CardRank {
+    pub issue_id: i64,
+    pub column_id: String,
+    /// Cards sort by rank as plain text, top first; see `rank::between`
+    pub rank: String,
+}
+
+/// A comment on an issue
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Comment {
+    pub id: Option<i64>,
+    pub issue_id: i64,
+    pub author: String,
+    /// Markdown
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// The comment's copy on GitHub, once imported from or exported there
+    pub external_ref: Option<ExternalRef>,
+    /// The external copy's `updated_at` when both copies last matched
+    #[serde(skip)]
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+/// An issue in the trash
+#[derive(Debug, Serialize, Clone)]
+pub struct TrashedIssue {
+    #[serde(flatten)]
+    pub issue: Issue,
+    pub deleted_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,46 +234,57 @@ pub struct Label {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Serialized by variant name (`"InProgress"`); the `Display` spelling (`"in_progress"`) is
+/// accepted too, so API clients and query strings can use the same words as the CLI
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum IssueStatus {
+    #[serde(alias = "open")]
     Open,
+    #[serde(alias = "in_progress")]
     InProgress,
+    #[serde(alias = "resolved")]
     Resolved,
+    #[serde(alias = "closed")]
     Closed,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Ordered from least to most urgent. Serialized like `IssueStatus`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum IssuePriority {
+    #[serde(alias = "low")]
     Low,
+    #[serde(alias = "medium")]
     Medium,
+    #[serde(alias = "high")]
     High,
+    #[serde(alias = "critical")]
     Critical,
 }
 
-impl ToString for IssueStatus {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for IssueStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            IssueStatus::Open => "open".to_string(),
-            IssueStatus::InProgress => "in_progress".to_string(),
-            IssueStatus::Resolved => "resolved".to_string(),
-            IssueStatus::Closed => "closed".to_string(),
+            IssueStatus::Open => write!(f, "open"),
+            IssueStatus::InProgress => write!(f, "in_progress"),
+            IssueStatus::Resolved => write!(f, "resolved"),
+            IssueStatus::Closed => write!(f, "closed"),
         }
     }
 }
 
-impl ToString for IssuePriority {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for IssuePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            IssuePriority::Low => "low".to_string(),
-            IssuePriority::Medium => "medium".to_string(),
-            IssuePriority::High => "high".to_string(),
-            IssuePriority::Critical => "critical".to_string(),
+            IssuePriority::Low => write!(f, "low"),
+            IssuePriority::Medium => write!(f, "medium"),
+            IssuePriority::High => write!(f, "high"),
+            IssuePriority::Critical => write!(f, "critical"),
         }
     }
 }
 
 impl std::str::FromStr for IssueStatus {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
         match s {
@@ -87,13 +292,13 @@ impl std::str::FromStr for IssueStatus {
             "in_progress" => Ok(IssueStatus::InProgress),
             "resolved" => Ok(IssueStatus::Resolved),
             "closed" => Ok(IssueStatus::Closed),
-            _ => Err(anyhow::anyhow!("Invalid issue status: {}", s)),
+            _ => Err(Error::Validation(format!("Invalid issue status: {}", s))),
         }
     }
 }
 
 impl std::str::FromStr for IssuePriority {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
         match s {
@@ -101,106 +306,304 @@ impl std::str::FromStr for IssuePriority {
             "medium" => Ok(IssuePriority::Medium),
             "high" => Ok(IssuePriority::High),
             "critical" => Ok(IssuePriority::Critical),
-            _ => Err(anyhow::anyhow!("Invalid issue priority: {}", s)),
+            _ => Err(Error::Validation(format!("Invalid issue priority: {}", s))),
+        }
+    }
+}
+
+/// How a commit message refers to an issue
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueReferenceKind {
+    /// A GitHub-style closing keyword such as `Fixes #12`
+    Closes,
+    /// A plain mention such as `#12` or `Refs #12`
+    References,
+}
+
+impl std::fmt::Display for IssueReferenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IssueReferenceKind::Closes => write!(f, "closes"),
+            IssueReferenceKind::References => write!(f, "references"),
+        }
+    }
+}
+
+impl std::str::FromStr for IssueReferenceKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "closes" => Ok(IssueReferenceKind::Closes),
+            "references" => Ok(IssueReferenceKind::References),
+            _ => Err(Error::Validation(format!("Invalid issue reference kind: {}", s))),
+        }
+    }
+}
+
+/// An issue number mentioned in a commit message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueReference {
+    pub issue_id: i64,
+    pub kind: IssueReferenceKind,
+}
+
+/// A commit linked to an issue, as returned by `TaskDatabase::get_commits_for_issue`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LinkedCommit {
+    #[serde(flatten)]
+    pub commit: GitCommit,
+    pub kind: IssueReferenceKind,
+}
+
+/// Keywords that close an issue when followed by `#N`, as on GitHub
+const CLOSING_KEYWORDS: &[&str] = &[
+    "close", "closes", "closed",
+    "fix", "fixes", "fixed",
+    "resolve", "resolves", "resolved",
+];
+
+/// Find `#N` issue references in a commit message.
+///
+/// A reference preceded by a closing keyword (`Fixes #3`, `closes: #3`) closes the issue;
+/// anything else (`#3`, `Refs #3`) is a plain reference. Each issue appears once, and a
+/// closing reference wins over a plain one.
+pub fn parse_issue_references(message: &str) -> Vec<IssueReference> {
+    let bytes = message.as_bytes();
+    let mut references: Vec<IssueReference> = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'#' {
+            i += 1;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+
+        // Skip things like `abc#1`, `&#39;` or `#12abc`
+        let standalone = (i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'&'))
+            && (end == bytes.len() || !(bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_'));
+
+        if end > start && standalone {
+            if let Ok(issue_id) = message[start..end].parse::<i64>() {
+                let preceding_word = message[..i]
+                    .trim_end()
+                    .trim_end_matches(':')
+                    .rsplit(|c: char| !c.is_alphanumeric())
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+
+                let kind = if CLOSING_KEYWORDS.contains(&preceding_word.as_str()) {
+                    IssueReferenceKind::Closes
+                } else {
+                    IssueReferenceKind::References
+                };
+
+                match references.iter_mut().find(|r| r.issue_id == issue_id) {
+                    Some(existing) if kind == IssueReferenceKind::Closes => existing.kind = kind,
+                    Some(_) => {}
+                    None => references.push(IssueReference { issue_id, kind }),
+                }
+            }
         }
+
+        i = end.max(i + 1);
+    }
+
+    references
+}
+
+/// Parse a stored timestamp, accepting both RFC 3339 and SQLite's `CURRENT_TIMESTAMP` format
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    if value.contains('T') {
+        Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+    } else {
+        // Handle SQLite datetime format
+        Ok(DateTime::parse_from_str(&format!("{} +0000", value), "%Y-%m-%d %H:%M:%S %z")?
+            .with_timezone(&Utc))
     }
 }
 
+/// A LIKE pattern (used with `ESCAPE '\'`) matching text that contains `text` literally
+fn contains_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Column list shared by every issue query; see `TaskDatabase::issue_from_row`
+const ISSUE_COLUMNS: &str =
+    "id, title, description, status, priority, assignee, created_at, updated_at, external_source, external_id, external_url, parent_id";
+
+/// Condition on a `blocks` link `d` and its source issue `b` for a blocker that still holds
+const OPEN_BLOCKER: &str =
+    "d.kind = 'blocks' AND b.deleted_at IS NULL AND b.status NOT IN ('resolved', 'closed')";
+
+/// Column list shared by every comment query; see `TaskDatabase::comment_from_row`
+const COMMENT_COLUMNS: &str =
+    "id, issue_id, author, body, created_at, updated_at, external_source, external_id, external_url, synced_at";
+
+/// Column list shared by every commit query; see `TaskDatabase::commit_from_row`
+const COMMIT_COLUMNS: &str =
+    "id, hash, author_name, author_email, commit_date, message, parent_hashes, files_changed, file_stats, insertions, deletions";
+
 pub struct TaskDatabase {
     #[allow(dead_code)]
     db: Database,
     conn: Connection,
+    /// Who changes made through this handle are recorded as, see `with_actor`
+    actor: String,
+    /// Held while an operation runs, so operations sharing the connection take turns
+    writes: tokio::sync::Mutex<()>,
 }
 
 impl TaskDatabase {
+    /// Open (or create) a database and apply any pending schema migrations
     pub async fn new(db_path: &str) -> Result<Self> {
-        let db = Builder::new_local(db_path).build().await?;
-        let conn = db.connect()?;
-        
-        let instance = Self { db, conn };
-        instance.init_schema().await?;
+        let instance = Self::open(db_path).await?;
+        instance.migrate().await?;
         Ok(instance)
     }
 
     pub async fn in_memory() -> Result<Self> {
-        let db = Builder::new_local(":memory:").build().await?;
-        let conn = db.connect()?;
-        
-        let instance = Self { db, conn };
-        instance.init_schema().await?;
+        let instance = Self::open(":memory:").await?;
+        instance.migrate().await?;
         Ok(instance)
     }
 
-    async fn init_schema(&self) -> Result<()> {
-        // Create commits table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS commits (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                hash TEXT UNIQUE NOT NULL,
-                author_name TEXT NOT NULL,
-                author_email TEXT NOT NULL,
-                commit_date DATETIME NOT NULL,
-                message TEXT NOT NULL,
-                files_changed TEXT NOT NULL, -- JSON array
-                insertions INTEGER DEFAULT 0,
-                deletions INTEGER DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    /// Open a database without touching its schema, e.g. to inspect pending migrations
+    pub async fn open(db_path: &str) -> Result<Self> {
+        let db = Builder::new_local(db_path).build().await?;
+        let conn = db.connect()?;
+        
+        let instance = Self { db, conn, actor: "atask".to_string(), writes: tokio::sync::Mutex::new(()) };
+        instance.conn.execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )",
             (),
         ).await?;
+        Ok(instance)
+    }
 
-        // Create labels table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS labels (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT UNIQUE NOT NULL,
-                color TEXT NOT NULL DEFAULT '#808080',
-                description TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            (),
-        ).await?;
+    /// Record changes made through this handle in the issue history as made by `actor`
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = actor.into();
+        self
+    }
 
-        // Create issues table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS issues (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                title TEXT NOT NULL,
-                description TEXT,
-                status TEXT NOT NULL DEFAULT 'open',
-                priority TEXT NOT NULL DEFAULT 'medium',
-                assignee TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            (),
-        ).await?;
+    /// Who changes are recorded as; `atask` unless set with `with_actor`
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
 
-        // Create issue_labels junction table
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS issue_labels (
-                issue_id INTEGER NOT NULL,
-                label_id INTEGER NOT NULL,
-                PRIMARY KEY (issue_id, label_id),
-                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE,
-                FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE
-            )",
-            (),
-        ).await?;
+    /// The highest migration version applied to this database (0 for an unversioned database)
+    pub async fn schema_version(&self) -> Result<i64> {
+        let mut rows = self.conn.query("SELECT COALESCE(MAX(version), 0) FROM schema_version", ()).await?;
+        if let Some(row) = rows.next().await? {
+            Ok(row.get(0)?)
+        } else {
+            Ok(0)
+        }
+    }
 
-        // Create indexes
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_commits_hash ON commits(hash)",
-            (),
-        ).await?;
+    /// Migrations that have not been applied yet, in the order they will run.
+    /// Fails if the database was created by a newer version of atask.
+    pub async fn pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+        let current = self.schema_version().await?;
+        let latest = migrations::latest_version();
+        if current > latest {
+            return Err(Error::Storage(format!(
+                "Database schema version {} is newer than this build of atask supports (version {}); \
+                 upgrade atask to open this database",
+                current,
+                latest
+            )));
+        }
+
+        Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+    }
+
+    /// Apply all pending migrations, each in its own transaction, and return the ones applied
+    pub async fn migrate(&self) -> Result<Vec<&'static Migration>> {
+        let pending = self.pending_migrations().await?;
+
+        for migration in &pending {
+            self.conn.execute("BEGIN", ()).await?;
+            match self.apply_migration(migration).await {
+                Ok(()) => {
+                    self.conn.execute("COMMIT", ()).await?;
+                }
+                Err(e) => {
+                    self.rollback().await;
+                    return Err(e.context(format!(
+                        "Failed to apply migration {} ({})",
+                        migration.version, migration.description
+                    )));
+                }
+            }
+        }
+
+        Ok(pending)
+    }
+
+    async fn apply_migration(&self, migration: &Migration) -> Result<()> {
+        for step in migration.steps {
+            match step {
+                MigrationStep::Sql(sql) => {
+                    self.conn.execute(sql, ()).await?;
+                }
+                MigrationStep::AddColumn { table, column, definition } => {
+                    self.add_column_if_missing(table, column, definition).await?;
+                }
+                MigrationStep::SeedDefaultBoard => {
+                    // Only the columns board_columns had when it was created
+                    for column in BoardDefinition::defaults().columns {
+                        self.conn.execute(
+                            "INSERT OR IGNORE INTO board_columns (id, title, color, label, status, position)
+                             VALUES (?, ?, ?, ?, ?, ?)",
+                            libsql::params![
+                                column.id,
+                                column.title,
+                                column.color,
+                                column.label,
+                                column.status.map(|s| s.to_string()),
+                                column.position
+                            ],
+                        ).await?;
+                    }
+                }
+            }
+        }
 
         self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_commits_date ON commits(commit_date)",
-            (),
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)",
+            libsql::params![migration.version, migration.description, Utc::now().to_rfc3339()],
         ).await?;
 
+        Ok(())
+    }
+
+    /// Add a column to an existing table unless it is already there
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut rows = self.conn.query(&format!("PRAGMA table_info({})", table), ()).await?;
+        while let Some(row) = rows.next().await? {
+            let name: String = row.get(1)?;
+            if name == column {
+                return Ok(());
+            }
+        }
+
         self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_issues_status ON issues(status)",
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
             (),
         ).await?;
 
@@ -210,17 +613,21 @@ impl TaskDatabase {
     // CRUD operations for commits
     pub async fn insert_commit(&self, commit: &GitCommit) -> Result<i64> {
         let files_json = serde_json::to_string(&commit.files_changed)?;
+        let parents_json = serde_json::to_string(&commit.parent_hashes)?;
+        let file_stats_json = serde_json::to_string(&commit.file_stats)?;
         
         self.conn.execute(
-            "INSERT INTO commits (hash, author_name, author_email, commit_date, message, files_changed, insertions, deletions)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO commits (hash, author_name, author_email, commit_date, message, parent_hashes, files_changed, file_stats, insertions, deletions)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             libsql::params![
                 commit.hash.clone(),
                 commit.author_name.clone(),
                 commit.author_email.clone(),
                 commit.commit_date.to_rfc3339(),
                 commit.message.clone(),
+                parents_json,
                 files_json,
+                file_stats_json,
                 commit.insertions,
                 commit.deletions
             ],
@@ -231,33 +638,18 @@ impl TaskDatabase {
         if let Some(row) = rows.next().await? {
             Ok(row.get(0)?)
         } else {
-            Err(anyhow::anyhow!("Failed to get last insert rowid"))
+            Err(Error::Storage("Failed to get last insert rowid".to_string()))
         }
     }
 
     pub async fn get_commit_by_hash(&self, hash: &str) -> Result<Option<GitCommit>> {
         let mut rows = self.conn.query(
-            "SELECT id, hash, author_name, author_email, commit_date, message, files_changed, insertions, deletions
-             FROM commits WHERE hash = ?",
+            &format!("SELECT {} FROM commits WHERE hash = ?", COMMIT_COLUMNS),
             libsql::params![hash],
         ).await?;
 
         if let Some(row) = rows.next().await? {
-            let files_json: String = row.get(6)?;
-            let files_changed: Vec<String> = serde_json::from_str(&files_json)?;
-            let commit_date: String = row.get(4)?;
-            
-            Ok(Some(GitCommit {
-                id: Some(row.get(0)?),
-                hash: row.get(1)?,
-                author_name: row.get(2)?,
-                author_email: row.get(3)?,
-                commit_date: DateTime::parse_from_rfc3339(&commit_date)?.with_timezone(&Utc),
-                message: row.get(5)?,
-                files_changed,
-                insertions: row.get(7)?,
-                deletions: row.get(8)?,
-            }))
+            Ok(Some(Self::commit_from_row(&row)?))
         } else {
             Ok(None)
         }
@@ -265,33 +657,95 @@ impl TaskDatabase {
 
     pub async fn get_all_commits(&self) -> Result<Vec<GitCommit>> {
         let mut rows = self.conn.query(
-            "SELECT id, hash, author_name, author_email, commit_date, message, files_changed, insertions, deletions
-             FROM commits ORDER BY commit_date DESC",
+            &format!("SELECT {} FROM commits ORDER BY commit_date DESC", COMMIT_COLUMNS),
             (),
         ).await?;
 
         let mut commits = Vec::new();
         while let Some(row) = rows.next().await? {
-            let files_json: String = row.get(6)?;
-            let files_changed: Vec<String> = serde_json::from_str(&files_json)?;
-            let commit_date: String = row.get(4)?;
-            
-            commits.push(GitCommit {
-                id: Some(row.get(0)?),
-                hash: row.get(1)?,
-                author_name: row.get(2)?,
-                author_email: row.get(3)?,
-                commit_date: DateTime::parse_from_rfc3339(&commit_date)?.with_timezone(&Utc),
-                message: row.get(5)?,
-                files_changed,
-                insertions: row.get(7)?,
-                deletions: row.get(8)?,
-            });
+            commits.push(Self::commit_from_row(&row)?);
         }
 
         Ok(commits)
     }
 
+    /// Commits matching every set field of `filter`, newest first
+    pub async fn get_commits(&self, filter: &CommitFilter) -> Result<Vec<GitCommit>> {
+        let mut conditions = Vec::new();
+        let mut params: Vec<libsql::Value> = Vec::new();
+
+        if let Some(author) = &filter.author {
+            conditions.push("(author_name LIKE ? ESCAPE '\\' OR author_email LIKE ? ESCAPE '\\')");
+            let pattern = contains_pattern(author);
+            params.push(pattern.clone().into());
+            params.push(pattern.into());
+        }
+        if let Some(since) = filter.since {
+            conditions.push("datetime(commit_date) >= datetime(?)");
+            params.push(since.to_rfc3339().into());
+        }
+        if let Some(until) = filter.until {
+            conditions.push("datetime(commit_date) <= datetime(?)");
+            params.push(until.to_rfc3339().into());
+        }
+        if let Some(path) = &filter.path {
+            let path = path.trim_end_matches('/');
+            conditions.push(
+                "EXISTS (SELECT 1 FROM json_each(commits.files_changed) AS f
+                         WHERE f.value = ? OR substr(f.value, 1, ?) = ?)",
+            );
+            let directory = format!("{}/", path);
+            params.push(path.to_string().into());
+            params.push((directory.len() as i64).into());
+            params.push(directory.into());
+        }
+        if let Some(issue_id) = filter.issue {
+            conditions.push("hash IN (SELECT commit_hash FROM commit_issues WHERE issue_id = ?)");
+            params.push(issue_id.into());
+        }
+
+        let mut sql = format!("SELECT {} FROM commits", COMMIT_COLUMNS);
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY commit_date DESC");
+        if let Some(limit) = filter.limit {
+            sql.push_str(" LIMIT ?");
+            params.push((limit as i64).into());
+        }
+
+        let mut rows = self.conn.query(&sql, libsql::params_from_iter(params)).await?;
+        let mut commits = Vec::new();
+        while let Some(row) = rows.next().await? {
+            commits.push(Self::commit_from_row(&row)?);
+        }
+
+        Ok(commits)
+    }
+
+    /// Build a GitCommit from a row selected with `COMMIT_COLUMNS`
+    fn commit_from_row(row: &libsql::Row) -> Result<GitCommit> {
+        let commit_date: String = row.get(4)?;
+        let parents_json: String = row.get(6)?;
+        let files_json: String = row.get(7)?;
+        let file_stats_json: String = row.get(8)?;
+
+        Ok(GitCommit {
+            id: Some(row.get(0)?),
+            hash: row.get(1)?,
+            author_name: row.get(2)?,
+            author_email: row.get(3)?,
+            commit_date: DateTime::parse_from_rfc3339(&commit_date)?.with_timezone(&Utc),
+            message: row.get(5)?,
+            parent_hashes: serde_json::from_str(&parents_json)?,
+            files_changed: serde_json::from_str(&files_json)?,
+            file_stats: serde_json::from_str(&file_stats_json)?,
+            insertions: row.get(9)?,
+            deletions: row.get(10)?,
+        })
+    }
+
     // CRUD operations for labels
     pub async fn insert_label(&self, label: &Label) -> Result<i64> {
         self.conn.execute(
@@ -304,7 +758,7 @@ impl TaskDatabase {
         if let Some(row) = rows.next().await? {
             Ok(row.get(0)?)
         } else {
-            Err(anyhow::anyhow!("Failed to get last insert rowid"))
+            Err(Error::Storage("Failed to get last insert rowid".to_string()))
         }
     }
 
@@ -365,136 +819,534 @@ impl TaskDatabase {
         Ok(labels)
     }
 
-    // CRUD operations for issues
-    pub async fn insert_issue(&self, issue: &Issue) -> Result<i64> {
-        self.conn.execute(
-            "INSERT INTO issues (title, description, status, priority, assignee, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?)",
-            libsql::params![
-                issue.title.clone(),
-                issue.description.clone(),
-                issue.status.to_string(),
-                issue.priority.to_string(),
-                issue.assignee.clone(),
-                issue.updated_at.to_rfc3339()
-            ],
-        ).await?;
+    /// Change a label's name, color and description.
+    /// Issues keep the label under its new name, and a rename shows up in the history
+    /// of each of them, as one operation.
+    pub async fn update_label(&self, label: &Label) -> Result<()> {
+        let id = label.id.ok_or_else(|| Error::Validation("Cannot update a label without an id".to_string()))?;
 
-        // Get the last insert rowid
-        let mut rows = self.conn.query("SELECT last_insert_rowid()", ()).await?;
-        let issue_id: i64 = if let Some(row) = rows.next().await? {
-            row.get(0)?
-        } else {
-            return Err(anyhow::anyhow!("Failed to get last insert rowid"));
-        };
+        self.within_operation(async {
+            let mut rows = self.conn.query("SELECT name FROM labels WHERE id = ?", libsql::params![id]).await?;
+            let old_name: Option<String> = match rows.next().await? {
+                Some(row) => Some(row.get(0)?),
+                None => None,
+            };
+            let renamed = old_name.filter(|old| *old != label.name);
+            let affected = match &renamed {
+                Some(old) => self.get_issue_ids_with_label(old).await?,
+                None => Vec::new(),
+            };
 
-        // Insert label associations
-        for label_name in &issue.labels {
-            if let Some(label) = self.get_label_by_name(label_name).await? {
-                if let Some(label_id) = label.id {
-                    self.conn.execute(
-                        "INSERT OR IGNORE INTO issue_labels (issue_id, label_id) VALUES (?, ?)",
-                        libsql::params![issue_id, label_id],
-                    ).await?;
-                }
+            self.conn.execute(
+                "UPDATE labels SET name = ?, color = ?, description = ? WHERE id = ?",
+                libsql::params![label.name.clone(), label.color.clone(), label.description.clone(), id],
+            ).await?;
+
+            for issue_id in affected {
+                self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Label, renamed.clone(), Some(label.name.clone()))]).await?;
+                self.touch_issue(issue_id).await?;
             }
-        }
+            Ok(())
+        }).await
+    }
 
-        Ok(issue_id)
+    /// Delete a label and detach it from every issue, recording the removal in each
+    /// one's history as one operation. Returns false if no label has this name.
+    pub async fn delete_label(&self, name: &str) -> Result<bool> {
+        self.within_operation(async {
+            let affected = self.get_issue_ids_with_label(name).await?;
+            self.conn.execute(
+                "DELETE FROM issue_labels WHERE label_id IN (SELECT id FROM labels WHERE name = ?)",
+                libsql::params![name],
+            ).await?;
+            let deleted = self.conn.execute(
+                "DELETE FROM labels WHERE name = ?",
+                libsql::params![name],
+            ).await?;
+
+            for issue_id in affected {
+                self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Label, Some(name.to_string()), None)]).await?;
+                self.touch_issue(issue_id).await?;
+            }
+            Ok(deleted > 0)
+        }).await
     }
 
-    pub async fn get_issue_by_id(&self, id: i64) -> Result<Option<Issue>> {
+    /// Ids of the issues (outside the trash) that carry a label
+    pub async fn get_issue_ids_with_label(&self, name: &str) -> Result<Vec<i64>> {
         let mut rows = self.conn.query(
-            "SELECT id, title, description, status, priority, assignee, created_at, updated_at
-             FROM issues WHERE id = ?",
-            libsql::params![id],
+            "SELECT il.issue_id FROM issue_labels il
+             JOIN labels l ON l.id = il.label_id
+             JOIN issues i ON i.id = il.issue_id
+             WHERE l.name = ? AND i.deleted_at IS NULL
+             ORDER BY il.issue_id",
+            libsql::params![name],
         ).await?;
 
-        if let Some(row) = rows.next().await? {
-            let created_at: String = row.get(6)?;
-            let updated_at: String = row.get(7)?;
-            
-            let parsed_created_at = if created_at.contains('T') {
-                DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc)
-            } else {
-                // Handle SQLite datetime format
-                DateTime::parse_from_str(&format!("{} +0000", created_at), "%Y-%m-%d %H:%M:%S %z")?
-                    .with_timezone(&Utc)
-            };
-            
-            let parsed_updated_at = if updated_at.contains('T') {
-                DateTime::parse_from_rfc3339(&updated_at)?.with_timezone(&Utc)
-            } else {
-                // Handle SQLite datetime format
-                DateTime::parse_from_str(&format!("{} +0000", updated_at), "%Y-%m-%d %H:%M:%S %z")?
-                    .with_timezone(&Utc)
-            };
-            
-            // Get labels for this issue
-            let labels = self.get_issue_labels(id).await?;
-            
-            Ok(Some(Issue {
-                id: Some(row.get(0)?),
-                title: row.get(1)?,
-                description: row.get(2)?,
-                status: row.get::<String>(3)?.parse()?,
-                priority: row.get::<String>(4)?.parse()?,
-                assignee: row.get(5)?,
-                created_at: parsed_created_at,
-                updated_at: parsed_updated_at,
-                labels,
-            }))
-        } else {
-            Ok(None)
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get(0)?);
         }
+
+        Ok(ids)
     }
 
-    pub async fn get_all_issues(&self) -> Result<Vec<Issue>> {
+    // Board definition
+
+    /// The board's columns in display order
+    pub async fn get_board_columns(&self) -> Result<Vec<BoardColumn>> {
         let mut rows = self.conn.query(
-            "SELECT id, title, description, status, priority, assignee, created_at, updated_at
-             FROM issues ORDER BY created_at DESC",
+            "SELECT id, title, color, label, status, position, wip_limit FROM board_columns ORDER BY position, id",
             (),
         ).await?;
 
-        let mut issues = Vec::new();
+        let mut columns = Vec::new();
         while let Some(row) = rows.next().await? {
-            let issue_id: i64 = row.get(0)?;
-            let created_at: String = row.get(6)?;
-            let updated_at: String = row.get(7)?;
-            
-            let parsed_created_at = if created_at.contains('T') {
-                DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc)
-            } else {
-                DateTime::parse_from_str(&format!("{} +0000", created_at), "%Y-%m-%d %H:%M:%S %z")?
-                    .with_timezone(&Utc)
-            };
-            
-            let parsed_updated_at = if updated_at.contains('T') {
-                DateTime::parse_from_rfc3339(&updated_at)?.with_timezone(&Utc)
-            } else {
-                DateTime::parse_from_str(&format!("{} +0000", updated_at), "%Y-%m-%d %H:%M:%S %z")?
-                    .with_timezone(&Utc)
-            };
-            
-            // Get labels for this issue
-            let labels = self.get_issue_labels(issue_id).await?;
-            
-            issues.push(Issue {
-                id: Some(issue_id),
+            let status: Option<String> = row.get(4)?;
+            columns.push(BoardColumn {
+                id: row.get(0)?,
                 title: row.get(1)?,
-                description: row.get(2)?,
-                status: row.get::<String>(3)?.parse()?,
-                priority: row.get::<String>(4)?.parse()?,
-                assignee: row.get(5)?,
-                created_at: parsed_created_at,
-                updated_at: parsed_updated_at,
-                labels,
+                color: row.get(2)?,
+                label: row.get(3)?,
+                status: status.map(|s| s.parse()).transpose()?,
+                position: row.get(5)?,
+                wip_limit: row.get(6)?,
+            });
+        }
+
+        Ok(columns)
+    }
+
+    /// How many issues (outside the trash) `board` places in the column `column_id`,
+    /// by the same rules as `BoardDefinition::column_for`
+    pub async fn count_issues_in_column(&self, board: &BoardDefinition, column_id: &str) -> Result<usize> {
+        let Some(index) = board.columns.iter().position(|column| column.id == column_id) else {
+            return Ok(0);
+        };
+        let column = &board.columns[index];
+        let mut params: Vec<libsql::Value> = Vec::new();
+        let mut carries = |label: &str, carried: bool| {
+            params.push(label.to_string().into());
+            format!(
+                "{}EXISTS (SELECT 1 FROM issue_labels il JOIN labels l ON l.id = il.label_id
+                           WHERE il.issue_id = issues.id AND l.name = ? COLLATE NOCASE)",
+                if carried { "" } else { "NOT " }
+            )
+        };
+
+        // Open issues go by the last workflow label they carry...
+        let mut placed = Vec::new();
+        if let Some(label) = &column.label {
+            let mut by_label = vec![carries(label, true)];
+            for later in board.columns[index + 1..].iter().filter_map(|c| c.label.as_deref()) {
+                by_label.push(carries(later, false));
+            }
+            placed.push(by_label.join(" AND "));
+        }
+        // ...or, without one, by their status, falling back to the first column
+        let first_for = |status: &IssueStatus| board.columns.iter()
+            .find(|c| c.status.as_ref() == Some(status))
+            .is_some_and(|c| c.id == column.id);
+        let mut by_status = Vec::new();
+        if let Some(status) = column.status.as_ref().filter(|status| first_for(status)) {
+            by_status.push(format!("status = '{}'", status));
+        }
+        if index == 0 {
+            let claimed: Vec<String> = board.columns.iter()
+                .filter_map(|c| c.status.as_ref().map(|status| format!("'{}'", status)))
+                .collect();
+            by_status.push(match claimed.is_empty() {
+                true => "1".to_string(),
+                false => format!("status NOT IN ({})", claimed.join(", ")),
+            });
+        }
+        if !by_status.is_empty() {
+            let mut unlabeled: Vec<String> = board.workflow_labels().into_iter()
+                .map(|label| carries(label, false))
+                .collect();
+            unlabeled.push(format!("({})", by_status.join(" OR ")));
+            placed.push(unlabeled.join(" AND "));
+        }
+
+        let mut conditions = Vec::new();
+        if !placed.is_empty() {
+            conditions.push(format!("(status != 'closed' AND (({})))", placed.join(") OR (")));
+        }
+        // Closed issues only show up in the first column for the closed status
+        if first_for(&IssueStatus::Closed) {
+            conditions.push("status = 'closed'".to_string());
+        }
+        if conditions.is_empty() {
+            return Ok(0);
+        }
+
+        let mut rows = self.conn.query(
+            &format!("SELECT COUNT(*) FROM issues WHERE deleted_at IS NULL AND ({})", conditions.join(" OR ")),
+            params,
+        ).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)? as usize),
+            None => Ok(0),
+        }
+    }
+
+    /// Add a board column, or replace the one with the same id
+    pub async fn save_board_column(&self, column: &BoardColumn) -> Result<()> {
+        if column.id.trim().is_empty() {
+            return Err(Error::Validation("Board column id cannot be empty".to_string()));
+        }
+        if column.wip_limit == Some(0) {
+            return Err(Error::Validation("WIP limit must be at least 1".to_string()));
+        }
+
+        self.conn.execute(
+            "INSERT INTO board_columns (id, title, color, label, status, position, wip_limit) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET title = excluded.title, color = excluded.color,
+                label = excluded.label, status = excluded.status, position = excluded.position,
+                wip_limit = excluded.wip_limit",
+            libsql::params![
+                column.id.clone(),
+                column.title.clone(),
+                column.color.clone(),
+                column.label.clone(),
+                column.status.as_ref().map(|s| s.to_string()),
+                column.position,
+                column.wip_limit
+            ],
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Remove a board column; returns false if there was no such column
+    pub async fn delete_board_column(&self, id: &str) -> Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM board_columns WHERE id = ?",
+            libsql::params![id],
+        ).await?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Manual card ranks in every column, leaving out issues in the trash
+    pub async fn get_card_ranks(&self) -> Result<Vec<CardRank>> {
+        let mut rows = self.conn.query(
+            "SELECT issue_id, column_id, rank FROM card_ranks
+             WHERE issue_id IN (SELECT id FROM issues WHERE deleted_at IS NULL)
+             ORDER BY column_id, rank",
+            (),
+        ).await?;
+
+        let mut ranks = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ranks.push(CardRank {
+                issue_id: row.get(0)?,
+                column_id: row.get(1)?,
+                rank: row.get(2)?,
             });
         }
 
+        Ok(ranks)
+    }
+
+    /// Place an issue's card at `rank` in a column, replacing any rank it had in another column
+    pub async fn set_card_rank(&self, issue_id: i64, column_id: &str, rank: &str) -> Result<()> {
+        let old = self.get_card_place(issue_id).await?;
+        let new = rank::place(column_id, rank);
+        if old.as_ref() == Some(&new) {
+            return Ok(());
+        }
+        self.conn.execute(
+            "INSERT OR REPLACE INTO card_ranks (issue_id, column_id, rank) VALUES (?, ?, ?)",
+            libsql::params![issue_id, column_id, rank],
+        ).await?;
+
+        self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Rank, old, Some(new))]).await
+    }
+
+    /// Take an issue's card out of the order, so it sorts after the ranked cards again
+    async fn clear_card_rank(&self, issue_id: i64) -> Result<()> {
+        let Some(old) = self.get_card_place(issue_id).await? else {
+            return Ok(());
+        };
+        self.conn.execute("DELETE FROM card_ranks WHERE issue_id = ?", libsql::params![issue_id]).await?;
+
+        self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Rank, Some(old), None)]).await
+    }
+
+    /// An issue's card rank as a `rank::place`, if it has one
+    async fn get_card_place(&self, issue_id: i64) -> Result<Option<String>> {
+        let mut rows = self.conn.query(
+            "SELECT column_id, rank FROM card_ranks WHERE issue_id = ?",
+            libsql::params![issue_id],
+        ).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(rank::place(&row.get::<String>(0)?, &row.get::<String>(1)?))),
+            None => Ok(None),
+        }
+    }
+
+    // CRUD operations for issues
+    pub async fn insert_issue(&self, issue: &Issue) -> Result<i64> {
+        self.insert_issue_by(issue, &self.actor).await
+    }
+
+    /// Insert an issue, recording `actor` as its creator in the issue history
+    pub async fn insert_issue_by(&self, issue: &Issue, actor: &str) -> Result<i64> {
+        self.check_parent(None, issue.parent_id).await?;
+        let external = issue.external_ref.as_ref();
+        self.conn.execute(
+            "INSERT INTO issues (title, description, status, priority, assignee, created_at, updated_at, external_source, external_id, external_url, parent_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            libsql::params![
+                issue.title.clone(),
+                issue.description.clone(),
+                issue.status.to_string(),
+                issue.priority.to_string(),
+                issue.assignee.clone(),
+                issue.created_at.to_rfc3339(),
+                issue.updated_at.to_rfc3339(),
+                external.map(|e| e.source.clone()),
+                external.map(|e| e.id.clone()),
+                external.and_then(|e| e.url.clone()),
+                issue.parent_id
+            ],
+        ).await?;
+
+        // Get the last insert rowid
+        let mut rows = self.conn.query("SELECT last_insert_rowid()", ()).await?;
+        let issue_id: i64 = if let Some(row) = rows.next().await? {
+            row.get(0)?
+        } else {
+            return Err(Error::Storage("Failed to get last insert rowid".to_string()));
+        };
+
+        // Insert label associations
+        self.link_issue_labels(issue_id, &issue.labels).await?;
+        self.record_changes(issue_id, actor, &[Change::new(EventField::Created, None, Some(issue.title.clone()))]).await?;
+
+        Ok(issue_id)
+    }
+
+    /// Associate existing labels with an issue, silently skipping unknown label names
+    async fn link_issue_labels(&self, issue_id: i64, labels: &[String]) -> Result<()> {
+        for label_name in labels {
+            if let Some(label) = self.get_label_by_name(label_name).await? {
+                if let Some(label_id) = label.id {
+                    self.conn.execute(
+                        "INSERT OR IGNORE INTO issue_labels (issue_id, label_id) VALUES (?, ?)",
+                        libsql::params![issue_id, label_id],
+                    ).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_issue_by_id(&self, id: i64) -> Result<Option<Issue>> {
+        let mut rows = self.conn.query(
+            &format!("SELECT {} FROM issues WHERE id = ? AND deleted_at IS NULL", ISSUE_COLUMNS),
+            libsql::params![id],
+        ).await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(Some(self.issue_from_row(&row).await?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Find the local copy of an issue from an external tracker, e.g. `("github", "42")`.
+    /// A copy in the trash is not returned; see `is_external_issue_trashed`.
+    pub async fn get_issue_by_external_ref(&self, source: &str, external_id: &str) -> Result<Option<Issue>> {
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT {} FROM issues WHERE external_source = ? AND external_id = ? AND deleted_at IS NULL",
+                ISSUE_COLUMNS
+            ),
+            libsql::params![source, external_id],
+        ).await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(Some(self.issue_from_row(&row).await?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_all_issues(&self) -> Result<Vec<Issue>> {
+        let mut rows = self.conn.query(
+            &format!("SELECT {} FROM issues WHERE deleted_at IS NULL ORDER BY created_at DESC", ISSUE_COLUMNS),
+            (),
+        ).await?;
+
+        let mut issues = Vec::new();
+        while let Some(row) = rows.next().await? {
+            issues.push(self.issue_from_row(&row).await?);
+        }
+
+        Ok(issues)
+    }
+
+    /// Issues matching every term of `query`, newest first
+    pub async fn query_issues(&self, query: &Query) -> Result<Vec<Issue>> {
+        let (conditions, params) = Self::query_conditions(query);
+        let mut sql = format!("SELECT {} FROM issues WHERE deleted_at IS NULL", ISSUE_COLUMNS);
+        for condition in &conditions {
+            sql.push_str(" AND ");
+            sql.push_str(condition);
+        }
+        sql.push_str(" ORDER BY created_at DESC");
+
+        let mut rows = self.conn.query(&sql, libsql::params_from_iter(params)).await?;
+        let mut issues = Vec::new();
+        while let Some(row) = rows.next().await? {
+            issues.push(self.issue_from_row(&row).await?);
+        }
+
         Ok(issues)
     }
 
+    /// One SQL condition per query term, with the values for its placeholders
+    fn query_conditions(query: &Query) -> (Vec<String>, Vec<libsql::Value>) {
+        let mut conditions = Vec::new();
+        let mut params: Vec<libsql::Value> = Vec::new();
+
+        for term in &query.terms {
+            let condition = match &term.filter {
+                Filter::Label(label) => {
+                    params.push(label.clone().into());
+                    "EXISTS (SELECT 1 FROM issue_labels il JOIN labels l ON l.id = il.label_id
+                             WHERE il.issue_id = issues.id AND l.name = ? COLLATE NOCASE)".to_string()
+                }
+                Filter::Status(status) => {
+                    params.push(status.to_string().into());
+                    "status = ?".to_string()
+                }
+                Filter::Assignee(assignee) => {
+                    params.push(assignee.clone().into());
+                    "COALESCE(assignee, '') = ? COLLATE NOCASE".to_string()
+                }
+                Filter::Priority(comparison, priority) => {
+                    let matching: Vec<IssuePriority> = [IssuePriority::Low, IssuePriority::Medium, IssuePriority::High, IssuePriority::Critical]
+                        .into_iter()
+                        .filter(|p| comparison.holds(p, priority))
+                        .collect();
+                    if matching.is_empty() {
+                        "0".to_string()
+                    } else {
+                        let placeholders = vec!["?"; matching.len()].join(", ");
+                        params.extend(matching.iter().map(|p| p.to_string().into()));
+                        format!("priority IN ({})", placeholders)
+                    }
+                }
+                Filter::Created(comparison, date) => {
+                    params.push(date.to_string().into());
+                    format!("date(created_at) {} ?", comparison.operator())
+                }
+                Filter::Updated(comparison, date) => {
+                    params.push(date.to_string().into());
+                    format!("date(updated_at) {} ?", comparison.operator())
+                }
+                Filter::Text(text) => {
+                    // LIKE is case-insensitive for ASCII
+                    let pattern = contains_pattern(text);
+                    params.push(pattern.clone().into());
+                    params.push(pattern.into());
+                    "(title LIKE ? ESCAPE '\\' OR COALESCE(description, '') LIKE ? ESCAPE '\\')".to_string()
+                }
+            };
+            conditions.push(if term.negated { format!("NOT ({})", condition) } else { condition });
+        }
+
+        (conditions, params)
+    }
+
+    /// Issues and commits matching every word of `terms`, best matches first; at most `limit` of each
+    pub async fn search(&self, terms: &str, limit: usize) -> Result<SearchResults> {
+        let expression = search::match_expression(terms)
+            .ok_or_else(|| Error::Validation("Search terms cannot be empty".to_string()))?;
+        let qualified = |columns: &str, table: &str| columns
+            .split(", ")
+            .map(|column| format!("{}.{}", table, column))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // bm25() is lower for better matches; matches in titles and messages count for more
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT {}, snippet(issues_fts, -1, char(2), char(3), '…', 16), bm25(issues_fts, 4.0, 1.0) AS score
+                 FROM issues_fts JOIN issues i ON i.id = issues_fts.rowid
+                 WHERE issues_fts MATCH ? AND i.deleted_at IS NULL
+                 ORDER BY score LIMIT ?",
+                qualified(ISSUE_COLUMNS, "i")
+            ),
+            libsql::params![expression.clone(), limit as i64],
+        ).await?;
+        let mut results = SearchResults::default();
+        while let Some(row) = rows.next().await? {
+            let score: f64 = row.get(13)?;
+            results.issues.push(IssueHit {
+                issue: self.issue_from_row(&row).await?,
+                snippet: Snippet(row.get(12)?),
+                score: -score,
+            });
+        }
+
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT {}, snippet(commits_fts, -1, char(2), char(3), '…', 16), bm25(commits_fts, 2.0, 1.0) AS score
+                 FROM commits_fts JOIN commits c ON c.id = commits_fts.rowid
+                 WHERE commits_fts MATCH ?
+                 ORDER BY score LIMIT ?",
+                qualified(COMMIT_COLUMNS, "c")
+            ),
+            libsql::params![expression, limit as i64],
+        ).await?;
+        while let Some(row) = rows.next().await? {
+            let score: f64 = row.get(12)?;
+            results.commits.push(CommitHit {
+                commit: Self::commit_from_row(&row)?,
+                snippet: Snippet(row.get(11)?),
+                score: -score,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Build an Issue (including its labels) from a row selected with `ISSUE_COLUMNS`
+    async fn issue_from_row(&self, row: &libsql::Row) -> Result<Issue> {
+        let issue_id: i64 = row.get(0)?;
+        let created_at: String = row.get(6)?;
+        let updated_at: String = row.get(7)?;
+        let external_source: Option<String> = row.get(8)?;
+        let external_id: Option<String> = row.get(9)?;
+
+        let external_ref = match (external_source, external_id) {
+            (Some(source), Some(id)) => Some(ExternalRef { source, id, url: row.get(10)? }),
+            _ => None,
+        };
+
+        // Get labels for this issue
+        let labels = self.get_issue_labels(issue_id).await?;
+
+        Ok(Issue {
+            id: Some(issue_id),
+            title: row.get(1)?,
+            description: row.get(2)?,
+            status: row.get::<String>(3)?.parse()?,
+            priority: row.get::<String>(4)?.parse()?,
+            assignee: row.get(5)?,
+            created_at: parse_timestamp(&created_at)?,
+            updated_at: parse_timestamp(&updated_at)?,
+            labels,
+            external_ref,
+            parent_id: row.get(11)?,
+        })
+    }
+
     async fn get_issue_labels(&self, issue_id: i64) -> Result<Vec<String>> {
         let mut rows = self.conn.query(
             "SELECT l.name FROM labels l 
@@ -503,138 +1355,1181 @@ impl TaskDatabase {
             libsql::params![issue_id],
         ).await?;
 
-        let mut labels = Vec::new();
-        while let Some(row) = rows.next().await? {
-            labels.push(row.get(0)?);
+        let mut labels = Vec::new();
+        while let Some(row) = rows.next().await? {
+            labels.push(row.get(0)?);
+        }
+
+        Ok(labels)
+    }
+
+    pub async fn update_issue_status(&self, id: i64, status: IssueStatus) -> Result<()> {
+        self.update_issue_status_by(id, status, &self.actor).await
+    }
+
+    async fn update_issue_status_by(&self, id: i64, status: IssueStatus, actor: &str) -> Result<()> {
+        let old = match self.get_issue_by_id(id).await? {
+            Some(issue) => issue.status,
+            None => return Ok(()),
+        };
+        self.conn.execute(
+            "UPDATE issues SET status = ?, updated_at = ? WHERE id = ?",
+            libsql::params![status.to_string(), Utc::now().to_rfc3339(), id],
+        ).await?;
+
+        if old != status {
+            self.record_changes(id, actor, &[Change::new(EventField::Status, Some(old.to_string()), Some(status.to_string()))]).await?;
+        }
+        Ok(())
+    }
+
+    /// Update an existing issue's title, description, status, priority, assignee and labels.
+    /// The issue's label set is replaced with `issue.labels`.
+    pub async fn update_issue(&self, issue: &Issue) -> Result<()> {
+        self.update_issue_by(issue, &self.actor).await
+    }
+
+    /// Update an issue like `update_issue`, recording the changes as made by `actor`,
+    /// e.g. `github` for changes pulled from there
+    pub async fn update_issue_by(&self, issue: &Issue, actor: &str) -> Result<()> {
+        let id = issue.id.ok_or_else(|| Error::Validation("Cannot update an issue without an id".to_string()))?;
+        let old = self.get_issue_by_id(id).await?;
+        if old.as_ref().is_some_and(|old| old.parent_id != issue.parent_id) {
+            self.check_parent(Some(id), issue.parent_id).await?;
+        }
+
+        self.conn.execute(
+            "UPDATE issues SET title = ?, description = ?, status = ?, priority = ?, assignee = ?, updated_at = ?,
+                external_source = ?, external_id = ?, external_url = ?, parent_id = ?
+             WHERE id = ?",
+            libsql::params![
+                issue.title.clone(),
+                issue.description.clone(),
+                issue.status.to_string(),
+                issue.priority.to_string(),
+                issue.assignee.clone(),
+                Utc::now().to_rfc3339(),
+                issue.external_ref.as_ref().map(|e| e.source.clone()),
+                issue.external_ref.as_ref().map(|e| e.id.clone()),
+                issue.external_ref.as_ref().and_then(|e| e.url.clone()),
+                issue.parent_id,
+                id
+            ],
+        ).await?;
+
+        self.conn.execute(
+            "DELETE FROM issue_labels WHERE issue_id = ?",
+            libsql::params![id],
+        ).await?;
+        self.link_issue_labels(id, &issue.labels).await?;
+
+        // Compare with what was stored, as unknown labels are skipped
+        if let (Some(old), Some(new)) = (old, self.get_issue_by_id(id).await?) {
+            self.record_changes(id, actor, &events::issue_changes(&old, &new)).await?;
+        }
+        Ok(())
+    }
+
+    /// Attach a label to an issue. Fails if the label does not exist.
+    pub async fn add_label_to_issue(&self, issue_id: i64, label_name: &str) -> Result<()> {
+        let label = self.get_label_by_name(label_name).await?
+            .ok_or_else(|| Error::NotFound(format!("Label '{}' does not exist", label_name)))?;
+
+        let added = self.conn.execute(
+            "INSERT OR IGNORE INTO issue_labels (issue_id, label_id) VALUES (?, ?)",
+            libsql::params![issue_id, label.id],
+        ).await?;
+        if added > 0 {
+            self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Label, None, Some(label.name))]).await?;
+        }
+        self.touch_issue(issue_id).await
+    }
+
+    /// Detach a label from an issue. Removing a label the issue doesn't carry is a no-op.
+    pub async fn remove_label_from_issue(&self, issue_id: i64, label_name: &str) -> Result<()> {
+        let removed = self.conn.execute(
+            "DELETE FROM issue_labels
+             WHERE issue_id = ? AND label_id IN (SELECT id FROM labels WHERE name = ?)",
+            libsql::params![issue_id, label_name],
+        ).await?;
+        if removed > 0 {
+            self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Label, Some(label_name.to_string()), None)]).await?;
+        }
+        self.touch_issue(issue_id).await
+    }
+
+    async fn touch_issue(&self, id: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE issues SET updated_at = ? WHERE id = ?",
+            libsql::params![Utc::now().to_rfc3339(), id],
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Move an issue to the trash. It disappears from every listing until restored
+    /// with `restore_issue` or removed for good with `purge_trash`.
+    pub async fn delete_issue(&self, id: i64) -> Result<()> {
+        let title = self.get_issue_by_id(id).await?.map(|issue| issue.title);
+        self.conn.execute(
+            "UPDATE issues SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+            libsql::params![Utc::now().to_rfc3339(), id],
+        ).await?;
+
+        if title.is_some() {
+            self.record_changes(id, &self.actor, &[Change::new(EventField::Deleted, title, None)]).await?;
+        }
+        Ok(())
+    }
+
+    /// Take an issue out of the trash. Returns false if it is not in the trash.
+    pub async fn restore_issue(&self, id: i64) -> Result<bool> {
+        let restored = self.conn.execute(
+            "UPDATE issues SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+            libsql::params![id],
+        ).await?;
+        if restored == 0 {
+            return Ok(false);
+        }
+
+        let title = self.get_issue_by_id(id).await?.map(|issue| issue.title);
+        self.record_changes(id, &self.actor, &[Change::new(EventField::Restored, None, title)]).await?;
+        Ok(true)
+    }
+
+    /// Issues in the trash, most recently deleted first
+    pub async fn get_trashed_issues(&self) -> Result<Vec<TrashedIssue>> {
+        let mut rows = self.conn.query(
+            &format!("SELECT {}, deleted_at FROM issues WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id DESC", ISSUE_COLUMNS),
+            (),
+        ).await?;
+
+        let mut trashed = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let deleted_at: String = row.get(12)?;
+            trashed.push(TrashedIssue {
+                issue: self.issue_from_row(&row).await?,
+                deleted_at: parse_timestamp(&deleted_at)?,
+            });
+        }
+
+        Ok(trashed)
+    }
+
+    /// Whether the local copy of an external issue is in the trash, so it shouldn't be imported again
+    pub async fn is_external_issue_trashed(&self, source: &str, external_id: &str) -> Result<bool> {
+        let mut rows = self.conn.query(
+            "SELECT 1 FROM issues WHERE external_source = ? AND external_id = ? AND deleted_at IS NOT NULL",
+            libsql::params![source, external_id],
+        ).await?;
+
+        Ok(rows.next().await?.is_some())
+    }
+
+    /// Delete issues in the trash for good, with their comments, links, card ranks and sync state;
+    /// their sub-tasks are left without a parent and their history is kept. With `ids`, only those issues are purged, otherwise the whole
+    /// trash is emptied, including deleted comments. Returns the number of issues purged.
+    pub async fn purge_trash(&self, ids: Option<&[i64]>) -> Result<usize> {
+        let trashed = self.get_trashed_issues().await?;
+        if let Some(ids) = ids {
+            if let Some(id) = ids.iter().find(|id| !trashed.iter().any(|t| t.issue.id == Some(**id))) {
+                return Err(Error::NotFound(format!("Issue #{} is not in the trash", id)));
+            }
+        }
+
+        let mut purged = 0;
+        for trashed_issue in &trashed {
+            let id = trashed_issue.issue.id.storage_context("Stored issue has no id")?;
+            if ids.is_some_and(|ids| !ids.contains(&id)) {
+                continue;
+            }
+            for table in ["issue_sync_state", "card_ranks", "comments", "issue_labels", "commit_issues"] {
+                self.conn.execute(
+                    &format!("DELETE FROM {} WHERE issue_id = ?", table),
+                    libsql::params![id],
+                ).await?;
+            }
+            self.conn.execute(
+                "DELETE FROM issue_dependencies WHERE source_id = ?1 OR target_id = ?1",
+                libsql::params![id],
+            ).await?;
+            self.conn.execute("UPDATE issues SET parent_id = NULL WHERE parent_id = ?", libsql::params![id]).await?;
+            self.conn.execute("DELETE FROM issues WHERE id = ?", libsql::params![id]).await?;
+            self.record_changes(id, &self.actor, &[Change::new(EventField::Purged, Some(trashed_issue.issue.title.clone()), None)]).await?;
+            purged += 1;
+        }
+
+        if ids.is_none() {
+            self.conn.execute("DELETE FROM comments WHERE deleted_at IS NOT NULL", ()).await?;
+        }
+        Ok(purged)
+    }
+
+    /// Append changes to the issue history, as part of the current operation or else a new one
+    async fn record_changes(&self, issue_id: i64, actor: &str, changes: &[Change]) -> Result<()> {
+        let operation_id = match OPERATION.try_with(|id| *id) {
+            Ok(id) => id,
+            Err(_) => Some(self.start_operation(None).await?),
+        };
+        let now = Utc::now().to_rfc3339();
+        for change in changes {
+            self.conn.execute(
+                "INSERT INTO issue_events (issue_id, actor, field, old_value, new_value, created_at, operation_id, comment_id)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                libsql::params![
+                    issue_id,
+                    actor,
+                    change.field.as_str(),
+                    change.old_value.clone(),
+                    change.new_value.clone(),
+                    now.clone(),
+                    operation_id,
+                    change.comment_id
+                ],
+            ).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn start_operation(&self, undoes: Option<i64>) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO operations (created_at, undoes) VALUES (?, ?)",
+            libsql::params![Utc::now().to_rfc3339(), undoes],
+        ).await?;
+
+        let mut rows = self.conn.query("SELECT last_insert_rowid()", ()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::Storage("Failed to get last insert rowid".to_string())),
+        }
+    }
+
+    /// Run `work` as one operation, so everything it changes is undone together.
+    /// Operations run one at a time, so checks made in `work` still hold when it writes.
+    /// Returns its result and the operation id, or `None` if nothing was changed.
+    pub async fn operation<T, E: From<Error>>(
+        &self,
+        work: impl std::future::Future<Output = std::result::Result<T, E>>,
+    ) -> std::result::Result<(T, Option<i64>), E> {
+        let _turn = self.writes.lock().await;
+        self.run_operation(work).await
+    }
+
+    /// `operation` for callers already holding `writes`
+    async fn run_operation<T, E: From<Error>>(
+        &self,
+        work: impl std::future::Future<Output = std::result::Result<T, E>>,
+    ) -> std::result::Result<(T, Option<i64>), E> {
+        let id = self.start_operation(None).await?;
+        let result = OPERATION.scope(Some(id), work).await;
+
+        let recorded = self.get_operation(id).await?.is_some();
+        if !recorded {
+            self.conn.execute("DELETE FROM operations WHERE id = ?", libsql::params![id]).await.map_err(Error::from)?;
+        }
+        Ok((result?, recorded.then_some(id)))
+    }
+
+    /// Run `work` with its changes recorded in the history but in no operation, so they
+    /// can't be undone. For changes pulled from GitHub: undoing them locally would make
+    /// the next sync push the old values back over other people's edits.
+    pub async fn without_undo<T>(&self, work: impl std::future::Future<Output = T>) -> T {
+        OPERATION.scope(None, work).await
+    }
+
+    /// Run `work` as part of the operation already under way, or else as one of its own
+    async fn within_operation<T>(&self, work: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        if OPERATION.try_with(|_| ()).is_ok() {
+            work.await
+        } else {
+            Ok(self.operation(work).await?.0)
+        }
+    }
+
+    /// An operation with its events, or `None` if it doesn't exist or changed nothing
+    pub async fn get_operation(&self, id: i64) -> Result<Option<Operation>> {
+        let mut rows = self.conn.query(
+            "SELECT id, created_at, undoes FROM operations WHERE id = ?",
+            libsql::params![id],
+        ).await?;
+        let (created_at, undoes): (String, Option<i64>) = match rows.next().await? {
+            Some(row) => (row.get(1)?, row.get(2)?),
+            None => return Ok(None),
+        };
+
+        let events = self.query_events("operation_id = ?", id).await?;
+        if events.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Operation { id, created_at: parse_timestamp(&created_at)?, undoes, events }))
+    }
+
+    /// Reverse an operation by applying its events backwards, as a new operation.
+    /// Fails, putting back anything it had reversed, if a field was changed again since,
+    /// or without starting if the operation was already undone or purged an issue.
+    /// Returns the new operation.
+    /// Runs alone like `operation`, so it can't be called from inside one.
+    pub async fn undo(&self, operation_id: i64) -> Result<Operation> {
+        let _turn = self.writes.lock().await;
+        let operation = self.get_operation(operation_id).await?
+            .ok_or_else(|| Error::NotFound(format!("Operation {} not found", operation_id)))?;
+        let mut rows = self.conn.query(
+            "SELECT id FROM operations WHERE undoes = ?",
+            libsql::params![operation_id],
+        ).await?;
+        if let Some(row) = rows.next().await? {
+            let undone_by: i64 = row.get(0)?;
+            return Err(Error::Conflict(format!("Operation {} was already undone by operation {}", operation_id, undone_by)));
+        }
+
+        let undo_id = self.start_operation(Some(operation_id)).await?;
+        if let Err(e) = self.reverse_operation(undo_id, &operation.events).await {
+            if let Err(cleanup) = self.abandon_undo(undo_id).await {
+                log::warn!("Could not put back the partial undo of operation {}: {}", operation_id, cleanup);
+            }
+            return Err(e);
+        }
+
+        self.get_operation(undo_id).await?
+            .ok_or_else(|| Error::Storage(format!("Undo of operation {} recorded no changes", operation_id)))
+    }
+
+    /// Apply `events` backwards as part of operation `id`
+    async fn reverse_operation(&self, id: i64, events: &[IssueEvent]) -> Result<()> {
+        OPERATION.scope(Some(id), async {
+            for event in events.iter().rev() {
+                self.reverse_event(event).await?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Take back what a failed undo changed before it stopped. The history is append-only,
+    /// so a partial undo stays recorded, reversed by an undo of its own, and no longer
+    /// counts as undoing its operation.
+    async fn abandon_undo(&self, undo_id: i64) -> Result<()> {
+        let events = self.query_events("operation_id = ?", undo_id).await?;
+        if events.is_empty() {
+            self.conn.execute("DELETE FROM operations WHERE id = ?", libsql::params![undo_id]).await?;
+            return Ok(());
+        }
+
+        let reversal_id = self.start_operation(Some(undo_id)).await?;
+        self.reverse_operation(reversal_id, &events).await?;
+        self.conn.execute("UPDATE operations SET undoes = NULL WHERE id = ?", libsql::params![undo_id]).await?;
+        Ok(())
+    }
+
+    /// Undo the latest `steps` operations that can be undone, newest first.
+    /// Undos themselves, operations already undone and purges are skipped.
+    /// Returns the operations that were undone.
+    pub async fn undo_last(&self, steps: usize) -> Result<Vec<Operation>> {
+        let mut rows = self.conn.query(
+            "SELECT o.id FROM operations o
+             WHERE o.undoes IS NULL
+               AND NOT EXISTS (SELECT 1 FROM operations u WHERE u.undoes = o.id)
+               AND EXISTS (SELECT 1 FROM issue_events e WHERE e.operation_id = o.id)
+               AND NOT EXISTS (SELECT 1 FROM issue_events e WHERE e.operation_id = o.id AND e.field = 'purged')
+             ORDER BY o.id DESC LIMIT ?",
+            libsql::params![steps as i64],
+        ).await?;
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get::<i64>(0)?);
+        }
+
+        let mut undone = Vec::new();
+        for id in ids {
+            let operation = self.get_operation(id).await?.storage_context("Operation disappeared")?;
+            self.undo(id).await?;
+            undone.push(operation);
+        }
+        Ok(undone)
+    }
+
+    /// Apply the opposite of one event, checking the issue still looks the way the event left it
+    async fn reverse_event(&self, event: &IssueEvent) -> Result<()> {
+        let id = event.issue_id;
+        let changed_since = || Error::Conflict(format!(
+            "Issue #{} changed its {} again since; undo that change first",
+            id, event.field
+        ));
+        let live_issue = || async {
+            self.get_issue_by_id(id).await?
+                .ok_or_else(|| Error::Conflict(format!("Issue #{} is not on the board any more", id)))
+        };
+
+        match event.field {
+            EventField::Created | EventField::Restored => {
+                live_issue().await?;
+                self.delete_issue(id).await?;
+            }
+            EventField::Deleted => {
+                if !self.restore_issue(id).await? {
+                    return Err(Error::Conflict(format!("Issue #{} is no longer in the trash", id)));
+                }
+            }
+            EventField::Purged => {
+                return Err(Error::Conflict(format!("Issue #{} was purged and can't be brought back", id)));
+            }
+            EventField::Label => {
+                live_issue().await?;
+                match (&event.old_value, &event.new_value) {
+                    // A renamed label: the first of the rename's events renames it back, the rest find it done
+                    (Some(old), Some(new)) => match (self.get_label_by_name(old).await?, self.get_label_by_name(new).await?) {
+                        (None, Some(mut label)) => {
+                            label.name = old.clone();
+                            self.update_label(&label).await?;
+                        }
+                        (Some(_), _) if self.get_issue_labels(id).await?.contains(old) => {}
+                        _ => return Err(changed_since()),
+                    },
+                    (_, Some(added)) => self.remove_label_from_issue(id, added).await?,
+                    (Some(removed), None) => {
+                        // A deleted label comes back, though without its color and description
+                        if self.get_label_by_name(removed).await?.is_none() {
+                            self.insert_label(&Label {
+                                id: None,
+                                name: removed.clone(),
+                                color: "#808080".to_string(),
+                                description: None,
+                                created_at: Utc::now(),
+                            }).await?;
+                        }
+                        self.add_label_to_issue(id, removed).await?
+                    }
+                    (None, None) => {}
+                }
+            }
+            EventField::Link => {
+                live_issue().await?;
+                let (value, restore) = match (&event.old_value, &event.new_value) {
+                    (_, Some(added)) => (added, false),
+                    (Some(removed), None) => (removed, true),
+                    (None, None) => return Ok(()),
+                };
+                let (kind, other_id) = parse_link_value(value)
+                    .ok_or_else(|| Error::Storage(format!("Unreadable link event: {}", value)))?;
+                if restore {
+                    self.link_issues(id, kind, other_id).await?;
+                } else {
+                    self.unlink_issues(id, kind, other_id).await?;
+                }
+            }
+            EventField::Rank => {
+                live_issue().await?;
+                if self.get_card_place(id).await? != event.new_value {
+                    return Err(changed_since());
+                }
+                match event.old_value.as_deref() {
+                    Some(old) => {
+                        let (column_id, rank) = rank::parse_place(old)
+                            .ok_or_else(|| Error::Storage(format!("Unreadable rank event: {}", old)))?;
+                        self.set_card_rank(id, column_id, rank).await?;
+                    }
+                    None => self.clear_card_rank(id).await?,
+                }
+            }
+            EventField::Comment => {
+                let comment_id = event.comment_id
+                    .ok_or_else(|| Error::Conflict("Comment changes recorded before undo existed can't be undone".to_string()))?;
+                match (&event.old_value, &event.new_value) {
+                    (None, _) => {
+                        self.delete_comment(comment_id).await?;
+                    }
+                    (Some(old_body), Some(new_body)) => {
+                        let mut comment = self.get_comment(comment_id).await?
+                            .filter(|comment| &comment.body == new_body)
+                            .ok_or_else(changed_since)?;
+                        comment.body = old_body.clone();
+                        comment.updated_at = Utc::now();
+                        self.update_comment(&comment).await?;
+                    }
+                    (Some(_), None) => {
+                        self.restore_comment(comment_id).await?;
+                    }
+                }
+            }
+            field => {
+                let mut issue = live_issue().await?;
+                let current = match field {
+                    EventField::Title => Some(issue.title.clone()),
+                    EventField::Description => issue.description.clone(),
+                    EventField::Status => Some(issue.status.to_string()),
+                    EventField::Priority => Some(issue.priority.to_string()),
+                    EventField::Parent => issue.parent_id.map(|parent| parent.to_string()),
+                    _ => issue.assignee.clone(),
+                };
+                if current != event.new_value {
+                    return Err(changed_since());
+                }
+
+                let old = event.old_value.clone();
+                match field {
+                    EventField::Title => issue.title = old.unwrap_or_default(),
+                    EventField::Description => issue.description = old,
+                    EventField::Status => issue.status = old.unwrap_or_default().parse()?,
+                    EventField::Priority => issue.priority = old.unwrap_or_default().parse()?,
+                    EventField::Parent => {
+                        issue.parent_id = old.map(|parent| parent.parse())
+                            .transpose()
+                            .map_err(|_| Error::Storage(format!("Unreadable parent in event {}", event.id)))?;
+                    }
+                    _ => issue.assignee = old,
+                }
+                self.update_issue(&issue).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The recorded history of an issue, oldest first. Kept after the issue is deleted.
+    pub async fn get_issue_events(&self, issue_id: i64) -> Result<Vec<IssueEvent>> {
+        self.query_events("issue_id = ?", issue_id).await
+    }
+
+    /// Events matching a condition on one parameter, oldest first
+    async fn query_events(&self, condition: &str, value: i64) -> Result<Vec<IssueEvent>> {
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT id, issue_id, actor, field, old_value, new_value, created_at, operation_id, comment_id
+                 FROM issue_events WHERE {} ORDER BY id",
+                condition
+            ),
+            libsql::params![value],
+        ).await?;
+
+        let mut events = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let field: String = row.get(3)?;
+            let created_at: String = row.get(6)?;
+            events.push(IssueEvent {
+                id: row.get(0)?,
+                issue_id: row.get(1)?,
+                actor: row.get(2)?,
+                field: field.parse()?,
+                old_value: row.get(4)?,
+                new_value: row.get(5)?,
+                created_at: parse_timestamp(&created_at)?,
+                operation_id: row.get(7)?,
+                comment_id: row.get(8)?,
+            });
+        }
+
+        Ok(events)
+    }
+
+    // Dependencies between issues
+    /// Link two issues, e.g. `link_issues(3, DependencyKind::Blocks, 5)` for "#3 blocks #5".
+    /// Returns false if they were already linked that way. Fails if either issue doesn't
+    /// exist, or if the link would close a cycle of blocks or duplicates links.
+    pub async fn link_issues(&self, issue_id: i64, kind: DependencyKind, other_id: i64) -> Result<bool> {
+        if issue_id == other_id {
+            return Err(Error::Validation(format!("Issue #{} can't be linked to itself", issue_id)));
+        }
+        for id in [issue_id, other_id] {
+            if self.get_issue_by_id(id).await?.is_none() {
+                return Err(Error::NotFound(format!("Issue #{} not found", id)));
+            }
+        }
+
+        let (stored, source, target) = kind.stored(issue_id, other_id);
+        if stored.is_acyclic() {
+            let edges = self.get_dependency_edges(stored).await?;
+            if let Some(path) = find_path(&edges, target, source) {
+                let cycle: Vec<String> = path.iter().chain([&target]).map(|id| format!("#{}", id)).collect();
+                return Err(Error::Conflict(format!(
+                    "Linking would create a cycle: {}",
+                    cycle.join(&format!(" {} ", stored))
+                )));
+            }
+        }
+
+        let added = self.conn.execute(
+            "INSERT OR IGNORE INTO issue_dependencies (source_id, target_id, kind, created_at) VALUES (?, ?, ?, ?)",
+            libsql::params![source, target, stored.as_str(), Utc::now().to_rfc3339()],
+        ).await?;
+        if added == 0 {
+            return Ok(false);
+        }
+        self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Link, None, Some(link_value(kind, other_id)))]).await?;
+        self.record_changes(other_id, &self.actor, &[Change::new(EventField::Link, None, Some(link_value(kind.inverse(), issue_id)))]).await?;
+        Ok(true)
+    }
+
+    /// Remove a link made by `link_issues`. Returns false if there was no such link.
+    pub async fn unlink_issues(&self, issue_id: i64, kind: DependencyKind, other_id: i64) -> Result<bool> {
+        let (stored, source, target) = kind.stored(issue_id, other_id);
+        let removed = self.conn.execute(
+            "DELETE FROM issue_dependencies WHERE source_id = ? AND target_id = ? AND kind = ?",
+            libsql::params![source, target, stored.as_str()],
+        ).await?;
+        if removed == 0 {
+            return Ok(false);
+        }
+        self.record_changes(issue_id, &self.actor, &[Change::new(EventField::Link, Some(link_value(kind, other_id)), None)]).await?;
+        self.record_changes(other_id, &self.actor, &[Change::new(EventField::Link, Some(link_value(kind.inverse(), issue_id)), None)]).await?;
+        Ok(true)
+    }
+
+    /// An issue's links to issues that aren't in the trash, seen from its side, by the other issue's id
+    pub async fn get_issue_links(&self, issue_id: i64) -> Result<Vec<IssueLink>> {
+        let mut rows = self.conn.query(
+            "SELECT d.kind, d.source_id, i.id, i.title, i.status
+             FROM issue_dependencies d
+             JOIN issues i ON i.id = CASE WHEN d.source_id = ?1 THEN d.target_id ELSE d.source_id END
+             WHERE (d.source_id = ?1 OR d.target_id = ?1) AND i.deleted_at IS NULL
+             ORDER BY i.id, d.kind",
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut links = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let kind: DependencyKind = row.get::<String>(0)?.parse()?;
+            let source_id: i64 = row.get(1)?;
+            links.push(IssueLink {
+                kind: if source_id == issue_id { kind } else { kind.inverse() },
+                issue_id: row.get(2)?,
+                title: row.get(3)?,
+                status: row.get::<String>(4)?.parse()?,
+            });
+        }
+
+        Ok(links)
+    }
+
+    /// The issues blocking this one that are neither resolved, closed nor in the trash
+    pub async fn get_open_blockers(&self, issue_id: i64) -> Result<Vec<i64>> {
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT d.source_id FROM issue_dependencies d JOIN issues b ON b.id = d.source_id
+                 WHERE d.target_id = ? AND {} ORDER BY d.source_id",
+                OPEN_BLOCKER
+            ),
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut blockers = Vec::new();
+        while let Some(row) = rows.next().await? {
+            blockers.push(row.get(0)?);
+        }
+
+        Ok(blockers)
+    }
+
+    /// Open and in-progress issues with no open blockers, most urgent first, then oldest first
+    pub async fn get_ready_issues(&self) -> Result<Vec<Issue>> {
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT {} FROM issues
+                 WHERE deleted_at IS NULL AND status IN ('open', 'in_progress')
+                   AND NOT EXISTS (
+                       SELECT 1 FROM issue_dependencies d JOIN issues b ON b.id = d.source_id
+                       WHERE d.target_id = issues.id AND {}
+                   )",
+                ISSUE_COLUMNS, OPEN_BLOCKER
+            ),
+            (),
+        ).await?;
+
+        let mut issues = Vec::new();
+        while let Some(row) = rows.next().await? {
+            issues.push(self.issue_from_row(&row).await?);
+        }
+        issues.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.created_at.cmp(&b.created_at)));
+
+        Ok(issues)
+    }
+
+    /// Source and target of every stored link of one kind
+    async fn get_dependency_edges(&self, kind: DependencyKind) -> Result<Vec<(i64, i64)>> {
+        let mut rows = self.conn.query(
+            "SELECT source_id, target_id FROM issue_dependencies WHERE kind = ?",
+            libsql::params![kind.as_str()],
+        ).await?;
+
+        let mut edges = Vec::new();
+        while let Some(row) = rows.next().await? {
+            edges.push((row.get(0)?, row.get(1)?));
+        }
+
+        Ok(edges)
+    }
+
+    // Sub-tasks
+    /// Check `parent` can be the parent of issue `id` (`None` for a new issue): it must exist,
+    /// not be in the trash, and not be the issue itself or one of its sub-tasks
+    async fn check_parent(&self, id: Option<i64>, parent: Option<i64>) -> Result<()> {
+        let Some(parent) = parent else {
+            return Ok(());
+        };
+        if self.get_issue_by_id(parent).await?.is_none() {
+            return Err(Error::Validation(format!("Parent issue #{} not found", parent)));
+        }
+        if let Some(id) = id {
+            if id == parent {
+                return Err(Error::Validation(format!("Issue #{} can't be its own parent", id)));
+            }
+            if self.get_descendant_ids(id).await?.contains(&parent) {
+                return Err(Error::Conflict(format!(
+                    "Issue #{} is a sub-task of #{}, so it can't be its parent",
+                    parent, id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The direct sub-tasks of an issue that aren't in the trash, oldest first
+    pub async fn get_children(&self, issue_id: i64) -> Result<Vec<Issue>> {
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT {} FROM issues WHERE parent_id = ? AND deleted_at IS NULL ORDER BY created_at, id",
+                ISSUE_COLUMNS
+            ),
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut children = Vec::new();
+        while let Some(row) = rows.next().await? {
+            children.push(self.issue_from_row(&row).await?);
+        }
+
+        Ok(children)
+    }
+
+    /// How many of an issue's sub-tasks, at any depth, are resolved or closed.
+    /// `None` if it has no sub-tasks outside the trash.
+    pub async fn get_subtask_progress(&self, issue_id: i64) -> Result<Option<Progress>> {
+        let mut rows = self.conn.query(
+            "WITH RECURSIVE descendants(id) AS (
+                 SELECT id FROM issues WHERE parent_id = ?1 AND deleted_at IS NULL
+                 UNION
+                 SELECT i.id FROM issues i JOIN descendants d ON i.parent_id = d.id WHERE i.deleted_at IS NULL
+             )
+             SELECT COUNT(*), COALESCE(SUM(status IN ('resolved', 'closed')), 0)
+             FROM issues WHERE id IN (SELECT id FROM descendants)",
+            libsql::params![issue_id],
+        ).await?;
+
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        let total: i64 = row.get(0)?;
+        let done: i64 = row.get(1)?;
+        Ok((total > 0).then_some(Progress { done: done as u32, total: total as u32 }))
+    }
+
+    /// Ids of an issue's parent, its parent's parent and so on, nearest first.
+    /// Works for issues in the trash too.
+    pub async fn get_ancestor_ids(&self, issue_id: i64) -> Result<Vec<i64>> {
+        let mut rows = self.conn.query(
+            "WITH RECURSIVE ancestors(id, depth) AS (
+                 SELECT parent_id, 1 FROM issues WHERE id = ?1 AND parent_id IS NOT NULL
+                 UNION
+                 SELECT i.parent_id, a.depth + 1 FROM issues i JOIN ancestors a ON i.id = a.id
+                 WHERE i.parent_id IS NOT NULL
+             )
+             SELECT id FROM ancestors ORDER BY depth",
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get(0)?);
+        }
+
+        Ok(ids)
+    }
+
+    /// Ids of every sub-task of an issue at any depth, including those in the trash
+    async fn get_descendant_ids(&self, issue_id: i64) -> Result<Vec<i64>> {
+        let mut rows = self.conn.query(
+            "WITH RECURSIVE descendants(id) AS (
+                 SELECT id FROM issues WHERE parent_id = ?1
+                 UNION
+                 SELECT i.id FROM issues i JOIN descendants d ON i.parent_id = d.id
+             )
+             SELECT id FROM descendants",
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get(0)?);
+        }
+
+        Ok(ids)
+    }
+
+    // CRUD operations for comments
+    pub async fn insert_comment(&self, comment: &Comment) -> Result<i64> {
+        if comment.body.trim().is_empty() {
+            return Err(Error::Validation("Comment must not be empty".to_string()));
+        }
+        let external = comment.external_ref.as_ref();
+        self.conn.execute(
+            "INSERT INTO comments (issue_id, author, body, created_at, updated_at, external_source, external_id, external_url, synced_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            libsql::params![
+                comment.issue_id,
+                comment.author.clone(),
+                comment.body.clone(),
+                comment.created_at.to_rfc3339(),
+                comment.updated_at.to_rfc3339(),
+                external.map(|e| e.source.clone()),
+                external.map(|e| e.id.clone()),
+                external.and_then(|e| e.url.clone()),
+                comment.synced_at.map(|t| t.to_rfc3339())
+            ],
+        ).await?;
+
+        let mut rows = self.conn.query("SELECT last_insert_rowid()", ()).await?;
+        let comment_id: i64 = if let Some(row) = rows.next().await? {
+            row.get(0)?
+        } else {
+            return Err(Error::Storage("Failed to get last insert rowid".to_string()));
+        };
+
+        self.record_changes(comment.issue_id, &comment.author, &[Change::comment(comment_id, None, Some(comment.body.clone()))]).await?;
+        Ok(comment_id)
+    }
+
+    pub async fn get_comment(&self, id: i64) -> Result<Option<Comment>> {
+        let mut rows = self.conn.query(
+            &format!("SELECT {} FROM comments WHERE id = ? AND deleted_at IS NULL", COMMENT_COLUMNS),
+            libsql::params![id],
+        ).await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(Some(Self::comment_from_row(&row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The comments on an issue, oldest first
+    pub async fn get_comments_for_issue(&self, issue_id: i64) -> Result<Vec<Comment>> {
+        let mut rows = self.conn.query(
+            &format!("SELECT {} FROM comments WHERE issue_id = ? AND deleted_at IS NULL ORDER BY created_at, id", COMMENT_COLUMNS),
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut comments = Vec::new();
+        while let Some(row) = rows.next().await? {
+            comments.push(Self::comment_from_row(&row)?);
+        }
+
+        Ok(comments)
+    }
+
+    pub async fn count_comments(&self, issue_id: i64) -> Result<u32> {
+        let mut rows = self.conn.query(
+            "SELECT COUNT(*) FROM comments WHERE issue_id = ? AND deleted_at IS NULL",
+            libsql::params![issue_id],
+        ).await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)? as u32),
+            None => Ok(0),
+        }
+    }
+
+    /// Save a comment's body, timestamps and external reference. Set `updated_at`
+    /// for local edits; they are sent to GitHub by `export_github_comments`.
+    pub async fn update_comment(&self, comment: &Comment) -> Result<()> {
+        self.update_comment_by(comment, &self.actor).await
+    }
+
+    async fn update_comment_by(&self, comment: &Comment, actor: &str) -> Result<()> {
+        let id = comment.id.ok_or_else(|| Error::Validation("Cannot update a comment without an id".to_string()))?;
+        if comment.body.trim().is_empty() {
+            return Err(Error::Validation("Comment must not be empty".to_string()));
+        }
+        let old = self.get_comment(id).await?;
+
+        let external = comment.external_ref.as_ref();
+        self.conn.execute(
+            "UPDATE comments SET body = ?, updated_at = ?, external_source = ?, external_id = ?, external_url = ?, synced_at = ?
+             WHERE id = ?",
+            libsql::params![
+                comment.body.clone(),
+                comment.updated_at.to_rfc3339(),
+                external.map(|e| e.source.clone()),
+                external.map(|e| e.id.clone()),
+                external.and_then(|e| e.url.clone()),
+                comment.synced_at.map(|t| t.to_rfc3339()),
+                id
+            ],
+        ).await?;
+
+        // Saving sync bookkeeping alone is not an edit
+        if let Some(old) = old.filter(|old| old.body != comment.body) {
+            self.record_changes(old.issue_id, actor, &[Change::comment(id, Some(old.body), Some(comment.body.clone()))]).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete a comment; it is kept until the trash is purged, so the deletion can be undone.
+    /// Returns false if there is no comment with this id.
+    pub async fn delete_comment(&self, id: i64) -> Result<bool> {
+        self.delete_comment_by(id, &self.actor).await
+    }
+
+    async fn delete_comment_by(&self, id: i64, actor: &str) -> Result<bool> {
+        let comment = match self.get_comment(id).await? {
+            Some(comment) => comment,
+            None => return Ok(false),
+        };
+        self.conn.execute(
+            "UPDATE comments SET deleted_at = ? WHERE id = ?",
+            libsql::params![Utc::now().to_rfc3339(), id],
+        ).await?;
+
+        self.record_changes(comment.issue_id, actor, &[Change::comment(id, Some(comment.body), None)]).await?;
+        Ok(true)
+    }
+
+    /// Bring back a deleted comment
+    async fn restore_comment(&self, id: i64) -> Result<()> {
+        let restored = self.conn.execute(
+            "UPDATE comments SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+            libsql::params![id],
+        ).await?;
+        let comment = self.get_comment(id).await?
+            .filter(|_| restored > 0)
+            .ok_or_else(|| Error::Conflict(format!("Comment {} is no longer deleted", id)))?;
+
+        self.record_changes(comment.issue_id, &self.actor, &[Change::comment(id, None, Some(comment.body))]).await
+    }
+
+    /// All comments on an issue, each with whether it was deleted locally
+    async fn get_comments_including_deleted(&self, issue_id: i64) -> Result<Vec<(Comment, bool)>> {
+        let mut rows = self.conn.query(
+            &format!("SELECT {}, deleted_at IS NOT NULL FROM comments WHERE issue_id = ? ORDER BY created_at, id", COMMENT_COLUMNS),
+            libsql::params![issue_id],
+        ).await?;
+
+        let mut comments = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let deleted: bool = row.get(10)?;
+            comments.push((Self::comment_from_row(&row)?, deleted));
+        }
+
+        Ok(comments)
+    }
+
+    /// Build a Comment from a row selected with `COMMENT_COLUMNS`
+    fn comment_from_row(row: &libsql::Row) -> Result<Comment> {
+        let created_at: String = row.get(4)?;
+        let updated_at: String = row.get(5)?;
+        let external_source: Option<String> = row.get(6)?;
+        let external_id: Option<String> = row.get(7)?;
+        let synced_at: Option<String> = row.get(9)?;
+
+        let external_ref = match (external_source, external_id) {
+            (Some(source), Some(id)) => Some(ExternalRef { source, id, url: row.get(8)? }),
+            _ => None,
+        };
+
+        Ok(Comment {
+            id: Some(row.get(0)?),
+            issue_id: row.get(1)?,
+            author: row.get(2)?,
+            body: row.get(3)?,
+            created_at: parse_timestamp(&created_at)?,
+            updated_at: parse_timestamp(&updated_at)?,
+            external_ref,
+            synced_at: synced_at.as_deref().map(parse_timestamp).transpose()?,
+        })
+    }
+
+    // Git integration functions
+
+    /// Import commits reachable from HEAD of the repository at `repo_path`
+    /// (the current directory when `None`).
+    ///
+    /// Imports are incremental: the last imported tip of each ref is recorded in
+    /// `import_state`, and the history walk stops at any recorded tip. All inserts
+    /// of one run happen in a single transaction, so an interrupted import leaves
+    /// the previous high-water mark intact and the next run picks up from there.
+    /// Issues the commits resolve change in one operation.
+    pub async fn populate_from_git_history(&self, repo_path: Option<&str>) -> Result<usize> {
+        // The repository handle is not Sync, so it is opened in short scopes that never span an await
+        let repo_path = repo_path.unwrap_or(".");
+        let (ref_name, tip) = GitOps::new_from_path(repo_path)?.resolve_ref("HEAD")?;
+
+        if self.get_import_tip(&ref_name).await?.as_deref() == Some(tip.as_str()) {
+            return Ok(0);
         }
 
-        Ok(labels)
+        let known_tips = self.get_all_import_tips().await?;
+        let commits = GitOps::new_from_path(repo_path)?.get_commits_since(&tip, &known_tips)?;
+
+        // The transaction is on the shared connection, so nothing else may write until it ends
+        let _turn = self.writes.lock().await;
+        self.conn.execute("BEGIN", ()).await?;
+        match self.run_operation(self.import_commits(&ref_name, &tip, commits)).await {
+            Ok((count, _)) => {
+                self.conn.execute("COMMIT", ()).await?;
+                Ok(count)
+            }
+            Err(e) => {
+                self.rollback().await;
+                Err(e)
+            }
+        }
     }
 
-    pub async fn update_issue_status(&self, id: i64, status: IssueStatus) -> Result<()> {
+    /// Roll back the open transaction after a failure. A failed rollback is only logged,
+    /// so the caller can still return the error that caused it.
+    async fn rollback(&self) {
+        if let Err(e) = self.conn.execute("ROLLBACK", ()).await {
+            log::warn!("Failed to roll back transaction: {}", e);
+        }
+    }
+
+    /// Insert commits and advance the ref's high-water mark; runs inside the caller's transaction
+    async fn import_commits(&self, ref_name: &str, tip: &str, commits: Vec<CommitInfo>) -> Result<usize> {
+        let mut commits_inserted = 0;
+        for commit_info in commits {
+            let commit = GitCommit::from(commit_info);
+            if self.insert_commit_if_new(&commit).await? {
+                self.link_commit_to_issues(&commit).await?;
+                commits_inserted += 1;
+            }
+        }
+
         self.conn.execute(
-            "UPDATE issues SET status = ?, updated_at = ? WHERE id = ?",
-            libsql::params![status.to_string(), Utc::now().to_rfc3339(), id],
+            "INSERT INTO import_state (ref_name, tip_hash, imported_at) VALUES (?, ?, ?)
+             ON CONFLICT(ref_name) DO UPDATE SET tip_hash = excluded.tip_hash, imported_at = excluded.imported_at",
+            libsql::params![ref_name, tip, Utc::now().to_rfc3339()],
         ).await?;
 
-        Ok(())
+        Ok(commits_inserted)
     }
 
-    pub async fn delete_issue(&self, id: i64) -> Result<()> {
-        self.conn.execute(
-            "DELETE FROM issues WHERE id = ?",
-            libsql::params![id],
+    /// Insert a commit unless one with the same hash exists; returns whether it was inserted
+    async fn insert_commit_if_new(&self, commit: &GitCommit) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO commits (hash, author_name, author_email, commit_date, message, parent_hashes, files_changed, file_stats, insertions, deletions)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            libsql::params![
+                commit.hash.clone(),
+                commit.author_name.clone(),
+                commit.author_email.clone(),
+                commit.commit_date.to_rfc3339(),
+                commit.message.clone(),
+                serde_json::to_string(&commit.parent_hashes)?,
+                serde_json::to_string(&commit.files_changed)?,
+                serde_json::to_string(&commit.file_stats)?,
+                commit.insertions,
+                commit.deletions
+            ],
         ).await?;
 
-        Ok(())
+        Ok(inserted > 0)
     }
 
-    // Git integration functions
-    pub async fn populate_from_git_history(&self, repo_path: Option<&str>) -> Result<usize> {
-        let git_log_output = if let Some(path) = repo_path {
-            Command::new("git")
-                .current_dir(path)
-                .args([
-                    "log",
-                    "--pretty=format:%H|%an|%ae|%ai|%s",
-                    "--numstat",
-                ])
-                .output()?
-        } else {
-            Command::new("git")
-                .args([
-                    "log",
-                    "--pretty=format:%H|%an|%ae|%ai|%s",
-                    "--numstat",
-                ])
-                .output()?
-        };
-
-        if !git_log_output.status.success() {
-            return Err(anyhow::anyhow!("Failed to get git log: {}", 
-                String::from_utf8_lossy(&git_log_output.stderr)));
-        }
+    /// Record the issues referenced by a commit message in `commit_issues`.
+    ///
+    /// `#N` is resolved by `resolve_issue_reference`; references to unknown issues are ignored.
+    /// Open or in-progress issues referenced with a closing keyword are moved to `Resolved`.
+    /// Returns the references that matched an issue, with the local issue ids.
+    pub async fn link_commit_to_issues(&self, commit: &GitCommit) -> Result<Vec<IssueReference>> {
+        let mut linked = Vec::new();
 
-        let output = String::from_utf8(git_log_output.stdout)?;
-        let mut commits_inserted = 0;
+        for mut reference in parse_issue_references(&commit.message) {
+            let issue = match self.resolve_issue_reference(reference.issue_id).await? {
+                Some(issue) => issue,
+                None => continue,
+            };
+            reference.issue_id = issue.id.storage_context("Stored issue has no id")?;
 
-        let lines: Vec<&str> = output.lines().collect();
-        let mut i = 0;
+            self.conn.execute(
+                "INSERT INTO commit_issues (commit_hash, issue_id, kind) VALUES (?, ?, ?)
+                 ON CONFLICT(commit_hash, issue_id) DO UPDATE SET kind = excluded.kind",
+                libsql::params![commit.hash.clone(), reference.issue_id, reference.kind.to_string()],
+            ).await?;
 
-        while i < lines.len() {
-            let line = lines[i].trim();
-            if line.is_empty() {
-                i += 1;
-                continue;
+            if reference.kind == IssueReferenceKind::Closes
+                && matches!(issue.status, IssueStatus::Open | IssueStatus::InProgress)
+            {
+                self.update_issue_status_by(reference.issue_id, IssueStatus::Resolved, &commit.author_name).await?;
             }
 
-            // Parse commit info line
-            let parts: Vec<&str> = line.splitn(5, '|').collect();
-            if parts.len() != 5 {
-                i += 1;
-                continue;
-            }
+            linked.push(reference);
+        }
 
-            let hash = parts[0].to_string();
-            let author_name = parts[1].to_string();
-            let author_email = parts[2].to_string();
-            let date_str = parts[3];
-            let message = parts[4].to_string();
+        Ok(linked)
+    }
 
-            let commit_date = DateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S %z")
-                .map_err(|e| anyhow::anyhow!("Failed to parse date '{}': {}", date_str, e))?
-                .with_timezone(&Utc);
+    /// The issue a `#N` in a commit message means: the issue imported from GitHub issue N if
+    /// there is one, as commits pushed to GitHub number issues that way, otherwise the local
+    /// issue with id N unless that one came from GitHub under another number
+    async fn resolve_issue_reference(&self, number: i64) -> Result<Option<Issue>> {
+        if let Some(issue) = self.get_issue_by_external_ref("github", &number.to_string()).await? {
+            return Ok(Some(issue));
+        }
+        Ok(self.get_issue_by_id(number).await?
+            .filter(|issue| issue.external_ref.as_ref().is_none_or(|external| external.source != "github")))
+    }
 
-            // Parse file changes
-            i += 1;
-            let mut files_changed = Vec::new();
-            let mut total_insertions = 0;
-            let mut total_deletions = 0;
-
-            while i < lines.len() {
-                let stat_line = lines[i].trim();
-                if stat_line.is_empty() {
-                    break;
-                }
+    /// Get the commits that reference an issue, newest first
+    pub async fn get_commits_for_issue(&self, issue_id: i64) -> Result<Vec<LinkedCommit>> {
+        let columns = COMMIT_COLUMNS
+            .split(", ")
+            .map(|column| format!("c.{}", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut rows = self.conn.query(
+            &format!(
+                "SELECT {}, ci.kind FROM commits c
+                 JOIN commit_issues ci ON ci.commit_hash = c.hash
+                 WHERE ci.issue_id = ?
+                 ORDER BY c.commit_date DESC",
+                columns
+            ),
+            libsql::params![issue_id],
+        ).await?;
 
-                // Check if this is the next commit (starts with hash pattern)
-                if stat_line.contains('|') && stat_line.len() > 40 {
-                    break;
-                }
+        let mut commits = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let kind: String = row.get(11)?;
+            commits.push(LinkedCommit {
+                commit: Self::commit_from_row(&row)?,
+                kind: kind.parse()?,
+            });
+        }
 
-                let parts: Vec<&str> = stat_line.split('\t').collect();
-                if parts.len() >= 3 {
-                    let insertions: i32 = parts[0].parse().unwrap_or(0);
-                    let deletions: i32 = parts[1].parse().unwrap_or(0);
-                    let filename = parts[2].to_string();
+        Ok(commits)
+    }
 
-                    files_changed.push(filename);
-                    total_insertions += insertions;
-                    total_deletions += deletions;
-                }
-                i += 1;
-            }
+    /// Get the last imported commit hash for a ref, if it has been imported before
+    pub async fn get_import_tip(&self, ref_name: &str) -> Result<Option<String>> {
+        let mut rows = self.conn.query(
+            "SELECT tip_hash FROM import_state WHERE ref_name = ?",
+            libsql::params![ref_name],
+        ).await?;
 
-            let git_commit = GitCommit {
-                id: None,
-                hash,
-                author_name,
-                author_email,
-                commit_date,
-                message,
-                files_changed,
-                insertions: total_insertions,
-                deletions: total_deletions,
-            };
+        if let Some(row) = rows.next().await? {
+            Ok(Some(row.get(0)?))
+        } else {
+            Ok(None)
+        }
+    }
 
-            // Check if commit already exists
-            if self.get_commit_by_hash(&git_commit.hash).await?.is_none() {
-                self.insert_commit(&git_commit).await?;
-                commits_inserted += 1;
-            }
+    async fn get_all_import_tips(&self) -> Result<Vec<String>> {
+        let mut rows = self.conn.query("SELECT tip_hash FROM import_state", ()).await?;
+
+        let mut tips = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tips.push(row.get(0)?);
         }
 
-        Ok(commits_inserted)
+        Ok(tips)
     }
 
     pub async fn create_default_labels(&self) -> Result<()> {
@@ -665,178 +2560,503 @@ impl TaskDatabase {
         Ok(())
     }
 
-    /// Load GitHub issues using the gh CLI command
-    pub async fn load_github_issues_via_cli(&self) -> Result<usize> {
-        // First, check if gh CLI is available
-        let gh_check = Command::new("gh")
-            .args(["--version"])
-            .output()
-            .context("Failed to check if 'gh' CLI is installed")?;
-        
-        if !gh_check.status.success() {
-            anyhow::bail!("GitHub CLI (gh) is not installed or not available");
-        }
-        
-        // Run gh issue list command to get JSON output
-        let output = Command::new("gh")
-            .args([
-                "issue", "list", 
-                "--json", "number,title,body,state,labels,assignees,createdAt,updatedAt",
-                "--limit", "100"  // Limit to avoid too many issues
-            ])
-            .output()
-            .context("Failed to execute 'gh issue list' command")?;
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("GitHub CLI command failed: {}", stderr);
+    /// Insert an issue mirrored from an external tracker, or update the existing copy in place.
+    ///
+    /// The issue is matched on `external_ref`, which is required. An existing copy is only
+    /// updated when the incoming `updated_at` is newer than the stored one; local-only state
+    /// (priority, and open/in-progress/resolved status while the remote issue stays open) is kept.
+    pub async fn upsert_external_issue(&self, issue: &Issue) -> Result<UpsertOutcome> {
+        let external = issue.external_ref.as_ref()
+            .ok_or_else(|| Error::Validation("Cannot upsert an issue without an external reference".to_string()))?;
+
+        let existing = match self.get_issue_by_external_ref(&external.source, &external.id).await? {
+            Some(existing) => existing,
+            None if self.is_external_issue_trashed(&external.source, &external.id).await? => {
+                return Ok(UpsertOutcome::Trashed);
+            }
+            None => return Ok(UpsertOutcome::Created(self.insert_issue_by(issue, &external.source).await?)),
+        };
+        let id = existing.id.storage_context("Stored issue has no id")?;
+
+        if issue.updated_at <= existing.updated_at {
+            return Ok(UpsertOutcome::Unchanged(id));
         }
-        
-        let stdout = String::from_utf8(output.stdout)
-            .context("Invalid UTF-8 in gh command output")?;
-        
-        let issues_json: Value = serde_json::from_str(&stdout)
-            .context("Failed to parse JSON from gh command")?;
-        
-        let issues_array = issues_json.as_array()
-            .context("Expected JSON array from gh issue list")?;
-        
+
+        let status = match (&issue.status, &existing.status) {
+            (IssueStatus::Closed, _) => IssueStatus::Closed,
+            (_, IssueStatus::Closed) => IssueStatus::Open,
+            (_, local) => local.clone(),
+        };
+
+        self.update_issue_by(&Issue {
+            id: Some(id),
+            status,
+            priority: existing.priority,
+            created_at: existing.created_at,
+            parent_id: existing.parent_id,
+            ..issue.clone()
+        }, &external.source).await?;
+
+        Ok(UpsertOutcome::Updated(id))
+    }
+
+    /// Import issues from GitHub, inserting new ones and refreshing ones already stored.
+    /// With `since`, only issues updated upstream at or after that time are fetched.
+    /// The newest upstream `updated_at` seen is recorded per repository (see
+    /// `get_github_import_mark`) so the next import can start from there. Issues that
+    /// fail to import are logged and skipped, and the mark is kept below the earliest
+    /// of them so the next import fetches them again.
+    /// Returns the number of issues created or updated.
+    pub async fn import_github_issues(&self, github: &GitHubOps, since: Option<DateTime<Utc>>) -> Result<usize> {
+        let remote_issues = github.list_issues_since(since).await?;
+
         let mut loaded_count = 0;
-        
-        for issue_value in issues_array {
-            let issue_number = issue_value["number"].as_u64()
-                .context("Issue number should be a number")?;
-            
-            // Check if we already have this issue
-            if let Ok(existing_issues) = self.get_all_issues().await {
-                if existing_issues.iter().any(|issue| {
-                    issue.title.contains(&format!("#{}", issue_number)) ||
-                    issue.description.as_ref().map(|d| d.contains(&format!("#{}", issue_number))).unwrap_or(false)
-                }) {
-                    continue; // Skip if already exists
+        let mut newest_update = since;
+        let mut earliest_failure: Option<DateTime<Utc>> = None;
+
+        for remote in &remote_issues {
+            match self.without_undo(self.import_github_issue(github, remote)).await {
+                Ok(true) => loaded_count += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    log::warn!("Failed to import issue #{}: {}", remote.number, e);
+                    if earliest_failure.is_none_or(|earliest| remote.updated_at < earliest) {
+                        earliest_failure = Some(remote.updated_at);
+                    }
                 }
             }
-            
-            let title = issue_value["title"].as_str()
-                .unwrap_or("Untitled Issue")
-                .to_string();
-            
-            let body = issue_value["body"].as_str()
-                .map(|s| s.to_string());
-            
-            let state = issue_value["state"].as_str()
-                .unwrap_or("open");
-            
-            let status = match state.to_lowercase().as_str() {
-                "closed" => IssueStatus::Closed,
-                _ => IssueStatus::Open,
+
+            if newest_update.is_none_or(|newest| remote.updated_at > newest) {
+                newest_update = Some(remote.updated_at);
+            }
+        }
+
+        // `since` is inclusive, so a mark just below a failed issue fetches it again
+        if let Some(earliest) = earliest_failure {
+            let below = earliest - chrono::Duration::seconds(1);
+            newest_update = newest_update.map(|newest| newest.min(below));
+        }
+        if let Some(newest) = newest_update {
+            self.conn.execute(
+                "INSERT INTO github_import_state (repository, last_updated_at, imported_at) VALUES (?, ?, ?)
+                 ON CONFLICT(repository) DO UPDATE SET last_updated_at = excluded.last_updated_at, imported_at = excluded.imported_at",
+                libsql::params![github.repository(), newest.to_rfc3339(), Utc::now().to_rfc3339()],
+            ).await?;
+        }
+
+        Ok(loaded_count)
+    }
+
+    /// Import one GitHub issue and, if it changed, its comments.
+    /// Returns whether the issue was created or updated.
+    async fn import_github_issue(&self, github: &GitHubOps, remote: &GitHubIssue) -> Result<bool> {
+        let issue = self.issue_from_github(remote).await?;
+
+        // Insert new issues and refresh ones we already have
+        let (id, changed) = match self.upsert_external_issue(&issue).await? {
+            UpsertOutcome::Created(id) | UpsertOutcome::Updated(id) => (id, true),
+            UpsertOutcome::Unchanged(id) => (id, false),
+            UpsertOutcome::Trashed => return Ok(false),
+        };
+
+        // New comments bump the issue's updated_at on GitHub, so an unchanged issue only
+        // lacks comments when importing them failed last time
+        let local_comments = self.count_comments(id).await?;
+        if (changed && (remote.comments > 0 || local_comments > 0)) || remote.comments > local_comments {
+            self.import_github_comments(github, id, remote.number).await
+                .map_err(|e| e.context("Failed to import its comments"))?;
+        }
+        Ok(changed)
+    }
+
+    /// Bring the comments on GitHub issue `issue_number` into local issue `issue_id`.
+    /// New comments are added, comments edited on GitHub since they were last synced
+    /// are overwritten and copies of comments deleted on GitHub are removed. Comments
+    /// deleted locally stay deleted. Returns the number of comments added or updated.
+    pub async fn import_github_comments(&self, github: &GitHubOps, issue_id: i64, issue_number: u64) -> Result<usize> {
+        let remote_comments = github.list_comments(issue_number).await?;
+        let local_comments = self.get_comments_including_deleted(issue_id).await?;
+        let github_id = |comment: &Comment| comment.external_ref.as_ref()
+            .filter(|external| external.source == "github")
+            .map(|external| external.id.clone());
+
+        let mut changed = 0;
+        for remote in &remote_comments {
+            let remote_updated_at = remote.updated_at.unwrap_or(remote.created_at);
+            let mut comment = Comment {
+                id: None,
+                issue_id,
+                author: remote.user.login.clone(),
+                body: remote.body.clone().unwrap_or_default(),
+                created_at: remote.created_at,
+                updated_at: remote_updated_at,
+                external_ref: Some(ExternalRef::github(remote.id.0, Some(remote.html_url.to_string()))),
+                synced_at: Some(remote_updated_at),
             };
-            
-            // Parse labels and create any missing labels in the database
-            let labels = if let Some(labels_array) = issue_value["labels"].as_array() {
-                let mut issue_labels = Vec::new();
-                for label_obj in labels_array {
-                    if let Some(label_name) = label_obj["name"].as_str() {
-                        // Create the label if it doesn't exist
-                        if self.get_label_by_name(label_name).await?.is_none() {
-                            let label_color = label_obj["color"].as_str()
-                                .unwrap_or("808080"); // Default gray color
-                            let label_description = label_obj["description"].as_str()
-                                .unwrap_or("");
-                            
-                            let new_label = Label {
-                                id: None,
-                                name: label_name.to_string(),
-                                color: format!("#{}", label_color),
-                                description: if label_description.is_empty() {
-                                    None
-                                } else {
-                                    Some(label_description.to_string())
-                                },
-                                created_at: Utc::now(),
-                            };
-                            
-                            if let Err(e) = self.insert_label(&new_label).await {
-                                eprintln!("⚠️  Failed to create label '{}': {}", label_name, e);
-                            }
-                        }
-                        issue_labels.push(label_name.to_string());
-                    }
+            if comment.body.trim().is_empty() {
+                continue;
+            }
+
+            let remote_id = remote.id.0.to_string();
+            match local_comments.iter().find(|(local, _)| github_id(local).as_ref() == Some(&remote_id)) {
+                Some((_, true)) => continue,
+                Some((local, _)) if local.synced_at.is_some_and(|synced| remote_updated_at <= synced) => continue,
+                Some((local, _)) => {
+                    comment.id = local.id;
+                    self.update_comment_by(&comment, "github").await?;
                 }
-                issue_labels
-            } else {
-                vec![]
-            };
-            
-            // Parse assignee
-            let assignee = if let Some(assignees_array) = issue_value["assignees"].as_array() {
-                assignees_array.first()
-                    .and_then(|assignee| assignee["login"].as_str())
-                    .map(|s| s.to_string())
-            } else {
-                None
-            };
-            
-            // Parse dates
-            let created_at = if let Some(created_str) = issue_value["createdAt"].as_str() {
-                DateTime::parse_from_rfc3339(created_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now())
-            } else {
-                Utc::now()
-            };
-            
-            let updated_at = if let Some(updated_str) = issue_value["updatedAt"].as_str() {
-                DateTime::parse_from_rfc3339(updated_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now())
-            } else {
-                Utc::now()
-            };
-            
-            // Determine priority from labels
-            let priority = if labels.iter().any(|l| l.to_lowercase().contains("critical")) {
-                IssuePriority::Critical
-            } else if labels.iter().any(|l| l.to_lowercase().contains("high")) {
-                IssuePriority::High
-            } else if labels.iter().any(|l| l.to_lowercase().contains("low")) {
-                IssuePriority::Low
-            } else {
-                IssuePriority::Medium
-            };
-            
-            // Create the issue
-            let issue = Issue {
-                id: None,
-                title: format!("#{}: {}", issue_number, title),
-                description: body,
-                status,
-                priority,
-                created_at,
-                updated_at,
-                assignee,
-                labels,
+                None => {
+                    self.insert_comment(&comment).await?;
+                }
+            }
+            changed += 1;
+        }
+
+        let remote_ids: Vec<String> = remote_comments.iter().map(|remote| remote.id.0.to_string()).collect();
+        for (local, deleted) in &local_comments {
+            if !deleted && github_id(local).is_some_and(|id| !remote_ids.contains(&id)) {
+                self.delete_comment_by(local.id.storage_context("Stored comment has no id")?, "github").await?;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Send local comments on issue `issue_id` to GitHub issue `issue_number`: comments
+    /// that were never sent are posted, comments edited since they were last synced are
+    /// updated, and deleted comments are deleted there too. Returns the number of comments sent.
+    pub async fn export_github_comments(&self, github: &GitHubOps, issue_id: i64, issue_number: u64) -> Result<usize> {
+        let github_id = |external: &ExternalRef| external.id.parse::<u64>()
+            .map_err(|_| Error::Storage(format!("Invalid GitHub comment id '{}'", external.id)));
+
+        let mut sent = 0;
+        for (comment, deleted) in self.get_comments_including_deleted(issue_id).await? {
+            let id = comment.id.storage_context("Stored comment has no id")?;
+            let external = comment.external_ref.as_ref().filter(|external| external.source == "github");
+            let remote = match (external, deleted) {
+                // Forget the GitHub copy once it is gone, so a restored comment is posted afresh
+                (Some(external), true) => {
+                    github.delete_comment(github_id(external)?).await?;
+                    self.save_comment_sync_state(id, None, None).await?;
+                    sent += 1;
+                    continue;
+                }
+                (None, false) if comment.external_ref.is_none() => github.add_comment(issue_number, &comment.body).await?,
+                (Some(external), false) if comment.synced_at.is_none_or(|synced| comment.updated_at > synced) => {
+                    github.update_comment(github_id(external)?, &comment.body).await?
+                }
+                _ => continue,
             };
-            
-            // Insert into database
-            match self.insert_issue(&issue).await {
-                Ok(_) => {
-                    loaded_count += 1;
+
+            // Saved straight away, so a failure further on never posts the comment twice.
+            // GitHub times are whole seconds and may trail the local edit.
+            let remote_updated_at = remote.updated_at.unwrap_or(remote.created_at);
+            self.save_comment_sync_state(
+                id,
+                Some(&ExternalRef::github(remote.id.0, Some(remote.html_url.to_string()))),
+                Some(remote_updated_at.max(comment.updated_at)),
+            ).await?;
+            sent += 1;
+        }
+
+        Ok(sent)
+    }
+
+    /// Record which GitHub comment a local comment is synced with, and when it was last synced
+    async fn save_comment_sync_state(&self, id: i64, external: Option<&ExternalRef>, synced_at: Option<DateTime<Utc>>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE comments SET external_source = ?, external_id = ?, external_url = ?, synced_at = ? WHERE id = ?",
+            libsql::params![
+                external.map(|e| e.source.clone()),
+                external.map(|e| e.id.clone()),
+                external.and_then(|e| e.url.clone()),
+                synced_at.map(|t| t.to_rfc3339()),
+                id
+            ],
+        ).await?;
+        Ok(())
+    }
+
+    /// Get the newest upstream `updated_at` imported from a GitHub `owner/repo`, if any
+    pub async fn get_github_import_mark(&self, repository: &str) -> Result<Option<DateTime<Utc>>> {
+        let mut rows = self.conn.query(
+            "SELECT last_updated_at FROM github_import_state WHERE repository = ?",
+            libsql::params![repository],
+        ).await?;
+
+        if let Some(row) = rows.next().await? {
+            let mark: String = row.get(0)?;
+            Ok(Some(parse_timestamp(&mark)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Send a mutation to GitHub, or queue it in the outbox if GitHub can't take it
+    /// right now (`Error::Upstream`: no connection, server errors, rate limits).
+    /// Other errors, such as a missing issue or a rejected change, are returned.
+    /// Mutations for an issue that already has queued ones are queued behind them
+    /// so they reach GitHub in the order they were made.
+    pub async fn submit_github_mutation(&self, github: &GitHubOps, mutation: &GitHubMutation) -> Result<Delivery> {
+        let repository = github.repository();
+        if !self.has_queued_github_mutations(&repository, mutation.issue_number()).await? {
+            match github.apply(mutation).await {
+                Ok(()) => return Ok(Delivery::Sent),
+                Err(e @ Error::Upstream(_)) => {
+                    let id = self.enqueue_github_mutation(&repository, mutation).await?;
+                    self.record_outbox_failure(id, &e).await?;
+                    return Ok(Delivery::Queued(id));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Delivery::Queued(self.enqueue_github_mutation(&repository, mutation).await?))
+    }
+
+    /// Add a mutation to the end of the outbox
+    pub async fn enqueue_github_mutation(&self, repository: &str, mutation: &GitHubMutation) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO github_outbox (repository, issue_number, mutation, created_at) VALUES (?, ?, ?, ?)",
+            libsql::params![
+                repository,
+                mutation.issue_number() as i64,
+                serde_json::to_string(mutation)?,
+                Utc::now().to_rfc3339()
+            ],
+        ).await?;
+
+        let mut rows = self.conn.query("SELECT last_insert_rowid()", ()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Err(Error::Storage("Failed to get last insert rowid".to_string())),
+        }
+    }
+
+    /// Whether the outbox holds mutations for a GitHub issue of `repository`
+    pub async fn has_queued_github_mutations(&self, repository: &str, issue_number: u64) -> Result<bool> {
+        let mut rows = self.conn.query(
+            "SELECT 1 FROM github_outbox WHERE repository = ? AND issue_number = ? LIMIT 1",
+            libsql::params![repository, issue_number as i64],
+        ).await?;
+        Ok(rows.next().await?.is_some())
+    }
+
+    /// Queued mutations for a repository, oldest first
+    pub async fn get_outbox(&self, repository: &str) -> Result<Vec<OutboxEntry>> {
+        let mut rows = self.conn.query(
+            "SELECT id, repository, mutation, created_at, attempts, last_error
+             FROM github_outbox WHERE repository = ? ORDER BY id",
+            libsql::params![repository],
+        ).await?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let mutation: String = row.get(2)?;
+            let created_at: String = row.get(3)?;
+            entries.push(OutboxEntry {
+                id: row.get(0)?,
+                repository: row.get(1)?,
+                mutation: serde_json::from_str(&mutation)?,
+                created_at: parse_timestamp(&created_at)?,
+                attempts: row.get(4)?,
+                last_error: row.get(5)?,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Replay the outbox for `github`'s repository in order. Earlier add/remove
+    /// changes to a label are dropped when a later one to the same label follows.
+    /// An entry that fails with `Error::Upstream` stays queued with its error, and
+    /// later entries for the same issue wait behind it; other issues carry on.
+    /// Entries GitHub refuses outright are dropped and listed in `rejected`.
+    pub async fn flush_outbox(&self, github: &GitHubOps) -> Result<FlushReport> {
+        let mut report = FlushReport::default();
+        let mut entries = self.get_outbox(&github.repository()).await?;
+
+        // Only the last queued change to each label decides whether it ends up on the issue
+        let mut superseded = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            if let Some(target) = entry.mutation.label_target() {
+                if entries[index + 1..].iter().any(|later| later.mutation.label_target() == Some(target)) {
+                    superseded.push(entry.id);
+                }
+            }
+        }
+        for id in &superseded {
+            self.delete_outbox_entry(*id).await?;
+        }
+        entries.retain(|entry| !superseded.contains(&entry.id));
+        report.coalesced = superseded.len();
+
+        let mut blocked_issues = Vec::new();
+        for mut entry in entries {
+            let issue_number = entry.mutation.issue_number();
+            if blocked_issues.contains(&issue_number) {
+                report.remaining.push(entry);
+                continue;
+            }
+
+            match github.apply(&entry.mutation).await {
+                Ok(()) => {
+                    self.delete_outbox_entry(entry.id).await?;
+                    report.sent += 1;
+                }
+                Err(e @ Error::Upstream(_)) => {
+                    self.record_outbox_failure(entry.id, &e).await?;
+                    entry.attempts += 1;
+                    entry.last_error = Some(e.to_string());
+                    blocked_issues.push(issue_number);
+                    report.remaining.push(entry);
                 }
                 Err(e) => {
-                    eprintln!("⚠️  Failed to insert issue #{}: {}", issue_number, e);
+                    self.delete_outbox_entry(entry.id).await?;
+                    entry.attempts += 1;
+                    entry.last_error = Some(e.to_string());
+                    report.rejected.push(entry);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Drop a queued mutation without sending it
+    pub async fn delete_outbox_entry(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM github_outbox WHERE id = ?", libsql::params![id]).await?;
+        Ok(())
+    }
+
+    async fn record_outbox_failure(&self, id: i64, error: &Error) -> Result<()> {
+        self.conn.execute(
+            "UPDATE github_outbox SET attempts = attempts + 1, last_error = ? WHERE id = ?",
+            libsql::params![error.to_string(), id],
+        ).await?;
+        Ok(())
+    }
+
+    /// Get the last-synced state of a GitHub-linked issue, if it has been synced before
+    pub async fn get_sync_snapshot(&self, issue_id: i64) -> Result<Option<SyncSnapshot>> {
+        let mut rows = self.conn.query(
+            "SELECT issue_id, title, description, closed, labels, assignee, local_updated_at, remote_updated_at
+             FROM issue_sync_state WHERE issue_id = ?",
+            libsql::params![issue_id],
+        ).await?;
+
+        if let Some(row) = rows.next().await? {
+            let labels: String = row.get(4)?;
+            let local_updated_at: String = row.get(6)?;
+            let remote_updated_at: String = row.get(7)?;
+            Ok(Some(SyncSnapshot {
+                issue_id: row.get(0)?,
+                title: row.get(1)?,
+                description: row.get(2)?,
+                closed: row.get::<i64>(3)? != 0,
+                labels: serde_json::from_str(&labels)?,
+                assignee: row.get(5)?,
+                local_updated_at: parse_timestamp(&local_updated_at)?,
+                remote_updated_at: parse_timestamp(&remote_updated_at)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Record the state both sides agreed on at the end of a sync
+    pub async fn save_sync_snapshot(&self, snapshot: &SyncSnapshot) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO issue_sync_state
+                (issue_id, title, description, closed, labels, assignee, local_updated_at, remote_updated_at, synced_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(issue_id) DO UPDATE SET
+                title = excluded.title, description = excluded.description, closed = excluded.closed,
+                labels = excluded.labels, assignee = excluded.assignee,
+                local_updated_at = excluded.local_updated_at, remote_updated_at = excluded.remote_updated_at,
+                synced_at = excluded.synced_at",
+            libsql::params![
+                snapshot.issue_id,
+                snapshot.title.clone(),
+                snapshot.description.clone(),
+                snapshot.closed,
+                serde_json::to_string(&snapshot.labels)?,
+                snapshot.assignee.clone(),
+                snapshot.local_updated_at.to_rfc3339(),
+                snapshot.remote_updated_at.to_rfc3339(),
+                Utc::now().to_rfc3339()
+            ],
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Create local copies of any GitHub labels we don't have yet, returning their names
+    pub async fn ensure_github_labels(&self, remote_labels: &[GitHubLabel]) -> Result<Vec<String>> {
+        let mut labels = Vec::new();
+        for remote_label in remote_labels {
+            if self.get_label_by_name(&remote_label.name).await?.is_none() {
+                let new_label = Label {
+                    id: None,
+                    name: remote_label.name.clone(),
+                    color: format!("#{}", remote_label.color),
+                    description: remote_label.description.clone().filter(|d| !d.is_empty()),
+                    created_at: Utc::now(),
+                };
+
+                if let Err(e) = self.insert_label(&new_label).await {
+                    log::warn!("Failed to create label '{}': {}", remote_label.name, e);
                 }
             }
+            labels.push(remote_label.name.clone());
         }
-        
-        Ok(loaded_count)
+
+        Ok(labels)
+    }
+
+    /// Convert a GitHub issue into an Issue, creating any labels we don't have yet
+    pub async fn issue_from_github(&self, remote: &GitHubIssue) -> Result<Issue> {
+        let labels = self.ensure_github_labels(&remote.labels).await?;
+
+        let status = match remote.state {
+            IssueState::Closed => IssueStatus::Closed,
+            _ => IssueStatus::Open,
+        };
+
+        // Determine priority from labels
+        let priority = if labels.iter().any(|l| l.to_lowercase().contains("critical")) {
+            IssuePriority::Critical
+        } else if labels.iter().any(|l| l.to_lowercase().contains("high")) {
+            IssuePriority::High
+        } else if labels.iter().any(|l| l.to_lowercase().contains("low")) {
+            IssuePriority::Low
+        } else {
+            IssuePriority::Medium
+        };
+
+        let assignee = remote.assignees.first()
+            .or(remote.assignee.as_ref())
+            .map(|author| author.login.clone());
+
+        Ok(Issue {
+            id: None,
+            title: remote.title.clone(),
+            description: remote.body.clone(),
+            status,
+            priority,
+            created_at: remote.created_at,
+            updated_at: remote.updated_at,
+            assignee,
+            labels,
+            external_ref: Some(ExternalRef::github(remote.number, Some(remote.html_url.to_string()))),
+            parent_id: None,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio;
 
     // Helper function to create a test database
     async fn create_test_db() -> Result<TaskDatabase> {
@@ -852,7 +3072,12 @@ mod tests {
             author_email: "test@example.com".to_string(),
             commit_date: Utc::now(),
             message: "Test commit message".to_string(),
+            parent_hashes: vec!["0123456789abcdef".to_string()],
             files_changed: vec!["src/main.rs".to_string(), "README.md".to_string()],
+            file_stats: vec![
+                FileChange { path: "src/main.rs".to_string(), insertions: 8, deletions: 5, binary: false },
+                FileChange { path: "README.md".to_string(), insertions: 2, deletions: 0, binary: false },
+            ],
             insertions: 10,
             deletions: 5,
         }
@@ -881,6 +3106,8 @@ mod tests {
             updated_at: Utc::now(),
             assignee: Some("test-user".to_string()),
             labels: vec!["test-label".to_string()],
+            external_ref: None,
+            parent_id: None,
         }
     }
 
@@ -908,7 +3135,9 @@ mod tests {
         assert_eq!(retrieved_commit.author_name, commit.author_name);
         assert_eq!(retrieved_commit.author_email, commit.author_email);
         assert_eq!(retrieved_commit.message, commit.message);
+        assert_eq!(retrieved_commit.parent_hashes, commit.parent_hashes);
         assert_eq!(retrieved_commit.files_changed, commit.files_changed);
+        assert_eq!(retrieved_commit.file_stats, commit.file_stats);
         assert_eq!(retrieved_commit.insertions, commit.insertions);
         assert_eq!(retrieved_commit.deletions, commit.deletions);
     }
@@ -931,6 +3160,112 @@ mod tests {
         assert_eq!(commits.len(), 2, "Should retrieve all inserted commits");
     }
 
+    #[tokio::test]
+    async fn test_query_issues() {
+        let db = create_test_db().await.unwrap();
+        for name in ["bug", "wontfix"] {
+            let mut label = create_sample_label();
+            label.name = name.to_string();
+            db.insert_label(&label).await.unwrap();
+        }
+        let issue = |title: &str, description: &str, status, priority, assignee: Option<&str>, labels: &[&str], created: &str| Issue {
+            title: title.to_string(),
+            description: Some(description.to_string()),
+            status,
+            priority,
+            assignee: assignee.map(str::to_string),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            created_at: DateTime::parse_from_rfc3339(created).unwrap().with_timezone(&Utc),
+            ..create_sample_issue()
+        };
+        let login = db.insert_issue(&issue("Login page broken", "100% reproducible", IssueStatus::Open,
+            IssuePriority::High, Some("alice"), &["bug"], "2024-03-01T10:00:00Z")).await.unwrap();
+        let crash = db.insert_issue(&issue("Crash on save", "Stack trace attached", IssueStatus::InProgress,
+            IssuePriority::Critical, Some("bob"), &["bug", "wontfix"], "2024-01-10T10:00:00Z")).await.unwrap();
+        let docs = db.insert_issue(&issue("Docs typo", "Fix the login_url example", IssueStatus::Open,
+            IssuePriority::Low, None, &[], "2024-02-01T10:00:00Z")).await.unwrap();
+
+        let ids = |text: &str| {
+            let query = Query::parse(text).unwrap();
+            let db = &db;
+            async move {
+                db.query_issues(&query).await.unwrap().into_iter().map(|i| i.id.unwrap()).collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(ids("").await, vec![login, docs, crash], "Newest first");
+        assert_eq!(ids("label:bug -label:wontfix").await, vec![login]);
+        assert_eq!(ids("priority:>=high").await, vec![login, crash]);
+        assert_eq!(ids("priority:<medium").await, vec![docs]);
+        assert_eq!(ids("priority:>critical").await, Vec::<i64>::new());
+        assert_eq!(ids("-assignee:alice status:open").await, vec![docs], "Unassigned issues aren't alice's");
+        assert_eq!(ids("created:<2024-02-01").await, vec![crash]);
+        assert_eq!(ids("created:2024-02-01").await, vec![docs]);
+        assert_eq!(ids("LABEL:BUG assignee:ALICE LOGIN").await, vec![login]);
+        assert_eq!(ids("\"stack trace\"").await, vec![crash], "Text matches descriptions");
+        assert_eq!(ids("100%").await, vec![login]);
+        assert_eq!(ids("login_").await, vec![docs], "LIKE wildcards are matched literally");
+    }
+
+    #[tokio::test]
+    async fn test_get_commits_with_filters() {
+        let db = create_test_db().await.unwrap();
+        let mut issue = create_sample_issue();
+        issue.labels = vec![];
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+
+        let base = DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let commits = [
+            ("aaa", "Alice", "alice@example.com", 0, vec!["src/web.rs"], "Add routes".to_string()),
+            ("bbb", "Bob", "bob@example.com", 1, vec!["src/web/api.rs", "README.md"], format!("Fix #{}", issue_id)),
+            ("ccc", "Alice", "alice@corp.example", 2, vec!["src/webby.rs"], "Rename".to_string()),
+            ("ddd", "Dee", "dee_100%@example.com", -1, vec!["docs/index.md"], "Docs".to_string()),
+        ];
+        for (hash, name, email, days, files, message) in commits {
+            let mut commit = create_sample_commit();
+            commit.hash = hash.to_string();
+            commit.author_name = name.to_string();
+            commit.author_email = email.to_string();
+            commit.commit_date = base + chrono::Duration::days(days);
+            commit.files_changed = files.into_iter().map(String::from).collect();
+            commit.message = message;
+            db.insert_commit(&commit).await.unwrap();
+            db.link_commit_to_issues(&commit).await.unwrap();
+        }
+
+        let hashes = |commits: Vec<GitCommit>| commits.into_iter().map(|c| c.hash).collect::<Vec<_>>();
+        let all = db.get_commits(&CommitFilter::default()).await.unwrap();
+        assert_eq!(hashes(all), vec!["ccc", "bbb", "aaa", "ddd"]);
+
+        let by_author = CommitFilter { author: Some("alice".to_string()), ..Default::default() };
+        assert_eq!(hashes(db.get_commits(&by_author).await.unwrap()), vec!["ccc", "aaa"]);
+
+        let by_email = CommitFilter { author: Some("corp.example".to_string()), ..Default::default() };
+        assert_eq!(hashes(db.get_commits(&by_email).await.unwrap()), vec!["ccc"]);
+        for wildcard in ["%", "_"] {
+            let literal = CommitFilter { author: Some(wildcard.to_string()), ..Default::default() };
+            assert_eq!(hashes(db.get_commits(&literal).await.unwrap()), vec!["ddd"], "LIKE wildcards are matched literally");
+        }
+
+        let window = CommitFilter {
+            since: Some(base + chrono::Duration::hours(1)),
+            until: Some(base + chrono::Duration::days(2)),
+            ..Default::default()
+        };
+        assert_eq!(hashes(db.get_commits(&window).await.unwrap()), vec!["ccc", "bbb"]);
+
+        // A directory matches the files below it, but not siblings sharing its prefix
+        let by_dir = CommitFilter { path: Some("src/web/".to_string()), ..Default::default() };
+        assert_eq!(hashes(db.get_commits(&by_dir).await.unwrap()), vec!["bbb"]);
+        let by_file = CommitFilter { path: Some("src/web.rs".to_string()), ..Default::default() };
+        assert_eq!(hashes(db.get_commits(&by_file).await.unwrap()), vec!["aaa"]);
+
+        let by_issue = CommitFilter { issue: Some(issue_id), ..Default::default() };
+        assert_eq!(hashes(db.get_commits(&by_issue).await.unwrap()), vec!["bbb"]);
+
+        let limited = CommitFilter { author: Some("alice".to_string()), limit: Some(1), ..Default::default() };
+        assert_eq!(hashes(db.get_commits(&limited).await.unwrap()), vec!["ccc"]);
+    }
+
     #[tokio::test]
     async fn test_commit_hash_uniqueness() {
         let db = create_test_db().await.unwrap();
@@ -994,6 +3329,66 @@ mod tests {
         assert!(result2.is_err(), "Second insert with same name should fail");
     }
 
+    #[tokio::test]
+    async fn test_update_and_delete_label() {
+        let db = create_test_db().await.unwrap();
+        db.insert_label(&create_sample_label()).await.unwrap();
+        let issue_id = db.insert_issue(&create_sample_issue()).await.unwrap();
+
+        let mut label = db.get_label_by_name("test-label").await.unwrap().unwrap();
+        label.name = "renamed".to_string();
+        label.color = "#00ff00".to_string();
+        label.description = None;
+        db.update_label(&label).await.unwrap();
+
+        assert!(db.get_label_by_name("test-label").await.unwrap().is_none());
+        let renamed = db.get_label_by_name("renamed").await.unwrap().unwrap();
+        assert_eq!(renamed.color, "#00ff00");
+        assert_eq!(renamed.description, None);
+        let issue = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(issue.labels, vec!["renamed".to_string()], "Issues follow the rename");
+
+        assert!(db.delete_label("renamed").await.unwrap());
+        assert!(!db.delete_label("renamed").await.unwrap());
+        let issue = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert!(issue.labels.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_label_changes_are_recorded_and_undone() {
+        let db = create_test_db().await.unwrap();
+        db.insert_label(&create_sample_label()).await.unwrap();
+        let first = db.insert_issue(&create_sample_issue()).await.unwrap();
+        let second = db.insert_issue(&create_sample_issue()).await.unwrap();
+        let labels = |id| {
+            let db = &db;
+            async move { db.get_issue_by_id(id).await.unwrap().unwrap().labels }
+        };
+
+        // A rename is one operation with an event on every issue carrying the label
+        let mut label = db.get_label_by_name("test-label").await.unwrap().unwrap();
+        label.name = "triaged".to_string();
+        db.update_label(&label).await.unwrap();
+        let rename = db.get_issue_events(first).await.unwrap().pop().unwrap();
+        assert_eq!(rename.to_string(), "renamed label test-label to triaged");
+        let operation = db.get_operation(rename.operation_id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(operation.events.iter().map(|e| e.issue_id).collect::<Vec<_>>(), vec![first, second]);
+
+        db.undo(operation.id).await.unwrap();
+        assert!(db.get_label_by_name("triaged").await.unwrap().is_none());
+        assert_eq!(labels(first).await, vec!["test-label".to_string()]);
+        assert_eq!(labels(second).await, vec!["test-label".to_string()]);
+
+        // A deletion puts the label back on its issues when undone
+        assert!(db.delete_label("test-label").await.unwrap());
+        let removal = db.get_issue_events(second).await.unwrap().pop().unwrap();
+        assert_eq!(removal.to_string(), "removed label test-label");
+        let undone = db.undo_last(1).await.unwrap();
+        assert_eq!(undone[0].events.len(), 2);
+        assert_eq!(labels(first).await, vec!["test-label".to_string()]);
+        assert_eq!(labels(second).await, vec!["test-label".to_string()]);
+    }
+
     #[tokio::test]
     async fn test_create_default_labels() {
         let db = create_test_db().await.unwrap();
@@ -1016,97 +3411,707 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_insert_and_retrieve_issue() {
+    async fn test_insert_and_retrieve_issue() {
+        let db = create_test_db().await.unwrap();
+        
+        // Create label first
+        let label = create_sample_label();
+        db.insert_label(&label).await.unwrap();
+        
+        // Insert issue
+        let issue = create_sample_issue();
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+        assert!(issue_id > 0, "Issue ID should be positive");
+        
+        // Retrieve issue
+        let retrieved = db.get_issue_by_id(issue_id).await.unwrap();
+        assert!(retrieved.is_some(), "Issue should be retrievable by ID");
+        
+        let retrieved_issue = retrieved.unwrap();
+        assert_eq!(retrieved_issue.title, issue.title);
+        assert_eq!(retrieved_issue.description, issue.description);
+        assert_eq!(retrieved_issue.status.to_string(), issue.status.to_string());
+        assert_eq!(retrieved_issue.priority.to_string(), issue.priority.to_string());
+        assert_eq!(retrieved_issue.assignee, issue.assignee);
+        assert_eq!(retrieved_issue.labels, issue.labels);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_issues() {
+        let db = create_test_db().await.unwrap();
+        
+        // Create label first
+        let label = create_sample_label();
+        db.insert_label(&label).await.unwrap();
+        
+        // Insert multiple issues
+        let mut issue1 = create_sample_issue();
+        issue1.title = "Issue 1".to_string();
+        let mut issue2 = create_sample_issue();
+        issue2.title = "Issue 2".to_string();
+        
+        db.insert_issue(&issue1).await.unwrap();
+        db.insert_issue(&issue2).await.unwrap();
+        
+        // Retrieve all issues
+        let issues = db.get_all_issues().await.unwrap();
+        assert_eq!(issues.len(), 2, "Should retrieve all inserted issues");
+    }
+
+    #[tokio::test]
+    async fn test_issue_label_association() {
+        let db = create_test_db().await.unwrap();
+        
+        // Create multiple labels
+        let mut label1 = create_sample_label();
+        label1.name = "bug".to_string();
+        let mut label2 = create_sample_label();
+        label2.name = "enhancement".to_string();
+        
+        db.insert_label(&label1).await.unwrap();
+        db.insert_label(&label2).await.unwrap();
+        
+        // Create issue with multiple labels
+        let mut issue = create_sample_issue();
+        issue.labels = vec!["bug".to_string(), "enhancement".to_string()];
+        
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+        
+        // Retrieve and verify labels
+        let retrieved_issue = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(retrieved_issue.labels.len(), 2, "Issue should have 2 labels");
+        assert!(retrieved_issue.labels.contains(&"bug".to_string()));
+        assert!(retrieved_issue.labels.contains(&"enhancement".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_update_issue_status() {
+        let db = create_test_db().await.unwrap();
+        
+        // Create label first
+        let label = create_sample_label();
+        db.insert_label(&label).await.unwrap();
+        
+        // Insert issue
+        let issue = create_sample_issue();
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+        
+        // Update status
+        db.update_issue_status(issue_id, IssueStatus::InProgress).await.unwrap();
+        
+        // Verify update
+        let updated_issue = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(updated_issue.status.to_string(), "in_progress");
+    }
+
+    #[tokio::test]
+    async fn test_update_issue() {
+        let db = create_test_db().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        
+        let mut issue = create_sample_issue();
+        issue.labels = vec!["bug".to_string()];
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+        
+        // Edit every mutable field
+        let mut edited = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        edited.title = "Edited title".to_string();
+        edited.description = None;
+        edited.priority = IssuePriority::Critical;
+        edited.assignee = Some("someone-else".to_string());
+        edited.labels = vec!["enhancement".to_string(), "question".to_string()];
+        db.update_issue(&edited).await.unwrap();
+        
+        let updated = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(updated.title, "Edited title");
+        assert_eq!(updated.description, None);
+        assert_eq!(updated.priority.to_string(), "critical");
+        assert_eq!(updated.assignee, Some("someone-else".to_string()));
+        assert_eq!(updated.labels.len(), 2);
+        assert!(!updated.labels.contains(&"bug".to_string()), "Old labels should be replaced");
+        
+        // Updating requires an id
+        assert!(db.update_issue(&create_sample_issue()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_issue_labels() {
+        let db = create_test_db().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        
+        let mut issue = create_sample_issue();
+        issue.labels = vec![];
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+        
+        db.add_label_to_issue(issue_id, "bug").await.unwrap();
+        // Adding the same label twice is idempotent
+        db.add_label_to_issue(issue_id, "bug").await.unwrap();
+        let retrieved = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(retrieved.labels, vec!["bug".to_string()]);
+        assert_eq!(db.get_issue_ids_with_label("bug").await.unwrap(), vec![issue_id]);
+        
+        // Unknown labels are rejected
+        assert!(db.add_label_to_issue(issue_id, "no-such-label").await.is_err());
+        
+        db.remove_label_from_issue(issue_id, "bug").await.unwrap();
+        let retrieved = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert!(retrieved.labels.is_empty());
+        assert!(db.get_issue_ids_with_label("bug").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upsert_external_issue() {
+        let db = create_test_db().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        
+        let mut remote = create_sample_issue();
+        remote.labels = vec!["bug".to_string()];
+        remote.updated_at = Utc::now() - chrono::Duration::hours(1);
+        remote.external_ref = Some(ExternalRef::github(42, Some("https://github.com/o/r/issues/42".to_string())));
+        
+        let id = match db.upsert_external_issue(&remote).await.unwrap() {
+            UpsertOutcome::Created(id) => id,
+            other => panic!("Expected Created, got {:?}", other),
+        };
+        let stored = db.get_issue_by_external_ref("github", "42").await.unwrap().unwrap();
+        assert_eq!(stored.id, Some(id));
+        assert_eq!(stored.title, "Test Issue", "Titles are no longer prefixed with the number");
+        assert_eq!(stored.external_ref, remote.external_ref);
+        
+        // Same remote copy again: nothing to do
+        assert_eq!(db.upsert_external_issue(&remote).await.unwrap(), UpsertOutcome::Unchanged(id));
+        
+        // Local work in progress survives a remote rename
+        db.update_issue_status(id, IssueStatus::InProgress).await.unwrap();
+        remote.title = "Renamed upstream".to_string();
+        remote.labels = vec!["enhancement".to_string()];
+        remote.updated_at = Utc::now() + chrono::Duration::minutes(1);
+        assert_eq!(db.upsert_external_issue(&remote).await.unwrap(), UpsertOutcome::Updated(id));
+        
+        let updated = db.get_issue_by_id(id).await.unwrap().unwrap();
+        assert_eq!(updated.title, "Renamed upstream");
+        assert_eq!(updated.labels, vec!["enhancement".to_string()]);
+        assert_eq!(updated.status.to_string(), "in_progress");
+        assert_eq!(db.get_all_issues().await.unwrap().len(), 1, "No duplicate should be created");
+        
+        // Closing upstream closes the local copy
+        remote.status = IssueStatus::Closed;
+        remote.updated_at = Utc::now() + chrono::Duration::minutes(2);
+        db.upsert_external_issue(&remote).await.unwrap();
+        assert_eq!(db.get_issue_by_id(id).await.unwrap().unwrap().status.to_string(), "closed");
+        
+        // External references are unique
+        let mut duplicate = create_sample_issue();
+        duplicate.external_ref = remote.external_ref.clone();
+        assert!(db.insert_issue(&duplicate).await.is_err());
+        
+        // An external reference is required
+        assert!(db.upsert_external_issue(&create_sample_issue()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_github_issues() {
+        use crate::git_ops::github_mock::{issue_json, MockGitHub, OWNER, REPO};
+
+        let db = create_test_db().await.unwrap();
+        let server = MockGitHub::start().await;
+        server.add_issue(issue_json(1, "Crash on start", "open", "2024-01-02T00:00:00Z", &["bug", "priority: high"]));
+        server.add_issue(issue_json(2, "Old request", "closed", "2024-01-03T00:00:00Z", &[]));
+        let github = server.client();
+        let repository = format!("{}/{}", OWNER, REPO);
+
+        assert_eq!(db.get_github_import_mark(&repository).await.unwrap(), None);
+        assert_eq!(db.import_github_issues(&github, None).await.unwrap(), 2);
+
+        let crash = db.get_issue_by_external_ref("github", "1").await.unwrap().unwrap();
+        assert_eq!(crash.title, "Crash on start");
+        assert_eq!(crash.description, Some("Body of issue 1".to_string()));
+        assert_eq!(crash.assignee, Some("alice".to_string()));
+        assert_eq!(crash.priority.to_string(), "high");
+        assert_eq!(crash.labels, vec!["bug".to_string(), "priority: high".to_string()]);
+        assert_eq!(db.get_label_by_name("priority: high").await.unwrap().unwrap().color, "#c0ffee");
+        assert_eq!(crash.external_ref.unwrap().url.as_deref(), Some("https://github.com/octo/widgets/issues/1"));
+
+        let closed = db.get_issue_by_external_ref("github", "2").await.unwrap().unwrap();
+        assert_eq!(closed.status.to_string(), "closed");
+
+        let mark = db.get_github_import_mark(&repository).await.unwrap().unwrap();
+        assert_eq!(mark.to_rfc3339(), "2024-01-03T00:00:00+00:00");
+
+        // The next import starts from the mark and only changes what moved upstream
+        server.add_issue(issue_json(3, "New upstream", "open", "2024-01-04T00:00:00Z", &[]));
+        assert_eq!(db.import_github_issues(&github, Some(mark)).await.unwrap(), 1);
+        assert!(server.requests().last().unwrap().contains("since="));
+        assert_eq!(db.get_all_issues().await.unwrap().len(), 3);
+        assert_eq!(
+            db.get_github_import_mark(&repository).await.unwrap().unwrap().to_rfc3339(),
+            "2024-01-04T00:00:00+00:00"
+        );
+
+        // A failed issue holds the mark back so the next import retries it
+        server.add_issue(issue_json(4, "Flaky", "open", "2024-01-05T00:00:00Z", &[]));
+        server.add_comment(4, "bob", "Seen again");
+        server.edit_issue(4, "2024-01-05T00:00:00Z", |_| {});
+        server.add_issue(issue_json(5, "Later", "open", "2024-01-06T00:00:00Z", &[]));
+        server.break_comments(4, true);
+        let mark = db.get_github_import_mark(&repository).await.unwrap();
+        assert_eq!(db.import_github_issues(&github, mark).await.unwrap(), 1);
+        let mark = db.get_github_import_mark(&repository).await.unwrap().unwrap();
+        assert_eq!(mark.to_rfc3339(), "2024-01-04T23:59:59+00:00");
+
+        server.break_comments(4, false);
+        db.import_github_issues(&github, Some(mark)).await.unwrap();
+        let flaky = db.get_issue_by_external_ref("github", "4").await.unwrap().unwrap();
+        assert_eq!(db.get_comments_for_issue(flaky.id.unwrap()).await.unwrap().len(), 1);
+        assert_eq!(
+            db.get_github_import_mark(&repository).await.unwrap().unwrap().to_rfc3339(),
+            "2024-01-06T00:00:00+00:00"
+        );
+    }
+
+    fn offline_github() -> GitHubOps {
+        use crate::git_ops::github_mock::{OWNER, REPO};
+        // Nothing listens on port 1, so every request fails to connect
+        GitHubOps::with_base_url("token".to_string(), OWNER.to_string(), REPO.to_string(), "http://127.0.0.1:1").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_outbox_replays_queued_mutations_in_order() {
+        use crate::git_ops::github_mock::{issue_json, MockGitHub};
+
+        let db = create_test_db().await.unwrap();
+        let offline = offline_github();
+        let mutations = [
+            GitHubMutation::AddComment { issue_number: 1, body: "Picked this up".to_string() },
+            GitHubMutation::AddLabel { issue_number: 1, label: "Progressing".to_string() },
+            GitHubMutation::AddLabel { issue_number: 1, label: "wontfix".to_string() },
+            GitHubMutation::RemoveLabel { issue_number: 1, label: "wontfix".to_string() },
+        ];
+        for mutation in &mutations {
+            assert!(matches!(db.submit_github_mutation(&offline, mutation).await.unwrap(), Delivery::Queued(_)));
+        }
+        let queued = db.get_outbox(&offline.repository()).await.unwrap();
+        assert_eq!(queued.iter().map(|e| e.mutation.clone()).collect::<Vec<_>>(), mutations.to_vec());
+        assert_eq!(queued[0].attempts, 1);
+        assert_eq!(queued[1].attempts, 0, "Later mutations wait behind the first without being tried");
+
+        let server = MockGitHub::start().await;
+        server.add_issue(issue_json(1, "Crash", "open", "2024-01-01T00:00:00Z", &[]));
+        let report = db.flush_outbox(&server.client()).await.unwrap();
+        assert_eq!(report.coalesced, 1, "Adding then removing wontfix only needs the removal");
+        assert_eq!(report.sent, 3);
+        assert!(report.remaining.is_empty());
+        assert!(db.get_outbox(&offline.repository()).await.unwrap().is_empty());
+
+        assert_eq!(server.comments(1)[0]["body"], "Picked this up");
+        let labels = server.issue(1)["labels"].clone();
+        assert_eq!(labels.as_array().unwrap().len(), 1);
+        assert_eq!(labels[0]["name"], "Progressing");
+        let requests = server.requests();
+        assert!(requests[0].contains("/comments"), "Replay keeps the original order: {:?}", requests);
+        assert!(requests[1].contains("/labels"));
+
+        // Once the outbox is empty, mutations go straight through
+        let direct = GitHubMutation::AddComment { issue_number: 1, body: "Done".to_string() };
+        assert_eq!(db.submit_github_mutation(&server.client(), &direct).await.unwrap(), Delivery::Sent);
+    }
+
+    #[tokio::test]
+    async fn test_outbox_keeps_failed_mutations() {
+        use crate::git_ops::github_mock::{issue_json, MockGitHub};
+
+        let db = create_test_db().await.unwrap();
+        let server = MockGitHub::start().await;
+        server.add_issue(issue_json(1, "Exists", "open", "2024-01-01T00:00:00Z", &[]));
+        server.add_issue(issue_json(2, "Flaky", "open", "2024-01-01T00:00:00Z", &[]));
+        server.fail_writes(2, Some(502));
+        let github = server.client();
+        let repository = github.repository();
+
+        for mutation in [
+            GitHubMutation::AddComment { issue_number: 2, body: "Delayed".to_string() },
+            GitHubMutation::AddComment { issue_number: 2, body: "Also delayed".to_string() },
+            GitHubMutation::AddComment { issue_number: 99, body: "Lost".to_string() },
+            GitHubMutation::AddComment { issue_number: 1, body: "Delivered".to_string() },
+        ] {
+            db.enqueue_github_mutation(&repository, &mutation).await.unwrap();
+        }
+
+        let report = db.flush_outbox(&github).await.unwrap();
+        assert_eq!(report.sent, 1, "Other issues are not held up by a failure");
+        assert_eq!(report.remaining.len(), 2);
+        assert_eq!(server.comments(1).len(), 1);
+        assert_eq!(report.rejected.len(), 1, "A missing issue will never take the comment");
+        assert_eq!(report.rejected[0].mutation.issue_number(), 99);
+
+        let remaining = db.get_outbox(&repository).await.unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining[0].attempts, 1);
+        assert!(remaining[0].last_error.as_deref().unwrap().contains("Failed to add comment"));
+        assert_eq!(remaining[1].attempts, 0, "Entries behind a failure on the same issue are not sent out of order");
+
+        db.delete_outbox_entry(remaining[0].id).await.unwrap();
+        assert_eq!(db.get_outbox(&repository).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_submit_github_mutation_queues_only_retryable_failures() {
+        use crate::git_ops::github_mock::{issue_json, MockGitHub};
+
+        let db = create_test_db().await.unwrap();
+        let server = MockGitHub::start().await;
+        server.add_issue(issue_json(1, "Crash", "open", "2024-01-01T00:00:00Z", &[]));
+        let github = server.client();
+        let comment = |issue_number| GitHubMutation::AddComment { issue_number, body: "Hi".to_string() };
+
+        let missing = db.submit_github_mutation(&github, &comment(99)).await.unwrap_err();
+        assert!(matches!(missing, Error::NotFound(_)), "{:?}", missing);
+        server.fail_writes(1, Some(422));
+        let refused = db.submit_github_mutation(&github, &comment(1)).await.unwrap_err();
+        assert!(matches!(refused, Error::Validation(_)), "{:?}", refused);
+        assert!(db.get_outbox(&github.repository()).await.unwrap().is_empty());
+
+        for (number, status) in [(2, 500), (3, 429)] {
+            server.add_issue(issue_json(number, "Busy", "open", "2024-01-01T00:00:00Z", &[]));
+            server.fail_writes(number, Some(status));
+            let delivery = db.submit_github_mutation(&github, &comment(number)).await.unwrap();
+            assert!(matches!(delivery, Delivery::Queued(_)), "{} is worth retrying", status);
+        }
+        assert_eq!(db.get_outbox(&github.repository()).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_issue_events() {
+        let db = create_test_db().await.unwrap().with_actor("alice");
+        db.insert_label(&create_sample_label()).await.unwrap();
+        db.create_default_labels().await.unwrap();
+        let id = db.insert_issue(&create_sample_issue()).await.unwrap();
+
+        let mut issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        issue.title = "Renamed".to_string();
+        issue.labels = vec!["bug".to_string(), "no-such-label".to_string()];
+        db.update_issue(&issue).await.unwrap();
+        db.update_issue(&issue).await.unwrap();
+        db.update_issue_status(id, IssueStatus::InProgress).await.unwrap();
+        db.add_label_to_issue(id, "bug").await.unwrap();
+        db.remove_label_from_issue(id, "bug").await.unwrap();
+        db.remove_label_from_issue(id, "bug").await.unwrap();
+
+        let mut commit = create_sample_commit();
+        commit.message = format!("Fixes #{}", id);
+        db.insert_commit(&commit).await.unwrap();
+        db.link_commit_to_issues(&commit).await.unwrap();
+
+        let comment_id = db.insert_comment(&Comment {
+            id: None,
+            issue_id: id,
+            author: "bob".to_string(),
+            body: "Seen it too".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            external_ref: None,
+            synced_at: None,
+        }).await.unwrap();
+        db.delete_comment(comment_id).await.unwrap();
+        db.delete_issue(id).await.unwrap();
+
+        let events = db.get_issue_events(id).await.unwrap();
+        let summary: Vec<String> = events.iter().map(|e| format!("{}: {}", e.actor, e)).collect();
+        assert_eq!(summary, [
+            "alice: created the issue \"Test Issue\"",
+            "alice: changed title from Test Issue to Renamed",
+            "alice: removed label test-label",
+            "alice: added label bug",
+            "alice: changed status from open to in_progress",
+            "alice: removed label bug",
+            "Test Author: changed status from in_progress to resolved",
+            "bob: commented",
+            "alice: deleted a comment",
+            "alice: deleted the issue",
+        ], "No-op updates and unknown labels leave no events; history outlives the issue");
+        assert_eq!(events[1].old_value.as_deref(), Some("Test Issue"));
+
+        let tampered = db.conn.execute("DELETE FROM issue_events", ()).await;
+        assert!(tampered.is_err(), "The history is append-only");
+        assert_eq!(db.get_issue_events(id).await.unwrap().len(), events.len());
+    }
+
+    #[tokio::test]
+    async fn test_comments() {
+        let db = create_test_db().await.unwrap();
+        let mut issue = create_sample_issue();
+        issue.labels = vec![];
+        let issue_id = db.insert_issue(&issue).await.unwrap();
+
+        let comment = |author: &str, body: &str, minutes: i64| Comment {
+            id: None,
+            issue_id,
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now() + chrono::Duration::minutes(minutes),
+            updated_at: Utc::now() + chrono::Duration::minutes(minutes),
+            external_ref: None,
+            synced_at: None,
+        };
+        let second = db.insert_comment(&comment("bob", "Same here", 5)).await.unwrap();
+        let first = db.insert_comment(&comment("alice", "Can't reproduce", 0)).await.unwrap();
+        assert_eq!(db.insert_comment(&comment("alice", "  ", 0)).await.unwrap_err().kind(), "validation");
+
+        let comments = db.get_comments_for_issue(issue_id).await.unwrap();
+        assert_eq!(comments.iter().map(|c| c.id.unwrap()).collect::<Vec<_>>(), vec![first, second], "Oldest first");
+        assert_eq!(db.count_comments(issue_id).await.unwrap(), 2);
+
+        let mut edited = db.get_comment(first).await.unwrap().unwrap();
+        edited.body = "Reproduced on Linux".to_string();
+        edited.updated_at = Utc::now() + chrono::Duration::hours(1);
+        db.update_comment(&edited).await.unwrap();
+        let stored = db.get_comment(first).await.unwrap().unwrap();
+        assert_eq!(stored.body, "Reproduced on Linux");
+        assert!(stored.updated_at > stored.created_at);
+
+        assert!(db.delete_comment(second).await.unwrap());
+        assert!(!db.delete_comment(second).await.unwrap());
+        assert_eq!(db.count_comments(issue_id).await.unwrap(), 1);
+
+        db.delete_issue(issue_id).await.unwrap();
+        assert!(db.get_comment(first).await.unwrap().is_some(), "Kept while the issue is in the trash");
+        db.purge_trash(None).await.unwrap();
+        assert!(db.get_comment(first).await.unwrap().is_none(), "Comments go with their issue");
+    }
+
+    #[tokio::test]
+    async fn test_github_comment_round_trip() {
+        use crate::git_ops::github_mock::{issue_json, MockGitHub};
+
         let db = create_test_db().await.unwrap();
-        
-        // Create label first
-        let label = create_sample_label();
-        db.insert_label(&label).await.unwrap();
-        
-        // Insert issue
-        let issue = create_sample_issue();
-        let issue_id = db.insert_issue(&issue).await.unwrap();
-        assert!(issue_id > 0, "Issue ID should be positive");
-        
-        // Retrieve issue
-        let retrieved = db.get_issue_by_id(issue_id).await.unwrap();
-        assert!(retrieved.is_some(), "Issue should be retrievable by ID");
-        
-        let retrieved_issue = retrieved.unwrap();
-        assert_eq!(retrieved_issue.title, issue.title);
-        assert_eq!(retrieved_issue.description, issue.description);
-        assert_eq!(retrieved_issue.status.to_string(), issue.status.to_string());
-        assert_eq!(retrieved_issue.priority.to_string(), issue.priority.to_string());
-        assert_eq!(retrieved_issue.assignee, issue.assignee);
-        assert_eq!(retrieved_issue.labels, issue.labels);
+        let server = MockGitHub::start().await;
+        server.add_issue(issue_json(1, "Crash on start", "open", "2024-01-02T00:00:00Z", &[]));
+        let remote_id = server.add_comment(1, "octocat", "Happens on every start");
+        let github = server.client();
+
+        // Importing an issue brings its comments along
+        db.import_github_issues(&github, None).await.unwrap();
+        let issue_id = db.get_issue_by_external_ref("github", "1").await.unwrap().unwrap().id.unwrap();
+        let comments = db.get_comments_for_issue(issue_id).await.unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].author, "octocat");
+        assert_eq!(comments[0].body, "Happens on every start");
+        assert_eq!(comments[0].external_ref.as_ref().unwrap().id, remote_id.to_string());
+        assert_eq!(db.import_github_comments(&github, issue_id, 1).await.unwrap(), 0, "Nothing new");
+
+        // Local comments are posted, local edits are sent, and neither is sent twice
+        let local_id = db.insert_comment(&Comment {
+            id: None,
+            issue_id,
+            author: "alice".to_string(),
+            body: "Fixed in the next build".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            external_ref: None,
+            synced_at: None,
+        }).await.unwrap();
+        assert_eq!(db.export_github_comments(&github, issue_id, 1).await.unwrap(), 1);
+        assert_eq!(server.comments(1)[1]["body"], "Fixed in the next build");
+        assert_eq!(db.export_github_comments(&github, issue_id, 1).await.unwrap(), 0);
+        let mut local = db.get_comment(local_id).await.unwrap().unwrap();
+        assert!(local.external_ref.is_some());
+        local.body = "Fixed in 1.2".to_string();
+        local.updated_at = Utc::now() + chrono::Duration::seconds(5);
+        db.update_comment(&local).await.unwrap();
+        assert_eq!(db.export_github_comments(&github, issue_id, 1).await.unwrap(), 1);
+        assert_eq!(server.comments(1)[1]["body"], "Fixed in 1.2");
+        assert!(server.requests().last().unwrap().starts_with("PATCH"));
+
+        // Edits and deletions on GitHub come back
+        server.edit_comment(remote_id, "Happens on every cold start", "2030-01-01T00:00:00Z");
+        assert_eq!(db.import_github_comments(&github, issue_id, 1).await.unwrap(), 1);
+        assert_eq!(db.get_comments_for_issue(issue_id).await.unwrap()[0].body, "Happens on every cold start");
+        let actors: Vec<String> = db.get_issue_events(issue_id).await.unwrap().into_iter().map(|e| e.actor).collect();
+        assert_eq!(actors, ["github", "octocat", "alice", "atask", "github"], "Pulled changes are attributed to GitHub");
+        server.delete_comment(remote_id);
+        assert_eq!(db.import_github_comments(&github, issue_id, 1).await.unwrap(), 0);
+        let comments = db.get_comments_for_issue(issue_id).await.unwrap();
+        assert_eq!(comments.iter().map(|c| c.id.unwrap()).collect::<Vec<_>>(), vec![local_id]);
+
+        // A comment posted before a later one fails to send is not posted again
+        let early_id = db.insert_comment(&Comment {
+            id: None,
+            issue_id,
+            author: "alice".to_string(),
+            body: "Also on macOS".to_string(),
+            created_at: Utc::now() - chrono::Duration::days(1),
+            updated_at: Utc::now(),
+            external_ref: None,
+            synced_at: None,
+        }).await.unwrap();
+        let mut local = db.get_comment(local_id).await.unwrap().unwrap();
+        server.delete_comment(local.external_ref.as_ref().unwrap().id.parse().unwrap());
+        local.body = "Fixed in 1.3".to_string();
+        local.updated_at = Utc::now() + chrono::Duration::seconds(10);
+        db.update_comment(&local).await.unwrap();
+        for _ in 0..2 {
+            assert!(db.export_github_comments(&github, issue_id, 1).await.is_err());
+        }
+        assert_eq!(server.comments(1).iter().filter(|c| c["body"] == "Also on macOS").count(), 1);
+
+        // Local deletions are sent, and a restored comment is posted as a new one
+        db.delete_comment(early_id).await.unwrap();
+        db.delete_comment(local_id).await.unwrap();
+        assert_eq!(db.export_github_comments(&github, issue_id, 1).await.unwrap(), 2);
+        assert!(server.comments(1).is_empty());
+        assert!(server.requests().iter().any(|r| r.starts_with("DELETE") && r.contains("/comments/")));
+        db.undo_last(1).await.unwrap();
+        assert_eq!(db.export_github_comments(&github, issue_id, 1).await.unwrap(), 1);
+        assert_eq!(server.comments(1)[0]["body"], "Fixed in 1.3");
+        assert_eq!(db.export_github_comments(&github, issue_id, 1).await.unwrap(), 0);
     }
 
     #[tokio::test]
-    async fn test_get_all_issues() {
+    async fn test_board_columns() {
         let db = create_test_db().await.unwrap();
-        
-        // Create label first
-        let label = create_sample_label();
-        db.insert_label(&label).await.unwrap();
-        
-        // Insert multiple issues
-        let mut issue1 = create_sample_issue();
-        issue1.title = "Issue 1".to_string();
-        let mut issue2 = create_sample_issue();
-        issue2.title = "Issue 2".to_string();
-        
-        db.insert_issue(&issue1).await.unwrap();
-        db.insert_issue(&issue2).await.unwrap();
-        
-        // Retrieve all issues
-        let issues = db.get_all_issues().await.unwrap();
-        assert_eq!(issues.len(), 2, "Should retrieve all inserted issues");
+
+        let columns = db.get_board_columns().await.unwrap();
+        let ids: Vec<&str> = columns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["evaluating", "preparing", "progressing", "done"], "Seeded with the default workflow");
+        assert_eq!(columns, crate::kanban::BoardDefinition::defaults().columns);
+        assert_eq!(columns[2].status, Some(IssueStatus::InProgress));
+        assert_eq!(columns[2].label.as_deref(), Some("Progressing"));
+
+        let mut done = columns[3].clone();
+        done.status = Some(IssueStatus::Closed);
+        done.position = -1;
+        db.save_board_column(&done).await.unwrap();
+        db.save_board_column(&BoardColumn {
+            id: "blocked".to_string(),
+            title: "Blocked".to_string(),
+            color: "#fecaca".to_string(),
+            label: Some("blocked".to_string()),
+            status: None,
+            position: 10,
+            wip_limit: Some(3),
+        }).await.unwrap();
+
+        let columns = db.get_board_columns().await.unwrap();
+        let ids: Vec<&str> = columns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["done", "evaluating", "preparing", "progressing", "blocked"]);
+        assert_eq!(columns[0].status, Some(IssueStatus::Closed));
+        assert_eq!(columns[4].status, None);
+        assert_eq!(columns[4].wip_limit, Some(3));
+        assert_eq!(columns[0].wip_limit, None);
+
+        assert!(db.delete_board_column("blocked").await.unwrap());
+        assert!(!db.delete_board_column("blocked").await.unwrap());
+        assert_eq!(db.get_board_columns().await.unwrap().len(), 4);
+
+        let mut unnamed = done.clone();
+        unnamed.id = " ".to_string();
+        assert!(db.save_board_column(&unnamed).await.is_err());
+
+        done.wip_limit = Some(0);
+        assert!(matches!(db.save_board_column(&done).await, Err(Error::Validation(_))));
     }
 
     #[tokio::test]
-    async fn test_issue_label_association() {
+    async fn test_count_issues_in_column_follows_column_for() {
         let db = create_test_db().await.unwrap();
-        
-        // Create multiple labels
-        let mut label1 = create_sample_label();
-        label1.name = "bug".to_string();
-        let mut label2 = create_sample_label();
-        label2.name = "enhancement".to_string();
-        
-        db.insert_label(&label1).await.unwrap();
-        db.insert_label(&label2).await.unwrap();
-        
-        // Create issue with multiple labels
-        let mut issue = create_sample_issue();
-        issue.labels = vec!["bug".to_string(), "enhancement".to_string()];
-        
-        let issue_id = db.insert_issue(&issue).await.unwrap();
-        
-        // Retrieve and verify labels
-        let retrieved_issue = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
-        assert_eq!(retrieved_issue.labels.len(), 2, "Issue should have 2 labels");
-        assert!(retrieved_issue.labels.contains(&"bug".to_string()));
-        assert!(retrieved_issue.labels.contains(&"enhancement".to_string()));
+        for name in ["Evaluating", "Preparing", "Progressing", "Done", "review", "bug"] {
+            db.insert_label(&Label { name: name.to_string(), ..create_sample_label() }).await.unwrap();
+        }
+        let mut board = crate::kanban::BoardDefinition::defaults();
+        board.columns.insert(3, BoardColumn {
+            id: "review".to_string(),
+            title: "Review".to_string(),
+            color: "#e9d5ff".to_string(),
+            label: Some("review".to_string()),
+            status: None,
+            position: 3,
+            wip_limit: None,
+        });
+
+        let issues = [
+            (IssueStatus::Open, vec![]),
+            (IssueStatus::Open, vec!["bug"]),
+            (IssueStatus::Open, vec!["progressing"]),
+            (IssueStatus::Open, vec!["Preparing", "Review"]),
+            (IssueStatus::Resolved, vec!["Evaluating"]),
+            (IssueStatus::InProgress, vec![]),
+            (IssueStatus::Resolved, vec![]),
+            (IssueStatus::Closed, vec!["Progressing"]),
+            (IssueStatus::Open, vec!["Done"]),
+        ];
+        for (status, labels) in issues {
+            let mut issue = create_sample_issue();
+            issue.status = status;
+            issue.labels = labels.into_iter().map(String::from).collect();
+            db.insert_issue(&issue).await.unwrap();
+        }
+        let trashed = db.insert_issue(&Issue { labels: vec!["Done".to_string()], ..create_sample_issue() }).await.unwrap();
+        db.delete_issue(trashed).await.unwrap();
+
+        let check = |board: crate::kanban::BoardDefinition| {
+            let db = &db;
+            async move {
+                let all = db.get_all_issues().await.unwrap();
+                for column in &board.columns {
+                    let expected = all.iter()
+                        .filter(|i| board.column_for(&i.labels, &i.status).is_some_and(|c| c.id == column.id))
+                        .count();
+                    let counted = db.count_issues_in_column(&board, &column.id).await.unwrap();
+                    assert_eq!(counted, expected, "{}", column.id);
+                }
+            }
+        };
+        check(board.clone()).await;
+        assert_eq!(db.count_issues_in_column(&board, "done").await.unwrap(), 2);
+
+        // With a column for closed issues, and without any status columns
+        board.columns[4].status = Some(IssueStatus::Closed);
+        check(board.clone()).await;
+        for column in &mut board.columns {
+            column.status = None;
+        }
+        check(board).await;
     }
 
     #[tokio::test]
-    async fn test_update_issue_status() {
+    async fn test_card_ranks() {
         let db = create_test_db().await.unwrap();
-        
-        // Create label first
-        let label = create_sample_label();
-        db.insert_label(&label).await.unwrap();
-        
-        // Insert issue
-        let issue = create_sample_issue();
-        let issue_id = db.insert_issue(&issue).await.unwrap();
-        
-        // Update status
-        db.update_issue_status(issue_id, IssueStatus::InProgress).await.unwrap();
-        
-        // Verify update
-        let updated_issue = db.get_issue_by_id(issue_id).await.unwrap().unwrap();
-        assert_eq!(updated_issue.status.to_string(), "in_progress");
+        for _ in 0..3 {
+            db.insert_issue(&create_sample_issue()).await.unwrap();
+        }
+        db.set_card_rank(1, "preparing", "m").await.unwrap();
+        db.set_card_rank(2, "preparing", "c").await.unwrap();
+        db.set_card_rank(3, "done", "i").await.unwrap();
+
+        let ranks = db.get_card_ranks().await.unwrap();
+        let order = |ranks: &[CardRank], column: &str| ranks.iter()
+            .filter(|r| r.column_id == column)
+            .map(|r| r.issue_id)
+            .collect::<Vec<_>>();
+        assert_eq!(order(&ranks, "preparing"), vec![2, 1]);
+
+        // Ranking a card in another column moves it there
+        db.set_card_rank(2, "done", &rank::between(Some("i"), None)).await.unwrap();
+        let ranks = db.get_card_ranks().await.unwrap();
+        assert_eq!(order(&ranks, "preparing"), vec![1]);
+        assert_eq!(order(&ranks, "done"), vec![3, 2]);
+
+        // Placing a card is recorded, and undoing it puts the card back where it was
+        let events = db.get_issue_events(2).await.unwrap();
+        let moved = events.last().unwrap();
+        assert_eq!(moved.field, EventField::Rank);
+        assert_eq!(moved.old_value.as_deref(), Some("c in preparing"));
+        assert_eq!(moved.to_string(), "placed the card in done");
+        db.set_card_rank(2, "done", &rank::between(Some("i"), None)).await.unwrap();
+        assert_eq!(db.get_issue_events(2).await.unwrap().len(), events.len(), "Staying put records nothing");
+        db.undo(moved.operation_id.unwrap()).await.unwrap();
+        assert_eq!(order(&db.get_card_ranks().await.unwrap(), "preparing"), vec![2, 1]);
+        let first = db.get_issue_events(3).await.unwrap().last().unwrap().operation_id.unwrap();
+        db.undo(first).await.unwrap();
+        assert_eq!(order(&db.get_card_ranks().await.unwrap(), "done"), Vec::<i64>::new(), "Never placed again");
+
+        db.delete_issue(1).await.unwrap();
+        assert!(db.get_card_ranks().await.unwrap().iter().all(|r| r.issue_id != 1));
     }
 
     #[tokio::test]
@@ -1133,6 +4138,223 @@ mod tests {
         assert!(deleted.is_none(), "Issue should not exist after deletion");
     }
 
+    #[tokio::test]
+    async fn test_trash() {
+        let db = create_test_db().await.unwrap();
+        db.insert_label(&create_sample_label()).await.unwrap();
+        let kept = db.insert_issue(&create_sample_issue()).await.unwrap();
+        let mut remote = create_sample_issue();
+        remote.external_ref = Some(ExternalRef::github(7, None));
+        let trashed = db.insert_issue(&remote).await.unwrap();
+
+        db.delete_issue(trashed).await.unwrap();
+        assert_eq!(db.get_all_issues().await.unwrap().len(), 1);
+        assert!(db.get_issue_by_external_ref("github", "7").await.unwrap().is_none());
+        let trash = db.get_trashed_issues().await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].issue.labels, vec!["test-label".to_string()], "Labels are kept in the trash");
+
+        remote.updated_at = Utc::now() + chrono::Duration::minutes(1);
+        assert_eq!(db.upsert_external_issue(&remote).await.unwrap(), UpsertOutcome::Trashed, "Sync doesn't bring it back");
+        assert_eq!(db.get_all_issues().await.unwrap().len(), 1);
+
+        assert!(db.restore_issue(trashed).await.unwrap());
+        assert!(!db.restore_issue(kept).await.unwrap(), "Only issues in the trash can be restored");
+        assert_eq!(db.get_all_issues().await.unwrap().len(), 2);
+
+        db.delete_issue(trashed).await.unwrap();
+        assert_eq!(db.purge_trash(Some(&[kept])).await.unwrap_err().kind(), "not_found");
+        assert_eq!(db.purge_trash(Some(&[trashed])).await.unwrap(), 1);
+        assert!(db.get_trashed_issues().await.unwrap().is_empty());
+        assert!(!db.restore_issue(trashed).await.unwrap());
+        let fields: Vec<EventField> = db.get_issue_events(trashed).await.unwrap().iter().map(|e| e.field).collect();
+        assert_eq!(fields, [EventField::Created, EventField::Deleted, EventField::Restored, EventField::Deleted, EventField::Purged]);
+    }
+
+    #[tokio::test]
+    async fn test_undo() {
+        let db = create_test_db().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        let mut issue = create_sample_issue();
+        issue.labels = vec!["bug".to_string()];
+        let id = db.insert_issue(&issue).await.unwrap();
+
+        // Everything inside `operation` is undone together
+        let ((), operation) = db.operation(async {
+            db.update_issue_status(id, IssueStatus::InProgress).await?;
+            db.remove_label_from_issue(id, "bug").await?;
+            db.add_label_to_issue(id, "enhancement").await
+        }).await.unwrap();
+        let operation = operation.unwrap();
+        assert_eq!(db.get_operation(operation).await.unwrap().unwrap().events.len(), 3);
+        let undo = db.undo(operation).await.unwrap();
+        assert_eq!(undo.undoes, Some(operation));
+        let restored = db.get_issue_by_id(id).await.unwrap().unwrap();
+        assert_eq!(restored.status, IssueStatus::Open);
+        assert_eq!(restored.labels, vec!["bug".to_string()]);
+        assert_eq!(db.undo(operation).await.unwrap_err().kind(), "conflict", "Only undone once");
+        assert_eq!(db.undo(9999).await.unwrap_err().kind(), "not_found");
+
+        let ((), nothing) = db.operation(async { Ok::<_, Error>(()) }).await.unwrap();
+        assert_eq!(nothing, None, "Operations that change nothing aren't kept");
+
+        // A field changed again since can't be undone without undoing that first
+        db.update_issue_status(id, IssueStatus::Resolved).await.unwrap();
+        db.update_issue_status(id, IssueStatus::Closed).await.unwrap();
+        let undone = db.undo_last(1).await.unwrap();
+        assert_eq!(undone[0].events[0].new_value.as_deref(), Some("closed"));
+        let resolved = undone[0].id - 1;
+        db.update_issue_status(id, IssueStatus::Closed).await.unwrap();
+        assert_eq!(db.undo(resolved).await.unwrap_err().kind(), "conflict");
+        assert_eq!(db.get_issue_by_id(id).await.unwrap().unwrap().status, IssueStatus::Closed, "A failed undo changes nothing");
+
+        // An undo that fails partway puts back what it had already reversed
+        let ((), both) = db.operation(async {
+            db.update_issue_status(id, IssueStatus::Open).await?;
+            let mut issue = db.get_issue_by_id(id).await?.unwrap();
+            issue.title = "Renamed".to_string();
+            db.update_issue(&issue).await
+        }).await.unwrap();
+        db.update_issue_status(id, IssueStatus::InProgress).await.unwrap();
+        assert_eq!(db.undo(both.unwrap()).await.unwrap_err().kind(), "conflict");
+        let issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        assert_eq!((issue.title.as_str(), issue.status), ("Renamed", IssueStatus::InProgress));
+        db.update_issue_status(id, IssueStatus::Open).await.unwrap();
+        db.undo(both.unwrap()).await.expect("Still undoable once the conflict is gone");
+        assert_eq!(db.get_issue_by_id(id).await.unwrap().unwrap().status, IssueStatus::Closed);
+
+        // Deletes and comments
+        let comment_id = db.insert_comment(&Comment {
+            id: None,
+            issue_id: id,
+            author: "bob".to_string(),
+            body: "Seen it too".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            external_ref: None,
+            synced_at: None,
+        }).await.unwrap();
+        db.delete_comment(comment_id).await.unwrap();
+        db.delete_issue(id).await.unwrap();
+        assert_eq!(db.undo_last(2).await.unwrap().len(), 2);
+        assert!(db.get_issue_by_id(id).await.unwrap().is_some());
+        assert_eq!(db.get_comment(comment_id).await.unwrap().unwrap().body, "Seen it too");
+
+        // Purges are for good
+        db.delete_issue(id).await.unwrap();
+        db.purge_trash(None).await.unwrap();
+        assert_eq!(db.undo_last(1).await.unwrap_err().kind(), "conflict", "The purge is skipped, and its delete can't be undone");
+    }
+
+    #[tokio::test]
+    async fn test_issue_dependencies() {
+        let db = create_test_db().await.unwrap();
+        let new_issue = |title: &str, priority: IssuePriority| Issue {
+            title: title.to_string(),
+            priority,
+            labels: vec![],
+            ..create_sample_issue()
+        };
+        let schema = db.insert_issue(&new_issue("Design schema", IssuePriority::Medium)).await.unwrap();
+        let api = db.insert_issue(&new_issue("Build API", IssuePriority::Critical)).await.unwrap();
+        let ui = db.insert_issue(&new_issue("Build UI", IssuePriority::High)).await.unwrap();
+        let docs = db.insert_issue(&new_issue("Write docs", IssuePriority::Low)).await.unwrap();
+
+        assert!(db.link_issues(schema, DependencyKind::Blocks, api).await.unwrap());
+        assert!(db.link_issues(ui, DependencyKind::BlockedBy, api).await.unwrap());
+        assert!(!db.link_issues(api, DependencyKind::Blocks, ui).await.unwrap(), "Already linked, from the other side");
+        assert!(db.link_issues(docs, DependencyKind::RelatesTo, ui).await.unwrap());
+        assert!(db.link_issues(ui, DependencyKind::RelatesTo, docs).await.is_ok(), "Relates-to may go both ways");
+
+        let links = db.get_issue_links(api).await.unwrap();
+        let summary: Vec<String> = links.iter().map(|link| format!("{} #{}", link.kind, link.issue_id)).collect();
+        assert_eq!(summary, [format!("blocked-by #{}", schema), format!("blocks #{}", ui)]);
+
+        let cycle = db.link_issues(ui, DependencyKind::Blocks, schema).await.unwrap_err();
+        assert_eq!(cycle.kind(), "conflict");
+        assert!(cycle.to_string().contains(&format!("#{} blocks #{} blocks #{} blocks #{}", schema, api, ui, schema)), "{}", cycle);
+        assert!(db.link_issues(docs, DependencyKind::Duplicates, ui).await.is_ok());
+        assert_eq!(db.link_issues(ui, DependencyKind::Duplicates, docs).await.unwrap_err().kind(), "conflict");
+        assert_eq!(db.link_issues(ui, DependencyKind::Blocks, ui).await.unwrap_err().kind(), "validation");
+        assert_eq!(db.link_issues(ui, DependencyKind::Blocks, 999).await.unwrap_err().kind(), "not_found");
+
+        // Only unblocked open work is ready, most urgent first
+        let ready = |issues: Vec<Issue>| issues.iter().map(|issue| issue.id.unwrap()).collect::<Vec<_>>();
+        assert_eq!(ready(db.get_ready_issues().await.unwrap()), vec![schema, docs]);
+        assert_eq!(db.get_open_blockers(ui).await.unwrap(), vec![api]);
+        db.update_issue_status(schema, IssueStatus::Resolved).await.unwrap();
+        assert_eq!(ready(db.get_ready_issues().await.unwrap()), vec![api, docs]);
+        db.delete_issue(api).await.unwrap();
+        assert_eq!(ready(db.get_ready_issues().await.unwrap()), vec![ui, docs], "Blockers in the trash don't count");
+        db.restore_issue(api).await.unwrap();
+
+        // Links are recorded on both issues and can be undone
+        assert!(db.unlink_issues(api, DependencyKind::BlockedBy, schema).await.unwrap());
+        assert!(!db.unlink_issues(api, DependencyKind::BlockedBy, schema).await.unwrap());
+        let events = db.get_issue_events(schema).await.unwrap();
+        assert_eq!(events.last().unwrap().to_string(), format!("removed link: blocks #{}", api));
+        db.undo_last(1).await.unwrap();
+        assert_eq!(db.get_issue_links(schema).await.unwrap()[0].issue_id, api);
+
+        db.delete_issue(api).await.unwrap();
+        db.purge_trash(None).await.unwrap();
+        assert!(db.get_issue_links(schema).await.unwrap().is_empty(), "Purged issues take their links along");
+    }
+
+    #[tokio::test]
+    async fn test_subtasks() {
+        let db = create_test_db().await.unwrap();
+        let new_issue = |title: &str, parent_id: Option<i64>| Issue {
+            title: title.to_string(),
+            parent_id,
+            labels: vec![],
+            ..create_sample_issue()
+        };
+        let epic = db.insert_issue(&new_issue("Epic", None)).await.unwrap();
+        let story = db.insert_issue(&new_issue("Story", Some(epic))).await.unwrap();
+        let tasks: Vec<i64> = [
+            db.insert_issue(&new_issue("Task 1", Some(story))).await.unwrap(),
+            db.insert_issue(&new_issue("Task 2", Some(story))).await.unwrap(),
+            db.insert_issue(&new_issue("Task 3", Some(story))).await.unwrap(),
+        ].into();
+        let chore = db.insert_issue(&new_issue("Chore", Some(epic))).await.unwrap();
+        assert_eq!(db.insert_issue(&new_issue("Orphan", Some(999))).await.unwrap_err().kind(), "validation");
+
+        let children: Vec<i64> = db.get_children(epic).await.unwrap().iter().map(|c| c.id.unwrap()).collect();
+        assert_eq!(children, vec![story, chore], "Only direct children");
+        assert_eq!(db.get_ancestor_ids(tasks[0]).await.unwrap(), vec![story, epic]);
+
+        // Progress counts every descendant
+        db.update_issue_status(tasks[0], IssueStatus::Resolved).await.unwrap();
+        db.update_issue_status(tasks[1], IssueStatus::Closed).await.unwrap();
+        db.update_issue_status(chore, IssueStatus::Closed).await.unwrap();
+        assert_eq!(db.get_subtask_progress(epic).await.unwrap(), Some(Progress { done: 3, total: 5 }));
+        assert_eq!(db.get_subtask_progress(story).await.unwrap(), Some(Progress { done: 2, total: 3 }));
+        assert_eq!(db.get_subtask_progress(chore).await.unwrap(), None);
+        db.delete_issue(tasks[2]).await.unwrap();
+        assert_eq!(db.get_subtask_progress(story).await.unwrap(), Some(Progress { done: 2, total: 2 }), "The trash doesn't count");
+
+        // No cycles
+        let mut epic_issue = db.get_issue_by_id(epic).await.unwrap().unwrap();
+        epic_issue.parent_id = Some(tasks[0]);
+        assert_eq!(db.update_issue(&epic_issue).await.unwrap_err().kind(), "conflict");
+        epic_issue.parent_id = Some(epic);
+        assert_eq!(db.update_issue(&epic_issue).await.unwrap_err().kind(), "validation");
+
+        // Moving a sub-task is recorded and can be undone
+        let mut chore_issue = db.get_issue_by_id(chore).await.unwrap().unwrap();
+        chore_issue.parent_id = Some(story);
+        db.update_issue(&chore_issue).await.unwrap();
+        let events = db.get_issue_events(chore).await.unwrap();
+        assert_eq!(events.last().unwrap().to_string(), format!("moved from under #{} to under #{}", epic, story));
+        db.undo_last(1).await.unwrap();
+        assert_eq!(db.get_issue_by_id(chore).await.unwrap().unwrap().parent_id, Some(epic));
+
+        db.delete_issue(story).await.unwrap();
+        db.purge_trash(Some(&[story])).await.unwrap();
+        assert_eq!(db.get_issue_by_id(tasks[0]).await.unwrap().unwrap().parent_id, None, "Purged parents let go of their sub-tasks");
+    }
+
     #[tokio::test]
     async fn test_issue_status_string_conversion() {
         assert_eq!(IssueStatus::Open.to_string(), "open");
@@ -1145,6 +4367,11 @@ mod tests {
         assert!("resolved".parse::<IssueStatus>().is_ok());
         assert!("closed".parse::<IssueStatus>().is_ok());
         assert!("invalid".parse::<IssueStatus>().is_err());
+
+        // JSON keeps the variant names but also takes the CLI spelling
+        assert_eq!(serde_json::to_string(&IssueStatus::InProgress).unwrap(), "\"InProgress\"");
+        assert_eq!(serde_json::from_str::<IssueStatus>("\"in_progress\"").unwrap(), IssueStatus::InProgress);
+        assert_eq!(serde_json::from_str::<IssueStatus>("\"InProgress\"").unwrap(), IssueStatus::InProgress);
     }
 
     #[tokio::test]
@@ -1189,6 +4416,370 @@ mod tests {
         assert_eq!(issues.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_populate_from_git_history() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git").args(args).current_dir(repo_path).output()
+        };
+        if git(&["init"]).is_err() {
+            println!("Warning: git is not available, skipping");
+            return;
+        }
+        git(&["config", "user.name", "Pipe | Name"]).unwrap();
+        git(&["config", "user.email", "pipe@example.com"]).unwrap();
+        std::fs::write(repo_path.join("a.txt"), "one\ntwo\n").unwrap();
+        git(&["add", "."]).unwrap();
+        git(&["commit", "-m", "Subject line\n\nBody line"]).unwrap();
+
+        let db = create_test_db().await.unwrap();
+        let path = repo_path.to_str().unwrap();
+        assert_eq!(db.populate_from_git_history(Some(path)).await.unwrap(), 1);
+        // Re-importing an unchanged repository is a no-op
+        assert_eq!(db.populate_from_git_history(Some(path)).await.unwrap(), 0);
+
+        let commits = db.get_all_commits().await.unwrap();
+        assert_eq!(commits.len(), 1);
+        let commit = &commits[0];
+        assert_eq!(
+            db.get_import_tip(&branch_ref(repo_path)).await.unwrap(),
+            Some(commit.hash.clone()),
+            "The high-water mark should point at the imported tip"
+        );
+        assert_eq!(commit.author_name, "Pipe | Name");
+        assert_eq!(commit.message, "Subject line\n\nBody line");
+        assert!(commit.parent_hashes.is_empty());
+        assert_eq!(commit.file_stats, vec![FileChange {
+            path: "a.txt".to_string(),
+            insertions: 2,
+            deletions: 0,
+            binary: false,
+        }]);
+
+        // Only commits after the high-water mark are walked and imported
+        std::fs::write(repo_path.join("b.txt"), "three\n").unwrap();
+        git(&["add", "."]).unwrap();
+        git(&["commit", "-m", "Second"]).unwrap();
+        assert_eq!(db.populate_from_git_history(Some(path)).await.unwrap(), 1);
+        assert_eq!(db.get_all_commits().await.unwrap().len(), 2);
+
+        // Issues resolved by one import change together, in a single operation
+        let first = db.insert_issue(&create_sample_issue()).await.unwrap();
+        let second = db.insert_issue(&create_sample_issue()).await.unwrap();
+        for id in [first, second] {
+            std::fs::write(repo_path.join("b.txt"), id.to_string()).unwrap();
+            git(&["commit", "-am", &format!("Fixes #{}", id)]).unwrap();
+        }
+        assert_eq!(db.populate_from_git_history(Some(path)).await.unwrap(), 2);
+        let resolved = db.get_issue_events(second).await.unwrap().pop().unwrap();
+        assert_eq!(resolved.new_value.as_deref(), Some("resolved"));
+        let operation = db.get_operation(resolved.operation_id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(operation.events.len(), 2);
+        db.undo(operation.id).await.unwrap();
+        assert_eq!(db.get_issue_by_id(first).await.unwrap().unwrap().status, IssueStatus::Open);
+    }
+
+    #[tokio::test]
+    async fn test_populate_from_git_history_tolerates_preexisting_commits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo_path = temp_dir.path();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git").args(args).current_dir(repo_path).output()
+        };
+        if git(&["init"]).is_err() {
+            println!("Warning: git is not available, skipping");
+            return;
+        }
+        git(&["config", "user.name", "Test User"]).unwrap();
+        git(&["config", "user.email", "test@example.com"]).unwrap();
+        std::fs::write(repo_path.join("a.txt"), "one\n").unwrap();
+        git(&["add", "."]).unwrap();
+        git(&["commit", "-m", "First"]).unwrap();
+
+        let db = create_test_db().await.unwrap();
+        let path = repo_path.to_str().unwrap();
+        let existing = GitOps::new_from_path(path).unwrap().get_commits(None).unwrap();
+
+        // A commit stored before any import state existed must not abort the import
+        db.insert_commit(&GitCommit::from(existing[0].clone())).await.unwrap();
+        assert_eq!(db.populate_from_git_history(Some(path)).await.unwrap(), 0);
+        assert!(db.get_import_tip(&branch_ref(repo_path)).await.unwrap().is_some());
+    }
+
+    /// Full name of the branch HEAD points at in a test repository
+    fn branch_ref(repo_path: &std::path::Path) -> String {
+        GitOps::new_from_path(repo_path).unwrap().resolve_ref("HEAD").unwrap().0
+    }
+
+    #[test]
+    fn test_parse_issue_references() {
+        use IssueReferenceKind::*;
+        let refs = |message: &str| -> Vec<(i64, IssueReferenceKind)> {
+            parse_issue_references(message).into_iter().map(|r| (r.issue_id, r.kind)).collect()
+        };
+
+        assert_eq!(refs("Fixes #12"), vec![(12, Closes)]);
+        assert_eq!(refs("closes: #3 and resolved #4"), vec![(3, Closes), (4, Closes)]);
+        assert_eq!(refs("Fix crash (fixes #5)"), vec![(5, Closes)]);
+        assert_eq!(refs("Refs #7, see also #8"), vec![(7, References), (8, References)]);
+        assert_eq!(refs("Mention #9 then fix it\n\nFixes #9"), vec![(9, Closes)]);
+        assert!(refs("No refs here, abc#1, &#39; or #12abc or #").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_link_commit_to_issues() {
+        let db = create_test_db().await.unwrap();
+        let mut issue = create_sample_issue();
+        issue.labels = vec![];
+        let fixed_id = db.insert_issue(&issue).await.unwrap();
+        let mentioned_id = db.insert_issue(&issue).await.unwrap();
+
+        let mut commit = create_sample_commit();
+        commit.message = format!("Fix the bug\n\nFixes #{}, refs #{}, ignores #999", fixed_id, mentioned_id);
+        db.insert_commit(&commit).await.unwrap();
+
+        let linked = db.link_commit_to_issues(&commit).await.unwrap();
+        assert_eq!(linked.len(), 2, "Unknown issues should be skipped");
+
+        let fixed = db.get_issue_by_id(fixed_id).await.unwrap().unwrap();
+        assert_eq!(fixed.status.to_string(), "resolved");
+        let mentioned = db.get_issue_by_id(mentioned_id).await.unwrap().unwrap();
+        assert_eq!(mentioned.status.to_string(), "open");
+
+        let commits = db.get_commits_for_issue(fixed_id).await.unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].commit.hash, commit.hash);
+        assert_eq!(commits[0].commit.insertions, commit.insertions);
+        assert_eq!(commits[0].kind, IssueReferenceKind::Closes);
+        assert_eq!(db.get_commits_for_issue(mentioned_id).await.unwrap()[0].kind, IssueReferenceKind::References);
+
+        // For issues imported from GitHub, #N is the GitHub number
+        let imported_id = db.insert_issue(&Issue {
+            external_ref: Some(ExternalRef { source: "github".to_string(), id: fixed_id.to_string(), url: None }),
+            ..issue.clone()
+        }).await.unwrap();
+        let mut commit = create_sample_commit();
+        commit.hash = "feedface".to_string();
+        commit.message = format!("Fixes #{}, refs #{}", fixed_id, imported_id);
+        db.insert_commit(&commit).await.unwrap();
+        let linked = db.link_commit_to_issues(&commit).await.unwrap();
+        assert_eq!(linked.len(), 1, "#{} is GitHub issue {}, not a local id", imported_id, fixed_id);
+        assert_eq!(linked[0].issue_id, imported_id);
+        assert_eq!(db.get_issue_by_id(imported_id).await.unwrap().unwrap().status.to_string(), "resolved");
+        assert_eq!(db.get_commits_for_issue(fixed_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schema_upgrade_adds_commit_columns() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("old.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Simulate a database created before parent_hashes/file_stats existed
+        {
+            let db = Builder::new_local(db_path).build().await.unwrap();
+            let conn = db.connect().unwrap();
+            conn.execute(
+                "CREATE TABLE commits (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    hash TEXT UNIQUE NOT NULL,
+                    author_name TEXT NOT NULL,
+                    author_email TEXT NOT NULL,
+                    commit_date DATETIME NOT NULL,
+                    message TEXT NOT NULL,
+                    files_changed TEXT NOT NULL,
+                    insertions INTEGER DEFAULT 0,
+                    deletions INTEGER DEFAULT 0,
+                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                )",
+                (),
+            ).await.unwrap();
+            conn.execute(
+                "INSERT INTO commits (hash, author_name, author_email, commit_date, message, files_changed)
+                 VALUES ('oldhash', 'Old', 'old@example.com', '2024-01-01T00:00:00+00:00', 'old', '[]')",
+                (),
+            ).await.unwrap();
+        }
+
+        let db = TaskDatabase::new(db_path).await.unwrap();
+        let commit = db.get_commit_by_hash("oldhash").await.unwrap().unwrap();
+        assert!(commit.parent_hashes.is_empty());
+        assert!(commit.file_stats.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let db = create_test_db().await.unwrap();
+        let issue = |title: &str, description: Option<&str>| Issue {
+            title: title.to_string(),
+            description: description.map(str::to_string),
+            labels: vec![],
+            ..create_sample_issue()
+        };
+        let login = db.insert_issue(&issue("Login retries forever", Some("The auth client never gives up"))).await.unwrap();
+        let docs = db.insert_issue(&issue("Document the CLI", Some("Mention login and logout"))).await.unwrap();
+        db.insert_issue(&issue("Unrelated", None)).await.unwrap();
+
+        let mut commit = create_sample_commit();
+        commit.message = "Cap the number of retries".to_string();
+        commit.files_changed = vec!["src/auth/client.rs".to_string()];
+        db.insert_commit(&commit).await.unwrap();
+
+        // Titles outrank descriptions, and words match by prefix and stem
+        let results = db.search("login", 10).await.unwrap();
+        let ids: Vec<_> = results.issues.iter().map(|hit| hit.issue.id.unwrap()).collect();
+        assert_eq!(ids, vec![login, docs]);
+        assert!(results.issues[0].score > results.issues[1].score);
+        assert_eq!(results.issues[0].snippet.highlight("[", "]", str::to_string), "[Login] retries forever");
+        assert!(results.commits.is_empty());
+        assert_eq!(db.search("log", 10).await.unwrap().issues.len(), 2);
+
+        let results = db.search("retry", 10).await.unwrap();
+        assert_eq!(results.issues.len(), 1);
+        assert_eq!(results.commits[0].commit.hash, commit.hash);
+        assert_eq!(db.search("auth client", 10).await.unwrap().commits.len(), 1, "File paths are searchable");
+        assert_eq!(db.search("login", 1).await.unwrap().issues.len(), 1);
+
+        // Updates and deletes reach the index
+        let mut updated = db.get_issue_by_id(docs).await.unwrap().unwrap();
+        updated.description = Some("Mention sign-in".to_string());
+        db.update_issue(&updated).await.unwrap();
+        assert_eq!(db.search("login", 10).await.unwrap().issues.len(), 1);
+        db.delete_issue(login).await.unwrap();
+        assert!(db.search("login", 10).await.unwrap().is_empty());
+
+        // Search text can't inject FTS5 syntax
+        assert_eq!(db.search("sign-in \" AND", 10).await.unwrap().issues.len(), 0);
+        assert_eq!(db.search("sign-in \"", 10).await.unwrap().issues.len(), 1);
+        assert_eq!(db.search(" - ", 10).await.unwrap_err().kind(), "validation");
+    }
+
+    #[tokio::test]
+    async fn test_search_index_covers_existing_rows() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("search.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Roll back to version 12, before issues and commits were indexed
+        {
+            let db = TaskDatabase::new(db_path).await.unwrap();
+            for table in ["issues", "commits"] {
+                for trigger in ["insert", "update", "delete"] {
+                    db.conn.execute(&format!("DROP TRIGGER {}_fts_{}", table, trigger), ()).await.unwrap();
+                }
+                db.conn.execute(&format!("DROP TABLE {}_fts", table), ()).await.unwrap();
+            }
+            db.conn.execute("DELETE FROM schema_version WHERE version > 12", ()).await.unwrap();
+            db.insert_issue(&create_sample_issue()).await.unwrap();
+            db.insert_commit(&create_sample_commit()).await.unwrap();
+        }
+
+        let db = TaskDatabase::new(db_path).await.unwrap();
+        let issue = create_sample_issue();
+        let results = db.search(&issue.title, 10).await.unwrap();
+        assert_eq!(results.issues.len(), 1);
+        let commit = create_sample_commit();
+        assert_eq!(db.search(&commit.message, 10).await.unwrap().commits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_card_positions_become_ranks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("positions.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Roll back to version 10, when cards had integer positions
+        {
+            let db = TaskDatabase::new(db_path).await.unwrap();
+            for _ in 0..3 {
+                db.insert_issue(&create_sample_issue()).await.unwrap();
+            }
+            for sql in [
+                "CREATE TABLE card_positions (issue_id INTEGER PRIMARY KEY, column_id TEXT NOT NULL, position INTEGER NOT NULL)",
+                "INSERT INTO card_positions VALUES (1, 'preparing', 10), (2, 'preparing', 2), (3, 'preparing', 0)",
+                "DELETE FROM schema_version WHERE version > 10",
+            ] {
+                db.conn.execute(sql, ()).await.unwrap();
+            }
+        }
+
+        let db = TaskDatabase::new(db_path).await.unwrap();
+        let ranks = db.get_card_ranks().await.unwrap();
+        assert_eq!(ranks.iter().map(|r| r.issue_id).collect::<Vec<_>>(), vec![3, 2, 1]);
+        let rank = rank::between(Some(&ranks[1].rank), Some(&ranks[2].rank));
+        assert!(ranks[1].rank < rank && rank < ranks[2].rank);
+    }
+
+    #[tokio::test]
+    async fn test_migrations_are_recorded_and_not_reapplied() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("versioned.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // A fresh database reports every migration as pending until it is migrated
+        let fresh = TaskDatabase::open(db_path).await.unwrap();
+        assert_eq!(fresh.schema_version().await.unwrap(), 0);
+        assert_eq!(fresh.pending_migrations().await.unwrap().len(), MIGRATIONS.len());
+        drop(fresh);
+
+        let db = TaskDatabase::new(db_path).await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), migrations::latest_version());
+        assert!(db.pending_migrations().await.unwrap().is_empty());
+        assert!(db.migrate().await.unwrap().is_empty(), "Nothing left to apply");
+    }
+
+    #[tokio::test]
+    async fn test_migration_recovers_github_numbers_from_titles() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("legacy.db");
+        let db_path = db_path.to_str().unwrap();
+
+        // Apply the schema as it was before external references existed
+        {
+            let db = TaskDatabase::open(db_path).await.unwrap();
+            for migration in MIGRATIONS.iter().filter(|m| m.version < 5) {
+                db.apply_migration(migration).await.unwrap();
+            }
+            for title in ["#7: Imported from gh", "#7: Imported twice", "#12abc: Not a number", "Local issue"] {
+                db.conn.execute(
+                    "INSERT INTO issues (title) VALUES (?)",
+                    libsql::params![title],
+                ).await.unwrap();
+            }
+        }
+
+        let db = TaskDatabase::new(db_path).await.unwrap();
+        let recovered = db.get_issue_by_external_ref("github", "7").await.unwrap().unwrap();
+        assert_eq!(recovered.title, "Imported from gh");
+        assert_eq!(recovered.id, Some(1), "The oldest copy keeps the reference");
+
+        let untouched: Vec<String> = db.get_all_issues().await.unwrap().into_iter()
+            .filter(|issue| issue.external_ref.is_none())
+            .map(|issue| issue.title)
+            .collect();
+        assert_eq!(untouched.len(), 3);
+        assert!(untouched.contains(&"#12abc: Not a number".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_version_is_refused() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("future.db");
+        let db_path = db_path.to_str().unwrap();
+
+        {
+            let db = TaskDatabase::new(db_path).await.unwrap();
+            db.conn.execute(
+                "INSERT INTO schema_version (version, description) VALUES (?, 'from the future')",
+                libsql::params![migrations::latest_version() + 1],
+            ).await.unwrap();
+        }
+
+        let err = TaskDatabase::new(db_path).await.err().expect("Newer databases must be refused");
+        assert!(err.to_string().contains("newer than this build"), "Unexpected error: {}", err);
+    }
+
     #[tokio::test]
     async fn test_nonexistent_commit_retrieval() {
         let db = create_test_db().await.unwrap();
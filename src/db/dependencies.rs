@@ -0,0 +1,169 @@
+//! Dependencies between issues.
+//!
+//! `issue_dependencies` (migration 17) stores one row per link, read as
+//! "source kind target": #3 blocks #5, #7 duplicates #2, #4 relates-to #6.
+//! The inverse kinds are stored as the row the other way round, and relates-to
+//! with the lower id first, since it reads the same both ways. Blocks and
+//! duplicates links may not form a cycle.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+use std::fmt;
+
+use super::IssueStatus;
+use crate::error::Error;
+
+/// How one issue relates to another, from the first issue's side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyKind {
+    /// The other issue can't start until this one is done
+    Blocks,
+    BlockedBy,
+    /// This issue is the same as the other one
+    Duplicates,
+    DuplicatedBy,
+    RelatesTo,
+}
+
+impl DependencyKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DependencyKind::Blocks => "blocks",
+            DependencyKind::BlockedBy => "blocked-by",
+            DependencyKind::Duplicates => "duplicates",
+            DependencyKind::DuplicatedBy => "duplicated-by",
+            DependencyKind::RelatesTo => "relates-to",
+        }
+    }
+
+    /// The same link seen from the other issue
+    pub fn inverse(self) -> Self {
+        match self {
+            DependencyKind::Blocks => DependencyKind::BlockedBy,
+            DependencyKind::BlockedBy => DependencyKind::Blocks,
+            DependencyKind::Duplicates => DependencyKind::DuplicatedBy,
+            DependencyKind::DuplicatedBy => DependencyKind::Duplicates,
+            DependencyKind::RelatesTo => DependencyKind::RelatesTo,
+        }
+    }
+
+    /// The row storing "`issue` kind `other`": its kind, source and target
+    pub(crate) fn stored(self, issue: i64, other: i64) -> (Self, i64, i64) {
+        match self {
+            DependencyKind::BlockedBy | DependencyKind::DuplicatedBy => (self.inverse(), other, issue),
+            DependencyKind::RelatesTo => (self, issue.min(other), issue.max(other)),
+            _ => (self, issue, other),
+        }
+    }
+
+    /// Whether links of this kind must not form a cycle
+    pub(crate) fn is_acyclic(self) -> bool {
+        !matches!(self, DependencyKind::RelatesTo)
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for DependencyKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blocks" => Ok(DependencyKind::Blocks),
+            "blocked-by" => Ok(DependencyKind::BlockedBy),
+            "duplicates" => Ok(DependencyKind::Duplicates),
+            "duplicated-by" => Ok(DependencyKind::DuplicatedBy),
+            "relates-to" => Ok(DependencyKind::RelatesTo),
+            _ => Err(Error::Validation(format!(
+                "Invalid dependency kind: {} (expected blocks, blocked-by, duplicates, duplicated-by or relates-to)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A link from an issue to another one, as listed by `TaskDatabase::get_issue_links`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IssueLink {
+    pub kind: DependencyKind,
+    /// The other issue
+    pub issue_id: i64,
+    pub title: String,
+    pub status: IssueStatus,
+}
+
+impl fmt::Display for IssueLink {
+    /// e.g. `blocks #5 Fix login (open)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} #{} {} ({})", self.kind, self.issue_id, self.title, self.status)
+    }
+}
+
+/// How a link is stored in a `link` event, e.g. `blocks #5`
+pub(crate) fn link_value(kind: DependencyKind, other: i64) -> String {
+    format!("{} #{}", kind, other)
+}
+
+/// Read back a `link_value`
+pub(crate) fn parse_link_value(value: &str) -> Option<(DependencyKind, i64)> {
+    let (kind, other) = value.split_once(" #")?;
+    Some((kind.parse().ok()?, other.parse().ok()?))
+}
+
+/// The shortest path from `from` to `to` along `edges`, both ends included
+pub(crate) fn find_path(edges: &[(i64, i64)], from: i64, to: i64) -> Option<Vec<i64>> {
+    let mut came_from = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut path = vec![to];
+            let mut step = to;
+            while step != from {
+                step = came_from[&step];
+                path.push(step);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &(_, next) in edges.iter().filter(|(source, _)| *source == node) {
+            if let Entry::Vacant(entry) = came_from.entry(next) {
+                entry.insert(node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_kinds() {
+        assert_eq!(DependencyKind::Blocks.stored(3, 5), (DependencyKind::Blocks, 3, 5));
+        assert_eq!(DependencyKind::BlockedBy.stored(3, 5), (DependencyKind::Blocks, 5, 3));
+        assert_eq!(DependencyKind::DuplicatedBy.stored(3, 5), (DependencyKind::Duplicates, 5, 3));
+        assert_eq!(DependencyKind::RelatesTo.stored(5, 3), (DependencyKind::RelatesTo, 3, 5));
+
+        let value = link_value(DependencyKind::BlockedBy, 12);
+        assert_eq!(value, "blocked-by #12");
+        assert_eq!(parse_link_value(&value), Some((DependencyKind::BlockedBy, 12)));
+        assert_eq!(parse_link_value("blocks 12"), None);
+        assert_eq!("parent-of".parse::<DependencyKind>().unwrap_err().kind(), "validation");
+    }
+
+    #[test]
+    fn test_find_path() {
+        let edges = [(1, 2), (2, 3), (3, 4), (1, 4), (5, 1)];
+        assert_eq!(find_path(&edges, 1, 4), Some(vec![1, 4]), "Shortest first");
+        assert_eq!(find_path(&edges, 2, 4), Some(vec![2, 3, 4]));
+        assert_eq!(find_path(&edges, 5, 5), Some(vec![5]));
+        assert_eq!(find_path(&edges, 4, 1), None);
+    }
+}
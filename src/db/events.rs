@@ -0,0 +1,288 @@
+//! Issue history and undo.
+//!
+//! Every `TaskDatabase` method that changes an issue appends one row per changed
+//! field to `issue_events` (migration 15), naming who made the change. Triggers
+//! refuse updates and deletes, so the log is append-only, and it outlives the
+//! issue it describes.
+//!
+//! Events are grouped into operations (migration 16): one per method call, or one
+//! for everything run inside `TaskDatabase::operation`, such as a CLI command or a
+//! board request. `TaskDatabase::undo` reverses an operation by applying its events
+//! backwards, as a new operation.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::Issue;
+use super::rank::parse_place;
+use crate::error::Error;
+
+/// What an event changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventField {
+    /// The issue was created; `new_value` is its title
+    Created,
+    /// The issue was moved to the trash; `old_value` is its title
+    Deleted,
+    /// The issue was taken out of the trash; `new_value` is its title
+    Restored,
+    /// The issue was removed from the trash for good; `old_value` is its title
+    Purged,
+    Title,
+    Description,
+    Status,
+    Priority,
+    Assignee,
+    /// The issue was made a sub-task of another; the values are parent issue ids
+    Parent,
+    /// A label was added (`new_value`) or removed (`old_value`)
+    Label,
+    /// A comment was added, edited or deleted; the values are its text before and after
+    Comment,
+    /// A dependency was added (`new_value`) or removed (`old_value`), e.g. `blocks #5`
+    Link,
+    /// The card was placed on the board; the values are its place, e.g. `i in done`
+    Rank,
+}
+
+impl EventField {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventField::Created => "created",
+            EventField::Deleted => "deleted",
+            EventField::Restored => "restored",
+            EventField::Purged => "purged",
+            EventField::Title => "title",
+            EventField::Description => "description",
+            EventField::Status => "status",
+            EventField::Priority => "priority",
+            EventField::Assignee => "assignee",
+            EventField::Parent => "parent",
+            EventField::Label => "label",
+            EventField::Comment => "comment",
+            EventField::Link => "link",
+            EventField::Rank => "rank",
+        }
+    }
+}
+
+impl fmt::Display for EventField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for EventField {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(EventField::Created),
+            "deleted" => Ok(EventField::Deleted),
+            "restored" => Ok(EventField::Restored),
+            "purged" => Ok(EventField::Purged),
+            "title" => Ok(EventField::Title),
+            "description" => Ok(EventField::Description),
+            "status" => Ok(EventField::Status),
+            "priority" => Ok(EventField::Priority),
+            "assignee" => Ok(EventField::Assignee),
+            "parent" => Ok(EventField::Parent),
+            "label" => Ok(EventField::Label),
+            "comment" => Ok(EventField::Comment),
+            "link" => Ok(EventField::Link),
+            "rank" => Ok(EventField::Rank),
+            _ => Err(Error::Validation(format!("Invalid event field: {}", s))),
+        }
+    }
+}
+
+/// One recorded change to an issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueEvent {
+    pub id: i64,
+    pub issue_id: i64,
+    /// Who made the change: a local user, or the tracker a change was pulled from (e.g. `github`)
+    pub actor: String,
+    pub field: EventField,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// The operation the change was part of; `None` for events recorded before operations existed
+    pub operation_id: Option<i64>,
+    /// The comment a `comment` event is about
+    pub comment_id: Option<i64>,
+}
+
+/// Changes made together and undone together
+#[derive(Debug, Clone, Serialize)]
+pub struct Operation {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    /// The operation this one reversed, if it is an undo
+    pub undoes: Option<i64>,
+    /// Oldest first
+    pub events: Vec<IssueEvent>,
+}
+
+tokio::task_local! {
+    /// The operation changes are recorded under while running inside `TaskDatabase::operation`,
+    /// or `None` inside `TaskDatabase::without_undo`
+    pub(crate) static OPERATION: Option<i64>;
+}
+
+/// A change about to be recorded
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Change {
+    pub field: EventField,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub comment_id: Option<i64>,
+}
+
+impl Change {
+    pub fn new(field: EventField, old_value: Option<String>, new_value: Option<String>) -> Self {
+        Self { field, old_value, new_value, comment_id: None }
+    }
+
+    pub fn comment(comment_id: i64, old_body: Option<String>, new_body: Option<String>) -> Self {
+        Self { comment_id: Some(comment_id), ..Self::new(EventField::Comment, old_body, new_body) }
+    }
+}
+
+/// The changes that turn `old` into `new`: one per changed field, and one per label added or removed
+pub(crate) fn issue_changes(old: &Issue, new: &Issue) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut compare = |field, old: Option<String>, new: Option<String>| {
+        if old != new {
+            changes.push(Change::new(field, old, new));
+        }
+    };
+    compare(EventField::Title, Some(old.title.clone()), Some(new.title.clone()));
+    compare(EventField::Description, old.description.clone(), new.description.clone());
+    compare(EventField::Status, Some(old.status.to_string()), Some(new.status.to_string()));
+    compare(EventField::Priority, Some(old.priority.to_string()), Some(new.priority.to_string()));
+    compare(EventField::Assignee, old.assignee.clone(), new.assignee.clone());
+    compare(EventField::Parent, old.parent_id.map(|id| id.to_string()), new.parent_id.map(|id| id.to_string()));
+
+    for label in old.labels.iter().filter(|label| !new.labels.contains(label)) {
+        changes.push(Change::new(EventField::Label, Some(label.clone()), None));
+    }
+    for label in new.labels.iter().filter(|label| !old.labels.contains(label)) {
+        changes.push(Change::new(EventField::Label, None, Some(label.clone())));
+    }
+    changes
+}
+
+impl fmt::Display for IssueEvent {
+    /// A one-line description, e.g. `changed status from open to closed`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old = self.old_value.as_deref();
+        let new = self.new_value.as_deref();
+        match (self.field, old, new) {
+            (EventField::Created, _, Some(title)) => write!(f, "created the issue \"{}\"", title),
+            (EventField::Created, _, None) => write!(f, "created the issue"),
+            (EventField::Deleted, _, _) => write!(f, "deleted the issue"),
+            (EventField::Restored, _, _) => write!(f, "restored the issue"),
+            (EventField::Purged, _, _) => write!(f, "purged the issue"),
+            (EventField::Label, Some(old), Some(new)) => write!(f, "renamed label {} to {}", old, new),
+            (EventField::Label, None, Some(label)) => write!(f, "added label {}", label),
+            (EventField::Label, Some(label), None) => write!(f, "removed label {}", label),
+            (EventField::Link, _, Some(link)) => write!(f, "added link: {}", link),
+            (EventField::Link, Some(link), None) => write!(f, "removed link: {}", link),
+            (EventField::Rank, _, Some(place)) => match parse_place(place) {
+                Some((column, _)) => write!(f, "placed the card in {}", column),
+                None => write!(f, "placed the card"),
+            },
+            (EventField::Rank, Some(_), None) => write!(f, "unplaced the card"),
+            (EventField::Comment, None, _) => write!(f, "commented"),
+            (EventField::Comment, Some(_), Some(_)) => write!(f, "edited a comment"),
+            (EventField::Comment, Some(_), None) => write!(f, "deleted a comment"),
+            (EventField::Parent, Some(old), Some(new)) => write!(f, "moved from under #{} to under #{}", old, new),
+            (EventField::Parent, None, Some(parent)) => write!(f, "moved under #{}", parent),
+            (EventField::Parent, Some(parent), None) => write!(f, "moved out of #{}", parent),
+            (EventField::Description, None, _) => write!(f, "added a description"),
+            (EventField::Description, Some(_), Some(_)) => write!(f, "changed the description"),
+            (EventField::Description, Some(_), None) => write!(f, "removed the description"),
+            (field, Some(old), Some(new)) => write!(f, "changed {} from {} to {}", field, old, new),
+            (field, None, Some(new)) => write!(f, "set {} to {}", field, new),
+            (field, Some(old), None) => write!(f, "cleared {} (was {})", field, old),
+            (field, None, None) => write!(f, "changed {}", field),
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    /// The first change, e.g. `#3 deleted the issue (+1 more)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.events.first() {
+            Some(event) => write!(f, "#{} {}", event.issue_id, event)?,
+            None => write!(f, "no changes")?,
+        }
+        if self.events.len() > 1 {
+            write!(f, " (+{} more)", self.events.len() - 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{IssuePriority, IssueStatus};
+
+    #[test]
+    fn test_issue_changes() {
+        let old = Issue {
+            id: Some(1),
+            title: "Fix login".to_string(),
+            description: None,
+            status: IssueStatus::Open,
+            priority: IssuePriority::Medium,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            assignee: Some("alice".to_string()),
+            labels: vec!["bug".to_string(), "question".to_string()],
+            external_ref: None,
+            parent_id: None,
+        };
+        let new = Issue {
+            status: IssueStatus::InProgress,
+            assignee: None,
+            labels: vec!["bug".to_string(), "enhancement".to_string()],
+            parent_id: Some(7),
+            ..old.clone()
+        };
+
+        let changes = issue_changes(&old, &new);
+        assert_eq!(changes, vec![
+            Change::new(EventField::Status, Some("open".to_string()), Some("in_progress".to_string())),
+            Change::new(EventField::Assignee, Some("alice".to_string()), None),
+            Change::new(EventField::Parent, None, Some("7".to_string())),
+            Change::new(EventField::Label, Some("question".to_string()), None),
+            Change::new(EventField::Label, None, Some("enhancement".to_string())),
+        ]);
+        assert!(issue_changes(&old, &old).is_empty());
+
+        let event = |change: &Change| IssueEvent {
+            id: 1,
+            issue_id: 1,
+            actor: "bob".to_string(),
+            field: change.field,
+            old_value: change.old_value.clone(),
+            new_value: change.new_value.clone(),
+            created_at: Utc::now(),
+            operation_id: Some(1),
+            comment_id: None,
+        };
+        let summaries: Vec<String> = changes.iter().map(|change| event(change).to_string()).collect();
+        assert_eq!(summaries, [
+            "changed status from open to in_progress",
+            "cleared assignee (was alice)",
+            "moved under #7",
+            "removed label question",
+            "added label enhancement",
+        ]);
+    }
+}
@@ -0,0 +1,64 @@
+//! Sub-tasks and progress.
+//!
+//! An issue can have a parent (`issues.parent_id`, migration 18), to any depth,
+//! so epics can be split into stories and stories into tasks. A parent's progress
+//! counts all of its descendants, and an issue counts as done once resolved or
+//! closed. Markdown task lists (`- [x] step`) in a description are counted as
+//! lightweight sub-tasks of their own.
+
+use pulldown_cmark::{Event, Options, Parser};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How many of some sub-tasks are done
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub done: u32,
+    pub total: u32,
+}
+
+impl Progress {
+    /// Done share in whole percent, for progress bars
+    pub fn percent(&self) -> u32 {
+        (self.done * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Progress {
+    /// e.g. `3/5`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)
+    }
+}
+
+/// The checked and total task-list items in Markdown, or `None` if it has no task list.
+/// Items inside code blocks don't count.
+pub fn task_list_progress(markdown: &str) -> Option<Progress> {
+    let mut progress = Progress::default();
+    for event in Parser::new_ext(markdown, Options::ENABLE_TASKLISTS) {
+        if let Event::TaskListMarker(checked) = event {
+            progress.total += 1;
+            if checked {
+                progress.done += 1;
+            }
+        }
+    }
+    (progress.total > 0).then_some(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_list_progress() {
+        let description = "Steps:\n\n- [x] Schema\n- [X] API\n- [ ] UI\n  - [ ] Nested\n\n```\n- [ ] not a task\n```\n";
+        let progress = task_list_progress(description).unwrap();
+        assert_eq!(progress, Progress { done: 2, total: 4 });
+        assert_eq!(progress.to_string(), "2/4");
+        assert_eq!(progress.percent(), 50);
+
+        assert_eq!(task_list_progress("- plain item\n- [link](x)"), None);
+        assert_eq!(Progress::default().percent(), 0);
+    }
+}
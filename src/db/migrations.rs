@@ -0,0 +1,381 @@
+//! Ordered schema migrations applied by `TaskDatabase::migrate`.
+//!
+//! Each migration runs once, inside a transaction, and is recorded in the
+//! `schema_version` table. Never edit a migration that has shipped; append a
+//! new one instead. Steps are written to be idempotent so databases created
+//! before versioning existed (version 0) can replay the whole list safely.
+
+/// A single schema change
+pub(crate) enum MigrationStep {
+    /// Run a SQL statement as-is
+    Sql(&'static str),
+    /// `ALTER TABLE ... ADD COLUMN`, skipped when the column already exists
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+    /// Insert the columns of `BoardDefinition::defaults`, keeping any with the same id
+    SeedDefaultBoard,
+}
+
+/// A versioned group of schema changes
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub(crate) steps: &'static [MigrationStep],
+}
+
+use MigrationStep::*;
+
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create commits, labels, issues and issue_labels tables",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS commits (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                hash TEXT UNIQUE NOT NULL,
+                author_name TEXT NOT NULL,
+                author_email TEXT NOT NULL,
+                commit_date DATETIME NOT NULL,
+                message TEXT NOT NULL,
+                files_changed TEXT NOT NULL, -- JSON array
+                insertions INTEGER DEFAULT 0,
+                deletions INTEGER DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )"),
+            Sql("CREATE TABLE IF NOT EXISTS labels (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT UNIQUE NOT NULL,
+                color TEXT NOT NULL DEFAULT '#808080',
+                description TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )"),
+            Sql("CREATE TABLE IF NOT EXISTS issues (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                title TEXT NOT NULL,
+                description TEXT,
+                status TEXT NOT NULL DEFAULT 'open',
+                priority TEXT NOT NULL DEFAULT 'medium',
+                assignee TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )"),
+            Sql("CREATE TABLE IF NOT EXISTS issue_labels (
+                issue_id INTEGER NOT NULL,
+                label_id INTEGER NOT NULL,
+                PRIMARY KEY (issue_id, label_id),
+                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE,
+                FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE
+            )"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_commits_hash ON commits(hash)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_commits_date ON commits(commit_date)"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_issues_status ON issues(status)"),
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "Store commit parent hashes and per-file stats",
+        steps: &[
+            AddColumn { table: "commits", column: "parent_hashes", definition: "TEXT NOT NULL DEFAULT '[]'" }, // JSON array
+            AddColumn { table: "commits", column: "file_stats", definition: "TEXT NOT NULL DEFAULT '[]'" }, // JSON array
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "Track the last imported commit per git ref",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS import_state (
+                ref_name TEXT PRIMARY KEY,
+                tip_hash TEXT NOT NULL,
+                imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )"),
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "Link commits to the issues they reference",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS commit_issues (
+                commit_hash TEXT NOT NULL,
+                issue_id INTEGER NOT NULL,
+                kind TEXT NOT NULL DEFAULT 'references',
+                PRIMARY KEY (commit_hash, issue_id),
+                FOREIGN KEY (commit_hash) REFERENCES commits (hash) ON DELETE CASCADE,
+                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE
+            )"),
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "Store external issue references (e.g. GitHub issue numbers) as columns",
+        steps: &[
+            AddColumn { table: "issues", column: "external_source", definition: "TEXT" },
+            AddColumn { table: "issues", column: "external_id", definition: "TEXT" },
+            AddColumn { table: "issues", column: "external_url", definition: "TEXT" },
+            // Older gh imports encoded the number in the title as "#N: title"; recover it,
+            // keeping only the oldest row per number so the unique index can be built
+            Sql("UPDATE issues
+                 SET external_source = 'github',
+                     external_id = substr(title, 2, instr(title, ':') - 2),
+                     title = substr(title, instr(title, ': ') + 2)
+                 WHERE external_id IS NULL
+                   AND title GLOB '#[0-9]*: *'
+                   AND substr(title, 2, instr(title, ':') - 2) NOT GLOB '*[^0-9]*'
+                   AND id IN (
+                       SELECT MIN(id) FROM issues
+                       WHERE title GLOB '#[0-9]*: *'
+                       GROUP BY substr(title, 2, instr(title, ':') - 2)
+                   )"),
+            Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_issues_external
+                 ON issues(external_source, external_id)"),
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "Track the newest imported GitHub issue update per repository",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS github_import_state (
+                repository TEXT PRIMARY KEY,
+                last_updated_at TEXT NOT NULL,
+                imported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )"),
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "Remember the last-synced state of issues linked to GitHub",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS issue_sync_state (
+                issue_id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT,
+                closed BOOLEAN NOT NULL,
+                labels TEXT NOT NULL DEFAULT '[]', -- JSON array
+                assignee TEXT,
+                local_updated_at TEXT NOT NULL,
+                remote_updated_at TEXT NOT NULL,
+                synced_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE
+            )"),
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "Queue GitHub mutations made while offline",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS github_outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository TEXT NOT NULL,
+                issue_number INTEGER NOT NULL,
+                mutation TEXT NOT NULL, -- JSON GitHubMutation
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT
+            )"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_github_outbox_repository ON github_outbox(repository, id)"),
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "Store the Kanban board's columns and workflow",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS board_columns (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                color TEXT NOT NULL,
+                label TEXT,
+                status TEXT,
+                position INTEGER NOT NULL
+            )"),
+            // The workflow the board used to hard-code
+            SeedDefaultBoard,
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "Remember the manual order of cards within board columns",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS card_positions (
+                issue_id INTEGER PRIMARY KEY,
+                column_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE
+            )"),
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "Order cards by lexicographic rank instead of integer position",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS card_ranks (
+                issue_id INTEGER PRIMARY KEY,
+                column_id TEXT NOT NULL,
+                rank TEXT NOT NULL,
+                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE
+            )"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_card_ranks_column ON card_ranks (column_id, rank)"),
+            // Zero-padded positions keep their order as text; the trailing `i` keeps
+            // ranks from ending in `0` so there is always room in between
+            Sql("INSERT OR IGNORE INTO card_ranks (issue_id, column_id, rank)
+                SELECT issue_id, column_id, printf('%06di', position) FROM card_positions"),
+            Sql("DROP TABLE IF EXISTS card_positions"),
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "Add work-in-progress limits to board columns",
+        steps: &[
+            AddColumn { table: "board_columns", column: "wip_limit", definition: "INTEGER" },
+        ],
+    },
+    Migration {
+        version: 13,
+        description: "Full-text search over issues and commits",
+        steps: &[
+            // Each index row shares its rowid with the issue or commit it was built from
+            Sql("CREATE VIRTUAL TABLE IF NOT EXISTS issues_fts USING fts5(
+                title, description, tokenize = 'porter unicode61'
+            )"),
+            Sql("CREATE VIRTUAL TABLE IF NOT EXISTS commits_fts USING fts5(
+                message, files, tokenize = 'porter unicode61'
+            )"),
+            Sql("CREATE TRIGGER IF NOT EXISTS issues_fts_insert AFTER INSERT ON issues BEGIN
+                INSERT INTO issues_fts (rowid, title, description) VALUES (new.id, new.title, new.description);
+            END"),
+            Sql("CREATE TRIGGER IF NOT EXISTS issues_fts_update AFTER UPDATE OF title, description ON issues BEGIN
+                UPDATE issues_fts SET title = new.title, description = new.description WHERE rowid = new.id;
+            END"),
+            Sql("CREATE TRIGGER IF NOT EXISTS issues_fts_delete AFTER DELETE ON issues BEGIN
+                DELETE FROM issues_fts WHERE rowid = old.id;
+            END"),
+            // File paths are indexed as one space-separated string; the tokenizer splits them on `/` and `.`
+            Sql("CREATE TRIGGER IF NOT EXISTS commits_fts_insert AFTER INSERT ON commits BEGIN
+                INSERT INTO commits_fts (rowid, message, files) VALUES (
+                    new.id, new.message, (SELECT group_concat(value, ' ') FROM json_each(new.files_changed))
+                );
+            END"),
+            Sql("CREATE TRIGGER IF NOT EXISTS commits_fts_update AFTER UPDATE OF message, files_changed ON commits BEGIN
+                UPDATE commits_fts SET
+                    message = new.message,
+                    files = (SELECT group_concat(value, ' ') FROM json_each(new.files_changed))
+                WHERE rowid = new.id;
+            END"),
+            Sql("CREATE TRIGGER IF NOT EXISTS commits_fts_delete AFTER DELETE ON commits BEGIN
+                DELETE FROM commits_fts WHERE rowid = old.id;
+            END"),
+            Sql("INSERT INTO issues_fts (rowid, title, description)
+                SELECT id, title, description FROM issues WHERE id NOT IN (SELECT rowid FROM issues_fts)"),
+            Sql("INSERT INTO commits_fts (rowid, message, files)
+                SELECT id, message, (SELECT group_concat(value, ' ') FROM json_each(files_changed))
+                FROM commits WHERE id NOT IN (SELECT rowid FROM commits_fts)"),
+        ],
+    },
+    Migration {
+        version: 14,
+        description: "Store issue comments",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS comments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                issue_id INTEGER NOT NULL,
+                author TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                external_source TEXT,
+                external_id TEXT,
+                external_url TEXT,
+                -- The external copy's updated_at when the two last matched
+                synced_at DATETIME,
+                FOREIGN KEY (issue_id) REFERENCES issues (id) ON DELETE CASCADE
+            )"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_comments_issue ON comments (issue_id, created_at)"),
+            Sql("CREATE UNIQUE INDEX IF NOT EXISTS idx_comments_external ON comments (external_source, external_id)"),
+        ],
+    },
+    Migration {
+        version: 15,
+        description: "Record issue events",
+        steps: &[
+            // No foreign key: the history of a deleted issue is kept
+            Sql("CREATE TABLE IF NOT EXISTS issue_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                issue_id INTEGER NOT NULL,
+                actor TEXT NOT NULL,
+                field TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                created_at DATETIME NOT NULL
+            )"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_issue_events_issue ON issue_events (issue_id, id)"),
+            Sql("CREATE TRIGGER IF NOT EXISTS issue_events_no_update BEFORE UPDATE ON issue_events BEGIN
+                SELECT RAISE(ABORT, 'issue_events is append-only');
+            END"),
+            Sql("CREATE TRIGGER IF NOT EXISTS issue_events_no_delete BEFORE DELETE ON issue_events BEGIN
+                SELECT RAISE(ABORT, 'issue_events is append-only');
+            END"),
+        ],
+    },
+    Migration {
+        version: 16,
+        description: "Group issue events into undoable operations and keep deleted issues in the trash",
+        steps: &[
+            Sql("CREATE TABLE IF NOT EXISTS operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at DATETIME NOT NULL,
+                -- The operation this one reverses, if it is an undo
+                undoes INTEGER UNIQUE REFERENCES operations (id)
+            )"),
+            AddColumn { table: "issue_events", column: "operation_id", definition: "INTEGER REFERENCES operations (id)" },
+            AddColumn { table: "issue_events", column: "comment_id", definition: "INTEGER" },
+            Sql("CREATE INDEX IF NOT EXISTS idx_issue_events_operation ON issue_events (operation_id)"),
+            AddColumn { table: "issues", column: "deleted_at", definition: "DATETIME" },
+            AddColumn { table: "comments", column: "deleted_at", definition: "DATETIME" },
+        ],
+    },
+    Migration {
+        version: 17,
+        description: "Store dependencies between issues",
+        steps: &[
+            // Read as "source kind target"; see db::dependencies
+            Sql("CREATE TABLE IF NOT EXISTS issue_dependencies (
+                source_id INTEGER NOT NULL,
+                target_id INTEGER NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('blocks', 'duplicates', 'relates-to')),
+                created_at DATETIME NOT NULL,
+                PRIMARY KEY (source_id, target_id, kind),
+                CHECK (source_id != target_id),
+                FOREIGN KEY (source_id) REFERENCES issues (id) ON DELETE CASCADE,
+                FOREIGN KEY (target_id) REFERENCES issues (id) ON DELETE CASCADE
+            )"),
+            Sql("CREATE INDEX IF NOT EXISTS idx_issue_dependencies_target ON issue_dependencies (target_id, kind)"),
+        ],
+    },
+    Migration {
+        version: 18,
+        description: "Let issues be sub-tasks of other issues",
+        steps: &[
+            AddColumn { table: "issues", column: "parent_id", definition: "INTEGER REFERENCES issues (id)" },
+            Sql("CREATE INDEX IF NOT EXISTS idx_issues_parent ON issues (parent_id)"),
+        ],
+    },
+];
+
+/// The schema version this build of atask produces
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i64 + 1, "Migrations must be numbered 1, 2, 3, ...");
+        }
+        assert_eq!(latest_version(), MIGRATIONS.len() as i64);
+    }
+}
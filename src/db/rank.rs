@@ -0,0 +1,92 @@
+//! Lexicographic ranks for ordering cards within a board column.
+//!
+//! A rank is a string of base-36 digits (`0-9a-z`) compared as plain text.
+//! There is always room for a new rank between two others, so placing a card
+//! writes one row instead of renumbering the column. Generated ranks never end
+//! in `0`, which keeps that guarantee.
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const BASE: u8 = DIGITS.len() as u8;
+
+/// Digit values of a rank, without trailing zeros (`"a0"` sorts like `"a"`)
+fn digits(rank: &str) -> Vec<u8> {
+    let mut digits: Vec<u8> = rank.bytes()
+        .map(|byte| DIGITS.iter().position(|&d| d == byte).unwrap_or(0) as u8)
+        .collect();
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits
+}
+
+/// A rank that sorts after `before` and ahead of `after`; `None` means the
+/// top or bottom of the column. If `after` doesn't sort after `before` it is ignored.
+pub fn between(before: Option<&str>, after: Option<&str>) -> String {
+    let low = before.map(digits).unwrap_or_default();
+    let mut high = after.map(digits).filter(|high| *high > low);
+
+    let mut rank = Vec::new();
+    for i in 0.. {
+        let l = low.get(i).copied().unwrap_or(0);
+        let h = match &high {
+            Some(high) => high.get(i).copied().unwrap_or(0),
+            None => BASE,
+        };
+        if l == h {
+            rank.push(l);
+            continue;
+        }
+        if h - l > 1 {
+            rank.push((l + h) / 2);
+            break;
+        }
+        // No digit fits between; keep the lower one and only stay above `before` from here on
+        rank.push(l);
+        high = None;
+    }
+
+    rank.into_iter().map(|d| DIGITS[d as usize] as char).collect()
+}
+
+/// How a card's place is stored in a `rank` event, e.g. `i in done`
+pub(crate) fn place(column_id: &str, rank: &str) -> String {
+    format!("{} in {}", rank, column_id)
+}
+
+/// Read back a `place` as its column and rank
+pub(crate) fn parse_place(value: &str) -> Option<(&str, &str)> {
+    let (rank, column_id) = value.split_once(" in ")?;
+    Some((column_id, rank))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_between_orders_ranks() {
+        let first = between(None, None);
+        let top = between(None, Some(&first));
+        let bottom = between(Some(&first), None);
+        assert!(top < first && first < bottom, "{} {} {}", top, first, bottom);
+
+        // Keep inserting just below the top card; every rank must stay strictly between
+        let mut low = top.clone();
+        for _ in 0..200 {
+            let rank = between(Some(&low), Some(&first));
+            assert!(low < rank && rank < first, "{} {} {}", low, rank, first);
+            assert!(!rank.ends_with('0'));
+            low = rank;
+        }
+
+        // Neighbours that differ by one digit
+        let rank = between(Some("a"), Some("b"));
+        assert!("a" < rank.as_str() && rank.as_str() < "b", "{}", rank);
+        let rank = between(None, Some("01"));
+        assert!(rank.as_str() < "01", "{}", rank);
+
+        // Bounds in the wrong order, or equal once trailing zeros are dropped, only keep `before`
+        assert!(between(Some("m"), Some("c")).as_str() > "m");
+        assert!(between(Some("a"), Some("a0")).as_str() > "a");
+    }
+}
@@ -0,0 +1,101 @@
+//! Full-text search over issues and commits.
+//!
+//! Migration 13 keeps the `issues_fts` and `commits_fts` FTS5 tables in step
+//! with `issues` and `commits` through triggers; `TaskDatabase::search` queries
+//! them. Every word of the search text must match, as a word prefix, and
+//! results are ordered by BM25 relevance.
+
+use super::{GitCommit, Issue};
+
+/// Wrapped around each matched term by SQLite's `snippet()`
+pub(crate) const MATCH_START: char = '\u{2}';
+pub(crate) const MATCH_END: char = '\u{3}';
+
+/// Text around the matched terms of a search hit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet(pub(crate) String);
+
+impl Snippet {
+    /// Wrap each matched term in `open`/`close`; `escape` is applied to all the text itself
+    pub fn highlight(&self, open: &str, close: &str, escape: impl Fn(&str) -> String) -> String {
+        let mut highlighted = String::new();
+        // Markers come in pairs, so every odd part is a match
+        for (i, part) in self.0.split([MATCH_START, MATCH_END]).enumerate() {
+            if i % 2 == 1 {
+                highlighted.push_str(open);
+                highlighted.push_str(&escape(part));
+                highlighted.push_str(close);
+            } else {
+                highlighted.push_str(&escape(part));
+            }
+        }
+        highlighted
+    }
+
+    /// The snippet without any highlighting
+    pub fn text(&self) -> String {
+        self.0.replace([MATCH_START, MATCH_END], "")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IssueHit {
+    pub issue: Issue,
+    pub snippet: Snippet,
+    /// Relevance; higher is better
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommitHit {
+    pub commit: GitCommit,
+    pub snippet: Snippet,
+    /// Relevance; higher is better
+    pub score: f64,
+}
+
+/// Search hits, best first within each kind
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    pub issues: Vec<IssueHit>,
+    pub commits: Vec<CommitHit>,
+}
+
+impl SearchResults {
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty() && self.commits.is_empty()
+    }
+}
+
+/// An FTS5 query matching every word of `terms` as a prefix, or `None` if
+/// there are no words. Words are quoted so FTS5 operators in the text are taken literally.
+pub(crate) fn match_expression(terms: &str) -> Option<String> {
+    let words: Vec<String> = terms
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(match_expression("login  retry").unwrap(), r#""login"* "retry"*"#);
+        assert_eq!(match_expression(r#"say "hi" OR - NEAR(x)"#).unwrap(), r#""say"* """hi"""* "OR"* "NEAR(x)"*"#);
+        assert_eq!(match_expression("  - ** "), None);
+    }
+
+    #[test]
+    fn test_snippet_highlight() {
+        let snippet = Snippet(format!("…fix {}login{} <b> {}page{}", MATCH_START, MATCH_END, MATCH_START, MATCH_END));
+        assert_eq!(snippet.text(), "…fix login <b> page");
+        assert_eq!(
+            snippet.highlight("<mark>", "</mark>", |text| text.replace('<', "&lt;").replace('>', "&gt;")),
+            "…fix <mark>login</mark> &lt;b&gt; <mark>page</mark>"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Crate-wide error type returned across the `db`, `git_ops`, `sync` and `web` boundaries.
+//!
+//! Each variant says what kind of failure happened so callers can react to it
+//! (the web API maps them to HTTP statuses) without parsing messages.
+
+use std::fmt;
+
+/// What went wrong, with a message for humans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The requested issue, label, column or other record does not exist
+    NotFound(String),
+    /// The change clashes with existing data, e.g. a duplicate label name
+    Conflict(String),
+    /// The input was rejected before anything was changed
+    Validation(String),
+    /// GitHub failed or could not be reached; worth retrying later
+    Upstream(String),
+    /// The local database or git repository failed
+    Storage(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Stable, machine-readable name of the variant, e.g. `not_found`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::Conflict(_) => "conflict",
+            Error::Validation(_) => "validation",
+            Error::Upstream(_) => "upstream",
+            Error::Storage(_) => "storage",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::NotFound(message)
+            | Error::Conflict(message)
+            | Error::Validation(message)
+            | Error::Upstream(message)
+            | Error::Storage(message) => message,
+        }
+    }
+
+    /// Prefix the message with what was being done, keeping the kind
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        let message = format!("{}: {}", context, self.message());
+        match self {
+            Error::NotFound(_) => Error::NotFound(message),
+            Error::Conflict(_) => Error::Conflict(message),
+            Error::Validation(_) => Error::Validation(message),
+            Error::Upstream(_) => Error::Upstream(message),
+            Error::Storage(_) => Error::Storage(message),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<libsql::Error> for Error {
+    fn from(err: libsql::Error) -> Self {
+        match &err {
+            // SQLITE_CONSTRAINT_UNIQUE and SQLITE_CONSTRAINT_PRIMARYKEY
+            libsql::Error::SqliteFailure(2067 | 1555, _) => Error::Conflict(err.to_string()),
+            libsql::Error::SqliteFailure(_, message) if message.contains("UNIQUE constraint failed") => {
+                Error::Conflict(err.to_string())
+            }
+            _ => Error::Storage(err.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Storage(format!("Invalid JSON: {}", err))
+    }
+}
+
+impl From<chrono::ParseError> for Error {
+    fn from(err: chrono::ParseError) -> Self {
+        Error::Storage(format!("Invalid timestamp: {}", err))
+    }
+}
+
+impl From<git2::Error> for Error {
+    fn from(err: git2::Error) -> Self {
+        Error::Storage(format!("Git error: {}", err))
+    }
+}
+
+impl From<octocrab::Error> for Error {
+    fn from(err: octocrab::Error) -> Self {
+        github_error(err, "GitHub error")
+    }
+}
+
+/// Give a GitHub failure the kind it deserves. Requests GitHub turned down for good
+/// become `NotFound` (404, 410) or `Validation` (other 4xx); only failures worth
+/// retrying stay `Upstream`: no connection, server errors and rate limits.
+fn github_error(err: octocrab::Error, context: impl fmt::Display) -> Error {
+    let message = format!("{}: {}", context, err);
+    match &err {
+        octocrab::Error::GitHub { source, .. } => match source.status_code.as_u16() {
+            404 | 410 => Error::NotFound(message),
+            403 if source.message.to_lowercase().contains("rate limit") => Error::Upstream(message),
+            429 => Error::Upstream(message),
+            400..=499 => Error::Validation(message),
+            _ => Error::Upstream(message),
+        },
+        _ => Error::Upstream(message),
+    }
+}
+
+/// Attach a message to a foreign error (or a missing value) and choose its kind,
+/// in the spirit of `anyhow::Context`
+pub trait Context<T> {
+    /// Fail as `Error::Storage`, e.g. for database or git failures
+    fn storage_context(self, context: impl fmt::Display) -> Result<T>;
+    /// Fail as `Error::Upstream`, e.g. for GitHub failures
+    fn upstream_context(self, context: impl fmt::Display) -> Result<T>;
+}
+
+impl<T, E: fmt::Display> Context<T> for std::result::Result<T, E> {
+    fn storage_context(self, context: impl fmt::Display) -> Result<T> {
+        self.map_err(|err| Error::Storage(format!("{}: {}", context, err)))
+    }
+
+    fn upstream_context(self, context: impl fmt::Display) -> Result<T> {
+        self.map_err(|err| Error::Upstream(format!("{}: {}", context, err)))
+    }
+}
+
+/// `upstream_context` for GitHub calls, keeping GitHub's verdict (see `github_error`)
+pub trait GitHubContext<T> {
+    fn github_context(self, context: impl fmt::Display) -> Result<T>;
+}
+
+impl<T> GitHubContext<T> for std::result::Result<T, octocrab::Error> {
+    fn github_context(self, context: impl fmt::Display) -> Result<T> {
+        self.map_err(|err| github_error(err, context))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn storage_context(self, context: impl fmt::Display) -> Result<T> {
+        self.ok_or_else(|| Error::Storage(context.to_string()))
+    }
+
+    fn upstream_context(self, context: impl fmt::Display) -> Result<T> {
+        self.ok_or_else(|| Error::Upstream(context.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_keeps_kind() {
+        let err = Error::NotFound("Issue #3 not found".to_string()).context("Failed to move issue");
+        assert_eq!(err, Error::NotFound("Failed to move issue: Issue #3 not found".to_string()));
+        assert_eq!(err.kind(), "not_found");
+
+        let missing: Option<u8> = None;
+        let err = missing.upstream_context("No response").unwrap_err();
+        assert_eq!(err.kind(), "upstream");
+        assert_eq!(err.to_string(), "No response");
+    }
+
+    #[tokio::test]
+    async fn test_unique_violations_are_conflicts() {
+        let db = libsql::Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        conn.execute("CREATE TABLE t (name TEXT UNIQUE)", ()).await.unwrap();
+        conn.execute("INSERT INTO t VALUES ('a')", ()).await.unwrap();
+
+        let err: Error = conn.execute("INSERT INTO t VALUES ('a')", ()).await.unwrap_err().into();
+        assert_eq!(err.kind(), "conflict", "{}", err);
+        let err: Error = conn.execute("SELECT * FROM missing", ()).await.unwrap_err().into();
+        assert_eq!(err.kind(), "storage");
+    }
+}
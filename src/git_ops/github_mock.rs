@@ -0,0 +1,489 @@
+//! A stand-in for the GitHub REST API so `GitHubOps` can be tested over real HTTP.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{OriginalUri, Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, patch, post};
+use axum::{Json, Router};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use super::GitHubOps;
+
+pub(crate) const OWNER: &str = "octo";
+pub(crate) const REPO: &str = "widgets";
+
+#[derive(Default)]
+struct MockState {
+    base_url: String,
+    issues: Vec<Value>,
+    /// (issue number, comment)
+    comments: Vec<(u64, Value)>,
+    /// Highest comment id handed out; like GitHub's, ids are never reused
+    last_comment_id: u64,
+    /// Issues whose comments can't be listed
+    broken_comments: Vec<u64>,
+    /// Issues whose updates and new comments are answered with this status
+    failing_writes: Vec<(u64, u16)>,
+    requests: Vec<String>,
+}
+
+type SharedState = Arc<Mutex<MockState>>;
+
+/// A mock GitHub server listening on a random local port
+pub(crate) struct MockGitHub {
+    pub base_url: String,
+    state: SharedState,
+}
+
+impl MockGitHub {
+    pub async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let state: SharedState = Arc::new(Mutex::new(MockState {
+            base_url: base_url.clone(),
+            ..Default::default()
+        }));
+
+        let app = Router::new()
+            .route("/repos/:owner/:repo/issues", get(list_issues))
+            .route("/repos/:owner/:repo/issues/:number", get(get_issue).patch(update_issue))
+            .route("/repos/:owner/:repo/issues/:number/labels", post(add_labels))
+            .route("/repos/:owner/:repo/issues/:number/labels/:name", delete(remove_label))
+            .route("/repos/:owner/:repo/issues/:number/comments", get(list_comments).post(create_comment))
+            .route("/repos/:owner/:repo/issues/comments/:id", patch(update_comment).delete(remove_comment))
+            .with_state(state.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { base_url, state }
+    }
+
+    /// A GitHubOps client pointed at this server
+    pub fn client(&self) -> GitHubOps {
+        GitHubOps::with_base_url(
+            "test-token".to_string(),
+            OWNER.to_string(),
+            REPO.to_string(),
+            &self.base_url,
+        ).unwrap()
+    }
+
+    pub fn add_issue(&self, issue: Value) {
+        self.state.lock().unwrap().issues.push(issue);
+    }
+
+    /// The current server-side copy of an issue
+    pub fn issue(&self, number: u64) -> Value {
+        self.state.lock().unwrap().issues.iter()
+            .find(|issue| issue["number"] == number)
+            .cloned()
+            .unwrap_or_else(|| panic!("No issue #{} on the mock server", number))
+    }
+
+    /// Simulate an edit made on GitHub at `updated_at`
+    pub fn edit_issue(&self, number: u64, updated_at: &str, edit: impl FnOnce(&mut Value)) {
+        let mut state = self.state.lock().unwrap();
+        let issue = state.issues.iter_mut()
+            .find(|issue| issue["number"] == number)
+            .unwrap_or_else(|| panic!("No issue #{} on the mock server", number));
+        edit(issue);
+        issue["updated_at"] = updated_at.into();
+    }
+
+    /// Make listing the comments of an issue fail with a server error, or work again
+    pub fn break_comments(&self, number: u64, broken: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.broken_comments.retain(|n| *n != number);
+        if broken {
+            state.broken_comments.push(number);
+        }
+    }
+
+    /// Make updating an issue or commenting on it fail with `status`, or work again with `None`
+    pub fn fail_writes(&self, number: u64, status: Option<u16>) {
+        let mut state = self.state.lock().unwrap();
+        state.failing_writes.retain(|(n, _)| *n != number);
+        if let Some(status) = status {
+            state.failing_writes.push((number, status));
+        }
+    }
+
+    /// Comments on an issue, oldest first
+    pub fn comments(&self, number: u64) -> Vec<Value> {
+        self.state.lock().unwrap().comments.iter()
+            .filter(|(issue, _)| *issue == number)
+            .map(|(_, comment)| comment.clone())
+            .collect()
+    }
+
+    /// Simulate a comment written on GitHub, which also bumps the issue's `updated_at`; returns its id
+    pub fn add_comment(&self, number: u64, login: &str, body: &str) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let id = next_comment_id(&mut state);
+        state.comments.push((number, comment_json(id, number, login, body)));
+        if let Some(issue) = state.issues.iter_mut().find(|issue| issue["number"] == number) {
+            issue["updated_at"] = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true).into();
+        }
+        id
+    }
+
+    /// Simulate a comment edited on GitHub at `updated_at`
+    pub fn edit_comment(&self, id: u64, body: &str, updated_at: &str) {
+        let mut state = self.state.lock().unwrap();
+        let (_, comment) = state.comments.iter_mut()
+            .find(|(_, comment)| comment["id"] == id)
+            .unwrap_or_else(|| panic!("No comment {} on the mock server", id));
+        comment["body"] = body.into();
+        comment["updated_at"] = updated_at.into();
+    }
+
+    /// Simulate a comment deleted on GitHub
+    pub fn delete_comment(&self, id: u64) {
+        self.state.lock().unwrap().comments.retain(|(_, comment)| comment["id"] != id);
+    }
+
+    /// Method, path and query of every request received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+async fn list_issues(
+    State(state): State<SharedState>,
+    Query(query): Query<HashMap<String, String>>,
+    OriginalUri(uri): OriginalUri,
+) -> impl IntoResponse {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("GET {}", uri));
+
+    let wanted_state = query.get("state").map(String::as_str).unwrap_or("open");
+    let since = query.get("since").map(|s| s.parse::<DateTime<Utc>>().unwrap());
+    let per_page: usize = query.get("per_page").and_then(|p| p.parse().ok()).unwrap_or(30);
+    let page: usize = query.get("page").and_then(|p| p.parse().ok()).unwrap_or(1);
+
+    let matching: Vec<Value> = state.issues.iter()
+        .filter(|issue| wanted_state == "all" || issue["state"] == wanted_state)
+        .filter(|issue| since.is_none_or(|since| {
+            issue["updated_at"].as_str().unwrap().parse::<DateTime<Utc>>().unwrap() >= since
+        }))
+        .cloned()
+        .collect();
+
+    let items: Vec<Value> = matching.iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(|issue| with_comment_count(&state, issue))
+        .collect();
+
+    let mut headers = Vec::new();
+    if page * per_page < matching.len() {
+        let mut next = format!(
+            "{}/repos/{}/{}/issues?state={}&per_page={}&page={}",
+            state.base_url, OWNER, REPO, wanted_state, per_page, page + 1
+        );
+        if let Some(since) = query.get("since") {
+            next.push_str(&format!("&since={}", since.replace('+', "%2B")));
+        }
+        headers.push((header::LINK, format!("<{}>; rel=\"next\"", next)));
+    }
+
+    (axum::http::HeaderMap::from_iter(
+        headers.into_iter().map(|(name, value)| (name, value.parse().unwrap())),
+    ), Json(items))
+}
+
+async fn get_issue(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, number)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("GET {}", uri));
+
+    state.issues.iter()
+        .find(|issue| issue["number"] == number)
+        .map(|issue| Json(with_comment_count(&state, issue)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// The error a write to an issue made to fail with `MockGitHub::fail_writes` gets
+fn write_failure(state: &MockState, number: u64) -> Option<(StatusCode, Json<Value>)> {
+    let (_, status) = state.failing_writes.iter().find(|(n, _)| *n == number)?;
+    Some((StatusCode::from_u16(*status).unwrap(), Json(json!({
+        "message": format!("Mock failure {}", status),
+        "documentation_url": "https://docs.github.com/rest",
+    }))))
+}
+
+/// An issue with its `comments` count filled in
+fn with_comment_count(state: &MockState, issue: &Value) -> Value {
+    let mut issue = issue.clone();
+    issue["comments"] = state.comments.iter().filter(|(number, _)| issue["number"] == *number).count().into();
+    issue
+}
+
+async fn update_issue(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, number)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+    Json(changes): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("PATCH {} {}", uri, changes));
+    if let Some(failure) = write_failure(&state, number) {
+        return Err(failure);
+    }
+
+    let issue = state.issues.iter_mut()
+        .find(|issue| issue["number"] == number)
+        .ok_or((StatusCode::NOT_FOUND, Json(json!({
+            "message": "Not Found",
+            "documentation_url": "https://docs.github.com/rest",
+        }))))?;
+    for field in ["title", "body", "state"] {
+        if let Some(value) = changes.get(field) {
+            issue[field] = value.clone();
+        }
+    }
+    if let Some(labels) = changes["labels"].as_array() {
+        issue["labels"] = labels.iter()
+            .map(|name| label_json(name.as_str().unwrap()))
+            .collect();
+    }
+    if let Some(assignees) = changes["assignees"].as_array() {
+        let users: Vec<Value> = assignees.iter().map(|login| user_json(login.as_str().unwrap())).collect();
+        issue["assignee"] = users.first().cloned().unwrap_or(Value::Null);
+        issue["assignees"] = users.into();
+    }
+    issue["updated_at"] = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true).into();
+
+    Ok(Json(issue.clone()))
+}
+
+async fn add_labels(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, number)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("POST {} {}", uri, body));
+
+    let issue = state.issues.iter_mut()
+        .find(|issue| issue["number"] == number)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    for name in body["labels"].as_array().ok_or(StatusCode::UNPROCESSABLE_ENTITY)? {
+        let labels = issue["labels"].as_array_mut().unwrap();
+        if !labels.iter().any(|label| label["name"] == *name) {
+            labels.push(label_json(name.as_str().unwrap()));
+        }
+    }
+    issue["updated_at"] = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true).into();
+
+    Ok(Json(issue["labels"].clone()))
+}
+
+async fn remove_label(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, number, name)): Path<(String, String, u64, String)>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("DELETE {}", uri));
+
+    let not_found = || (StatusCode::NOT_FOUND, Json(json!({
+        "message": "Label does not exist",
+        "documentation_url": "https://docs.github.com/rest",
+    })));
+    let issue = state.issues.iter_mut()
+        .find(|issue| issue["number"] == number)
+        .ok_or_else(not_found)?;
+    let labels = issue["labels"].as_array_mut().unwrap();
+    let position = labels.iter().position(|label| label["name"] == name).ok_or_else(not_found)?;
+    labels.remove(position);
+    let remaining = Value::Array(labels.clone());
+    issue["updated_at"] = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true).into();
+
+    Ok(Json(remaining))
+}
+
+async fn create_comment(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, number)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("POST {} {}", uri, body));
+    if let Some(failure) = write_failure(&state, number) {
+        return Err(failure);
+    }
+
+    if !state.issues.iter().any(|issue| issue["number"] == number) {
+        return Err((StatusCode::NOT_FOUND, Json(json!({
+            "message": "Not Found",
+            "documentation_url": "https://docs.github.com/rest",
+        }))));
+    }
+    let comment = comment_json(next_comment_id(&mut state), number, "octocat", body["body"].as_str().unwrap_or(""));
+    state.comments.push((number, comment.clone()));
+
+    Ok(Json(comment))
+}
+
+async fn list_comments(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, number)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("GET {}", uri));
+    if state.broken_comments.contains(&number) {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Ok(Json(state.comments.iter()
+        .filter(|(issue, _)| *issue == number)
+        .map(|(_, comment)| comment.clone())
+        .collect()))
+}
+
+async fn update_comment(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, id)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("PATCH {} {}", uri, body));
+
+    let (_, comment) = state.comments.iter_mut()
+        .find(|(_, comment)| comment["id"] == id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    comment["body"] = body["body"].clone();
+    comment["updated_at"] = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true).into();
+
+    Ok(Json(comment.clone()))
+}
+
+async fn remove_comment(
+    State(state): State<SharedState>,
+    Path((_owner, _repo, id)): Path<(String, String, u64)>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let mut state = state.lock().unwrap();
+    state.requests.push(format!("DELETE {}", uri));
+
+    let before = state.comments.len();
+    state.comments.retain(|(_, comment)| comment["id"] != id);
+    if state.comments.len() == before {
+        return Err((StatusCode::NOT_FOUND, Json(json!({
+            "message": "Not Found",
+            "documentation_url": "https://docs.github.com/rest",
+        }))));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn next_comment_id(state: &mut MockState) -> u64 {
+    state.last_comment_id += 1;
+    state.last_comment_id
+}
+
+/// A comment in the shape returned by the issue comments API
+pub(crate) fn comment_json(id: u64, issue_number: u64, login: &str, body: &str) -> Value {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    json!({
+        "id": id,
+        "node_id": format!("IC_{}", id),
+        "url": format!("https://api.github.com/repos/{}/{}/issues/comments/{}", OWNER, REPO, id),
+        "html_url": format!("https://github.com/{}/{}/issues/{}#issuecomment-{}", OWNER, REPO, issue_number, id),
+        "issue_url": format!("https://api.github.com/repos/{}/{}/issues/{}", OWNER, REPO, issue_number),
+        "body": body,
+        "user": user_json(login),
+        "created_at": now,
+        "updated_at": now,
+    })
+}
+
+fn label_json(name: &str) -> Value {
+    json!({
+        "id": 1,
+        "node_id": format!("L_{}", name),
+        "url": format!("https://api.github.com/repos/{}/{}/labels/{}", OWNER, REPO, name),
+        "name": name,
+        "description": null,
+        "color": "c0ffee",
+        "default": false,
+    })
+}
+
+fn user_json(login: &str) -> Value {
+    let url = format!("https://api.github.com/users/{}", login);
+    json!({
+        "login": login,
+        "id": 1,
+        "node_id": "U_1",
+        "avatar_url": format!("https://avatars.githubusercontent.com/{}", login),
+        "gravatar_id": "",
+        "url": url,
+        "html_url": format!("https://github.com/{}", login),
+        "followers_url": format!("{}/followers", url),
+        "following_url": format!("{}/following", url),
+        "gists_url": format!("{}/gists", url),
+        "starred_url": format!("{}/starred", url),
+        "subscriptions_url": format!("{}/subscriptions", url),
+        "organizations_url": format!("{}/orgs", url),
+        "repos_url": format!("{}/repos", url),
+        "events_url": format!("{}/events", url),
+        "received_events_url": format!("{}/received_events", url),
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+/// An issue in the shape returned by `GET /repos/{owner}/{repo}/issues`
+pub(crate) fn issue_json(number: u64, title: &str, state: &str, updated_at: &str, labels: &[&str]) -> Value {
+    let api_url = format!("https://api.github.com/repos/{}/{}/issues/{}", OWNER, REPO, number);
+    json!({
+        "id": number * 1000,
+        "node_id": format!("I_{}", number),
+        "url": api_url,
+        "repository_url": format!("https://api.github.com/repos/{}/{}", OWNER, REPO),
+        "labels_url": format!("{}/labels{{/name}}", api_url),
+        "comments_url": format!("{}/comments", api_url),
+        "events_url": format!("{}/events", api_url),
+        "html_url": format!("https://github.com/{}/{}/issues/{}", OWNER, REPO, number),
+        "number": number,
+        "state": state,
+        "title": title,
+        "body": format!("Body of issue {}", number),
+        "user": user_json("octocat"),
+        "labels": labels.iter().map(|name| label_json(name)).collect::<Vec<_>>(),
+        "assignee": user_json("alice"),
+        "assignees": [user_json("alice")],
+        "author_association": "OWNER",
+        "locked": false,
+        "comments": 0,
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": updated_at,
+    })
+}
+
+/// A pull request as it appears in the issues listing
+pub(crate) fn pull_request_json(number: u64, title: &str, updated_at: &str) -> Value {
+    let mut pr = issue_json(number, title, "open", updated_at, &[]);
+    let url = format!("https://api.github.com/repos/{}/{}/pulls/{}", OWNER, REPO, number);
+    pr["pull_request"] = json!({
+        "url": url,
+        "html_url": format!("https://github.com/{}/{}/pull/{}", OWNER, REPO, number),
+        "diff_url": format!("https://github.com/{}/{}/pull/{}.diff", OWNER, REPO, number),
+        "patch_url": format!("https://github.com/{}/{}/pull/{}.patch", OWNER, REPO, number),
+    });
+    pr
+}
@@ -4,6 +4,9 @@
 //! instead of relying on CLI tools, avoiding pager/editor interaction issues.
 
 pub mod db;
+pub mod error;
 pub mod git_ops;
 pub mod kanban;
+pub mod query;
+pub mod sync;
 pub mod web;
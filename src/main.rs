@@ -1,12 +1,20 @@
 mod db;
+pub mod error;
 pub mod git_ops;
 pub mod web;
 pub mod kanban;
+pub mod query;
+pub mod sync;
 
 use anyhow::Result;
 use chrono::Utc;
 use clap::{Parser, Subcommand};
-use db::{TaskDatabase, Issue, IssueStatus, IssuePriority};
+use db::{TaskDatabase, BoardColumn, Issue, IssueStatus, IssuePriority, IssueReferenceKind, LinkedCommit};
+use db::dependencies::{DependencyKind, IssueLink};
+use db::hierarchy::{task_list_progress, Progress};
+use error::Context;
+use std::io::Write;
+use sync::{ConflictPolicy, Resolution, SyncConflict, SyncService};
 use web::KanbanWebServer;
 
 #[derive(Parser)]
@@ -21,8 +29,21 @@ struct Cli {
 enum Commands {
     /// Initialize the database and show current status
     Init,
-    /// List all issues from the database
-    ListIssues,
+    /// List issues from the database, all of them unless a query is given
+    ListIssues {
+        /// Search query, e.g. 'label:bug status:open priority:>=high "login page" -label:wontfix'
+        #[arg(short, long)]
+        query: Option<String>,
+    },
+    /// Full-text search over issue titles and descriptions, and commit messages and file paths
+    Search {
+        /// Words to look for; each must match the start of a word
+        #[arg(required = true)]
+        terms: Vec<String>,
+        /// Maximum number of issues, and of commits, to show
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+    },
     /// Show database statistics
     DbStats,
     /// Show git commit history
@@ -37,32 +58,289 @@ enum Commands {
         #[arg(short, long, default_value_t = 3000)]
         port: u16,
     },
+    /// Database maintenance commands
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+    /// Configure the Kanban board's columns
+    Board {
+        #[command(subcommand)]
+        command: BoardCommands,
+    },
+    /// Two-way sync of GitHub-linked issues with the repository's `origin` (requires GITHUB_TOKEN)
+    Sync {
+        /// How to settle fields edited on both sides: local-wins, remote-wins or interactive
+        #[arg(long, default_value = "interactive")]
+        policy: ConflictPolicy,
+        /// Replay GitHub changes queued while offline instead of syncing
+        #[arg(long)]
+        flush: bool,
+    },
+    /// Create, inspect and edit issues
+    Issue {
+        /// Print results as JSON instead of human-readable text
+        #[arg(long, global = true)]
+        json: bool,
+        #[command(subcommand)]
+        command: IssueCommands,
+    },
+    /// List open issues that nothing open is blocking, most urgent first
+    Ready {
+        /// Print the issues as JSON instead of human-readable text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Reverse the latest changes: each issue command, sync or board request is one step
+    Undo {
+        /// How many operations to undo, newest first
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// List, restore or purge deleted issues
+    Trash {
+        #[command(subcommand)]
+        command: Option<TrashCommands>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommands {
+    /// List deleted issues, most recently deleted first (the default)
+    List,
+    /// Put a deleted issue back
+    Restore {
+        /// Issue id
+        id: i64,
+    },
+    /// Delete issues in the trash for good; empties the whole trash unless ids are given
+    Purge {
+        /// Issue ids
+        ids: Vec<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Apply pending schema migrations
+    Migrate {
+        /// Only list the pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BoardCommands {
+    /// List the board's columns in display order
+    Columns,
+    /// Add a column, or change the one with this id
+    SetColumn {
+        /// Column id used by the move API
+        id: String,
+        /// Column heading
+        #[arg(short, long)]
+        title: String,
+        /// CSS color for the column header
+        #[arg(short, long, default_value = "#f3f4f6")]
+        color: String,
+        /// Workflow label that places issues in this column
+        #[arg(short, long)]
+        label: Option<String>,
+        /// Status that places issues in this column and is set when moving into it
+        #[arg(short, long)]
+        status: Option<IssueStatus>,
+        /// Columns are shown in ascending position
+        #[arg(short, long, default_value_t = 0)]
+        position: i64,
+        /// Most issues the column should hold; moves past it need forcing
+        #[arg(short, long)]
+        wip_limit: Option<u32>,
+    },
+    /// Remove a column
+    RemoveColumn {
+        /// Column id
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IssueCommands {
+    /// Create a new issue
+    New {
+        /// Issue title
+        #[arg(short, long)]
+        title: String,
+        /// Issue description (Markdown)
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Priority: low, medium, high or critical
+        #[arg(short, long, default_value = "medium")]
+        priority: IssuePriority,
+        /// Assignee login
+        #[arg(short, long)]
+        assignee: Option<String>,
+        /// Label to attach (repeatable or comma-separated)
+        #[arg(short, long = "label", value_delimiter = ',')]
+        labels: Vec<String>,
+        /// Make the issue a sub-task of this one
+        #[arg(long, value_name = "ID")]
+        parent: Option<i64>,
+    },
+    /// Show a single issue
+    Show {
+        /// Issue id
+        id: i64,
+    },
+    /// Show who changed an issue and when, oldest first
+    History {
+        /// Issue id; deleted issues keep their history
+        id: i64,
+    },
+    /// Edit an issue's fields; omitted fields are left unchanged
+    Edit {
+        /// Issue id
+        id: i64,
+        /// New title
+        #[arg(short, long)]
+        title: Option<String>,
+        /// New description (pass an empty string to clear it)
+        #[arg(short, long)]
+        description: Option<String>,
+        /// New priority: low, medium, high or critical
+        #[arg(short, long)]
+        priority: Option<IssuePriority>,
+        /// New assignee (pass an empty string to unassign)
+        #[arg(short, long)]
+        assignee: Option<String>,
+        /// Replace all labels (repeatable or comma-separated)
+        #[arg(short, long = "label", value_delimiter = ',')]
+        labels: Option<Vec<String>>,
+        /// Make the issue a sub-task of this one
+        #[arg(long, value_name = "ID")]
+        parent: Option<i64>,
+        /// Stop the issue being a sub-task
+        #[arg(long, conflicts_with = "parent")]
+        no_parent: bool,
+    },
+    /// Close an issue
+    Close {
+        /// Issue id
+        id: i64,
+    },
+    /// Reopen a closed or resolved issue
+    Reopen {
+        /// Issue id
+        id: i64,
+    },
+    /// Move an issue to the trash (see `atask trash`)
+    Delete {
+        /// Issue id
+        id: i64,
+    },
+    /// Assign an issue, or unassign it when no assignee is given
+    Assign {
+        /// Issue id
+        id: i64,
+        /// Assignee login
+        assignee: Option<String>,
+    },
+    /// Comment on an issue, or list its comments when no text is given
+    Comment {
+        /// Issue id
+        id: i64,
+        /// Comment text (Markdown)
+        body: Option<String>,
+        /// Comment author (defaults to $USER)
+        #[arg(long)]
+        author: Option<String>,
+        /// Replace the text of this comment instead of adding one
+        #[arg(long, value_name = "COMMENT_ID", requires = "body")]
+        edit: Option<i64>,
+        /// Delete this comment
+        #[arg(long, value_name = "COMMENT_ID", conflicts_with_all = ["body", "edit"])]
+        delete: Option<i64>,
+    },
+    /// Link an issue to another, e.g. `atask issue link 3 blocks 5`
+    Link {
+        /// Issue id
+        id: i64,
+        /// blocks, blocked-by, duplicates, duplicated-by or relates-to
+        kind: DependencyKind,
+        /// The other issue's id
+        other: i64,
+        /// Remove the link instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Add or remove labels on an issue
+    Label {
+        /// Issue id
+        id: i64,
+        /// Label to add (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        add: Vec<String>,
+        /// Label to remove (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        remove: Vec<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
     let cli = Cli::parse();
 
     match cli.command {
         Commands::Init => {
             init_database().await?;
         }
-        Commands::ListIssues => {
-            let db = TaskDatabase::new("atask.db").await?;
-            let issues = db.get_all_issues().await?;
+        Commands::ListIssues { query } => {
+            let db = open_database().await?;
+            let issues = match query {
+                Some(query) => db.query_issues(&query::Query::parse(&query)?).await?,
+                None => db.get_all_issues().await?,
+            };
             
             println!("📝 Issues ({}):", issues.len());
             for issue in &issues {
                 println!("   - #{}: {} [{}] - Labels: {}", 
                     issue.id.unwrap_or(0), 
                     issue.title,
-                    issue.status.to_string(),
+                    issue.status,
                     issue.labels.join(", ")
                 );
             }
         }
+        Commands::Search { terms, limit } => {
+            let db = open_database().await?;
+            let results = db.search(&terms.join(" "), limit).await?;
+            let highlight = |snippet: &db::search::Snippet| snippet.highlight("**", "**", |text| text.replace('\n', " "));
+
+            if results.is_empty() {
+                println!("🔍 No matches for '{}'", terms.join(" "));
+            }
+            if !results.issues.is_empty() {
+                println!("📝 Issues ({}):", results.issues.len());
+                for hit in &results.issues {
+                    println!("   - #{}: {} [{}]", hit.issue.id.unwrap_or(0), hit.issue.title, hit.issue.status);
+                    println!("       {}", highlight(&hit.snippet));
+                }
+            }
+            if !results.commits.is_empty() {
+                println!("📦 Commits ({}):", results.commits.len());
+                for hit in &results.commits {
+                    println!("   - {} by {} ({})",
+                        &hit.commit.hash[..8],
+                        hit.commit.author_name,
+                        hit.commit.commit_date.format("%Y-%m-%d %H:%M")
+                    );
+                    println!("       {}", highlight(&hit.snippet));
+                }
+            }
+        }
         Commands::DbStats => {
-            let db = TaskDatabase::new("atask.db").await?;
+            let db = open_database().await?;
             let commits = db.get_all_commits().await?;
             let labels = db.get_all_labels().await?;
             let issues = db.get_all_issues().await?;
@@ -73,7 +351,7 @@ async fn main() -> Result<()> {
             println!("   Issues: {}", issues.len());
         }
         Commands::Commits { count } => {
-            let db = TaskDatabase::new("atask.db").await?;
+            let db = open_database().await?;
             let commits = db.get_all_commits().await?;
             
             println!("📦 Git Commits ({}):", commits.len().min(count));
@@ -89,7 +367,7 @@ async fn main() -> Result<()> {
             println!("🚀 Starting Kanban Web Server...");
             
             // Initialize database
-            let db = TaskDatabase::new("atask.db").await?;
+            let db = open_database().await?;
             println!("✅ Database initialized");
             
             // Create web server with database
@@ -99,16 +377,579 @@ async fn main() -> Result<()> {
             
             server.serve(port).await?;
         }
+        Commands::Db { command: DbCommands::Migrate { dry_run } } => {
+            // Open without migrating so pending migrations can be listed first
+            let db = TaskDatabase::open("atask.db").await?;
+            let current = db.schema_version().await?;
+            let pending = db.pending_migrations().await?;
+
+            println!("🗄️  Schema version: {}", current);
+            if pending.is_empty() {
+                println!("✅ Database is up to date");
+            } else if dry_run {
+                println!("📋 Pending migrations ({}):", pending.len());
+                for migration in &pending {
+                    println!("   - {}: {}", migration.version, migration.description);
+                }
+            } else {
+                for migration in db.migrate().await? {
+                    println!("✅ Applied migration {}: {}", migration.version, migration.description);
+                }
+                println!("🗄️  Schema version: {}", db.schema_version().await?);
+            }
+        }
+        Commands::Board { command } => {
+            let db = open_database().await?;
+            run_board_command(&db, command).await?;
+        }
+        Commands::Sync { flush: true, .. } => {
+            let db = open_database().await?;
+            let github = origin_github()?;
+            println!("📤 Replaying queued GitHub changes for {}", github.repository());
+
+            let report = db.flush_outbox(&github).await?;
+            println!("✅ Sent {}, dropped {} superseded label changes", report.sent, report.coalesced);
+            for entry in &report.remaining {
+                match &entry.last_error {
+                    Some(error) => println!("❌ [{}] {} (attempt {}): {}", entry.id, entry.mutation, entry.attempts, error),
+                    None => println!("⏳ [{}] {} (waiting behind a failed change)", entry.id, entry.mutation),
+                }
+            }
+            for entry in &report.rejected {
+                println!("🗑️  [{}] {} was refused by GitHub and dropped: {}",
+                    entry.id, entry.mutation, entry.last_error.as_deref().unwrap_or_default());
+            }
+        }
+        Commands::Sync { policy, .. } => {
+            let db = open_database().await?;
+            let github = origin_github()?;
+            println!("🔄 Syncing issues with {} ({})", github.repository(), policy);
+
+            let report = SyncService::new(&db, &github, policy).sync(ask_resolution).await?;
+
+            if report.outbox_sent > 0 {
+                println!("📤 Sent {} GitHub changes queued earlier", report.outbox_sent);
+            }
+            println!("✅ Created {}, pushed {}, pulled {}, unchanged {}",
+                report.created, report.pushed, report.pulled, report.unchanged);
+            if report.queued > 0 {
+                println!("⏳ {} issues have changes queued until GitHub can take them; `atask sync --flush` retries", report.queued);
+            }
+            if report.comments_pushed + report.comments_pulled > 0 {
+                println!("💬 Comments pushed {}, pulled {}", report.comments_pushed, report.comments_pulled);
+            }
+            for conflict in &report.conflicts {
+                let fields: Vec<&str> = conflict.fields.iter().map(|f| f.field).collect();
+                println!("⚔️  #{} {}: {} ({:?})", conflict.number, conflict.title, fields.join(", "), conflict.resolution);
+            }
+            for (number, error) in &report.failed {
+                println!("❌ #{}: {}", number, error);
+            }
+        }
+        Commands::Issue { json, command } => {
+            let db = open_database().await?;
+            db.operation(run_issue_command(&db, command, json)).await?;
+        }
+        Commands::Ready { json } => {
+            let db = open_database().await?;
+            let issues = db.get_ready_issues().await?;
+            if json {
+                print_json(&issues)?;
+            } else {
+                println!("🟢 Ready issues ({}):", issues.len());
+                for issue in &issues {
+                    println!("   - #{}: {} [{}, {}]", issue.id.unwrap_or(0), issue.title, issue.status, issue.priority);
+                }
+            }
+        }
+        Commands::Undo { steps } => {
+            let db = open_database().await?;
+            let undone = db.undo_last(steps).await?;
+            if undone.is_empty() {
+                println!("🤷 Nothing to undo");
+            }
+            for operation in &undone {
+                println!("↩️  Undid operation {}: {}", operation.id, operation);
+            }
+        }
+        Commands::Trash { command } => {
+            let db = open_database().await?;
+            match command.unwrap_or(TrashCommands::List) {
+                TrashCommands::List => {
+                    let trashed = db.get_trashed_issues().await?;
+                    println!("🗑️  Trash ({}):", trashed.len());
+                    for trashed_issue in &trashed {
+                        println!("   - #{}: {} (deleted {})",
+                            trashed_issue.issue.id.unwrap_or(0),
+                            trashed_issue.issue.title,
+                            trashed_issue.deleted_at.format("%Y-%m-%d %H:%M")
+                        );
+                    }
+                }
+                TrashCommands::Restore { id } => {
+                    if !db.restore_issue(id).await? {
+                        anyhow::bail!("Issue #{} is not in the trash", id);
+                    }
+                    println!("♻️  Restored issue #{}", id);
+                }
+                TrashCommands::Purge { ids } => {
+                    let ids = (!ids.is_empty()).then_some(ids);
+                    let purged = db.purge_trash(ids.as_deref()).await?;
+                    println!("🔥 Purged {} issues", purged);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_board_command(db: &TaskDatabase, command: BoardCommands) -> Result<()> {
+    match command {
+        BoardCommands::Columns => {
+            let columns = db.get_board_columns().await?;
+            println!("🗂️  Board columns ({}):", columns.len());
+            for column in &columns {
+                println!("   {:>3}. {} ({}) {} label: {}, status: {}, WIP limit: {}",
+                    column.position,
+                    column.title,
+                    column.id,
+                    column.color,
+                    column.label.as_deref().unwrap_or("-"),
+                    column.status.as_ref().map(|s| s.to_string()).unwrap_or_else(|| "-".to_string()),
+                    column.wip_limit.map(|limit| limit.to_string()).unwrap_or_else(|| "-".to_string())
+                );
+            }
+        }
+        BoardCommands::SetColumn { id, title, color, label, status, position, wip_limit } => {
+            db.save_board_column(&BoardColumn { id: id.clone(), title, color, label, status, position, wip_limit }).await?;
+            println!("✅ Saved column '{}'", id);
+        }
+        BoardCommands::RemoveColumn { id } => {
+            if !db.delete_board_column(&id).await? {
+                anyhow::bail!("Board column '{}' not found", id);
+            }
+            println!("🗑️  Removed column '{}'", id);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_issue_command(db: &TaskDatabase, command: IssueCommands, json: bool) -> Result<()> {
+    match command {
+        IssueCommands::New { title, description, priority, assignee, labels, parent } => {
+            if title.trim().is_empty() {
+                anyhow::bail!("Issue title must not be empty");
+            }
+            let labels = normalize_labels(labels);
+            ensure_labels_exist(db, &labels).await?;
+
+            let issue = Issue {
+                id: None,
+                title,
+                description,
+                status: IssueStatus::Open,
+                priority,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                assignee,
+                labels,
+                external_ref: None,
+                parent_id: parent,
+            };
+            let id = db.insert_issue(&issue).await?;
+            let created = fetch_issue(db, id).await?;
+
+            if json {
+                print_json(&created)?;
+            } else {
+                println!("✅ Created issue #{}", id);
+                print_issue(&created);
+            }
+        }
+        IssueCommands::Show { id } => {
+            let issue = fetch_issue(db, id).await?;
+            let commits = db.get_commits_for_issue(id).await?;
+            let comments = db.get_comments_for_issue(id).await?;
+            let links = db.get_issue_links(id).await?;
+            let children = db.get_children(id).await?;
+            let subtasks = db.get_subtask_progress(id).await?;
+            if json {
+                print_json(&IssueDetails { issue, commits, comments, links, children, subtasks })?;
+            } else {
+                print_issue(&issue);
+                print_subtasks(&children, subtasks);
+                print_links(&links);
+                print_linked_commits(&commits);
+                print_comments(&comments);
+            }
+        }
+        IssueCommands::History { id } => {
+            let events = db.get_issue_events(id).await?;
+            if events.is_empty() {
+                fetch_issue(db, id).await?;
+            }
+            if json {
+                print_json(&events)?;
+            } else {
+                println!("🕘 History of issue #{} ({}):", id, events.len());
+                for event in &events {
+                    println!("   {}  {}  {}", event.created_at.format("%Y-%m-%d %H:%M"), event.actor, event);
+                }
+            }
+        }
+        IssueCommands::Edit { id, title, description, priority, assignee, labels, parent, no_parent } => {
+            let mut issue = fetch_issue(db, id).await?;
+
+            if let Some(title) = title {
+                if title.trim().is_empty() {
+                    anyhow::bail!("Issue title must not be empty");
+                }
+                issue.title = title;
+            }
+            if let Some(description) = description {
+                issue.description = Some(description).filter(|d| !d.is_empty());
+            }
+            if let Some(priority) = priority {
+                issue.priority = priority;
+            }
+            if let Some(assignee) = assignee {
+                issue.assignee = Some(assignee).filter(|a| !a.is_empty());
+            }
+            if let Some(labels) = labels {
+                let labels = normalize_labels(labels);
+                ensure_labels_exist(db, &labels).await?;
+                issue.labels = labels;
+            }
+            if parent.is_some() || no_parent {
+                issue.parent_id = parent;
+            }
+
+            db.update_issue(&issue).await?;
+            report_issue(db, id, json, "Updated").await?;
+        }
+        IssueCommands::Close { id } => {
+            fetch_issue(db, id).await?;
+            db.update_issue_status(id, IssueStatus::Closed).await?;
+            report_issue(db, id, json, "Closed").await?;
+        }
+        IssueCommands::Reopen { id } => {
+            fetch_issue(db, id).await?;
+            db.update_issue_status(id, IssueStatus::Open).await?;
+            report_issue(db, id, json, "Reopened").await?;
+        }
+        IssueCommands::Delete { id } => {
+            fetch_issue(db, id).await?;
+            db.delete_issue(id).await?;
+            if json {
+                print_json(&serde_json::json!({ "deleted": id }))?;
+            } else {
+                println!("🗑️  Moved issue #{} to the trash", id);
+            }
+        }
+        IssueCommands::Assign { id, assignee } => {
+            let mut issue = fetch_issue(db, id).await?;
+            issue.assignee = assignee.filter(|a| !a.is_empty());
+            db.update_issue(&issue).await?;
+            report_issue(db, id, json, "Updated assignee of").await?;
+        }
+        IssueCommands::Comment { id, body, author, edit, delete } => {
+            fetch_issue(db, id).await?;
+            if let Some(comment_id) = delete {
+                fetch_comment(db, id, comment_id).await?;
+                db.delete_comment(comment_id).await?;
+                if json {
+                    print_json(&serde_json::json!({ "deleted": comment_id }))?;
+                } else {
+                    println!("🗑️  Deleted comment {} on issue #{}", comment_id, id);
+                }
+                return Ok(());
+            }
+
+            let comment = match (body, edit) {
+                (None, _) => {
+                    let comments = db.get_comments_for_issue(id).await?;
+                    if json {
+                        print_json(&comments)?;
+                    } else if comments.is_empty() {
+                        println!("💬 No comments on issue #{}", id);
+                    } else {
+                        print_comments(&comments);
+                    }
+                    return Ok(());
+                }
+                (Some(body), Some(comment_id)) => {
+                    let mut comment = fetch_comment(db, id, comment_id).await?;
+                    comment.body = body;
+                    comment.updated_at = Utc::now();
+                    db.update_comment(&comment).await?;
+                    comment
+                }
+                (Some(body), None) => {
+                    let author = author
+                        .filter(|a| !a.trim().is_empty())
+                        .unwrap_or_else(|| db.actor().to_string());
+                    let now = Utc::now();
+                    let mut comment = db::Comment {
+                        id: None,
+                        issue_id: id,
+                        author,
+                        body,
+                        created_at: now,
+                        updated_at: now,
+                        external_ref: None,
+                        synced_at: None,
+                    };
+                    comment.id = Some(db.insert_comment(&comment).await?);
+                    comment
+                }
+            };
+
+            if json {
+                print_json(&comment)?;
+            } else {
+                println!("💬 {} comment {} on issue #{}", if edit.is_some() { "Updated" } else { "Added" }, comment.id.unwrap_or(0), id);
+            }
+        }
+        IssueCommands::Label { id, add, remove } => {
+            fetch_issue(db, id).await?;
+            let add = normalize_labels(add);
+            let remove = normalize_labels(remove);
+            if add.is_empty() && remove.is_empty() {
+                anyhow::bail!("Nothing to do: pass --add and/or --remove");
+            }
+            ensure_labels_exist(db, &add).await?;
+
+            for label in &remove {
+                db.remove_label_from_issue(id, label).await?;
+            }
+            for label in &add {
+                db.add_label_to_issue(id, label).await?;
+            }
+            report_issue(db, id, json, "Relabeled").await?;
+        }
+        IssueCommands::Link { id, kind, other, remove } => {
+            let changed = if remove {
+                db.unlink_issues(id, kind, other).await?
+            } else {
+                db.link_issues(id, kind, other).await?
+            };
+            let links = db.get_issue_links(id).await?;
+            if json {
+                print_json(&links)?;
+            } else {
+                match (remove, changed) {
+                    (false, true) => println!("🔗 #{} now {} #{}", id, kind, other),
+                    (false, false) => println!("🔗 #{} already {} #{}", id, kind, other),
+                    (true, true) => println!("✂️  #{} no longer {} #{}", id, kind, other),
+                    (true, false) => println!("🤷 #{} was not linked as {} #{}", id, kind, other),
+                }
+                print_links(&links);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Load an issue or fail with a "not found" error
+async fn fetch_issue(db: &TaskDatabase, id: i64) -> Result<Issue> {
+    db.get_issue_by_id(id).await?
+        .ok_or_else(|| anyhow::anyhow!("Issue #{} not found", id))
+}
+
+/// Load a comment on an issue or fail with a "not found" error
+async fn fetch_comment(db: &TaskDatabase, issue_id: i64, id: i64) -> Result<db::Comment> {
+    db.get_comment(id).await?
+        .filter(|comment| comment.issue_id == issue_id)
+        .ok_or_else(|| anyhow::anyhow!("Comment {} not found on issue #{}", id, issue_id))
+}
+
+/// Re-read an issue after a mutation and print it
+async fn report_issue(db: &TaskDatabase, id: i64, json: bool, verb: &str) -> Result<()> {
+    let issue = fetch_issue(db, id).await?;
+    if json {
+        print_json(&issue)?;
+    } else {
+        println!("✅ {} issue #{}", verb, id);
+        print_issue(&issue);
+    }
+    Ok(())
+}
+
+/// Trim label names and drop empty entries (e.g. from `--label ""`)
+fn normalize_labels(labels: Vec<String>) -> Vec<String> {
+    labels.into_iter()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect()
+}
+
+/// Fail early on unknown labels instead of letting the database drop them silently
+async fn ensure_labels_exist(db: &TaskDatabase, labels: &[String]) -> Result<()> {
+    for label in labels {
+        if db.get_label_by_name(label).await?.is_none() {
+            anyhow::bail!("Label '{}' does not exist", label);
+        }
     }
+    Ok(())
+}
 
+/// JSON shape of `atask issue show`: the issue plus the commits that reference it, its comments,
+/// its links and its direct sub-tasks
+#[derive(serde::Serialize)]
+struct IssueDetails {
+    #[serde(flatten)]
+    issue: Issue,
+    commits: Vec<LinkedCommit>,
+    comments: Vec<db::Comment>,
+    links: Vec<IssueLink>,
+    children: Vec<Issue>,
+    /// Sub-tasks done at any depth
+    subtasks: Option<Progress>,
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
     Ok(())
 }
 
+fn print_linked_commits(commits: &[LinkedCommit]) {
+    if commits.is_empty() {
+        return;
+    }
+    println!();
+    println!("📦 Linked commits ({}):", commits.len());
+    for linked in commits {
+        let commit = &linked.commit;
+        println!("   - {} {} by {} (+{} -{}){}",
+            &commit.hash[..8.min(commit.hash.len())],
+            commit.message.lines().next().unwrap_or(""),
+            commit.author_name,
+            commit.insertions,
+            commit.deletions,
+            if linked.kind == IssueReferenceKind::Closes { " [closes]" } else { "" }
+        );
+    }
+}
+
+fn print_subtasks(children: &[Issue], progress: Option<Progress>) {
+    let Some(progress) = progress else {
+        return;
+    };
+    println!();
+    println!("🧩 Sub-tasks ({} done):", progress);
+    for child in children {
+        println!("   - #{} {} ({})", child.id.unwrap_or(0), child.title, child.status);
+    }
+}
+
+fn print_links(links: &[IssueLink]) {
+    if links.is_empty() {
+        return;
+    }
+    println!();
+    println!("🔗 Links ({}):", links.len());
+    for link in links {
+        println!("   - {}", link);
+    }
+}
+
+fn print_comments(comments: &[db::Comment]) {
+    if comments.is_empty() {
+        return;
+    }
+    println!();
+    println!("💬 Comments ({}):", comments.len());
+    for comment in comments {
+        println!("   [{}] {} on {}{}",
+            comment.id.unwrap_or(0),
+            comment.author,
+            comment.created_at.format("%Y-%m-%d %H:%M"),
+            if comment.updated_at > comment.created_at { " (edited)" } else { "" }
+        );
+        for line in comment.body.lines() {
+            println!("       {}", line);
+        }
+    }
+}
+
+fn print_issue(issue: &Issue) {
+    println!("📝 #{}: {}", issue.id.unwrap_or(0), issue.title);
+    println!("   Status: {}", issue.status);
+    println!("   Priority: {}", issue.priority);
+    println!("   Assignee: {}", issue.assignee.as_deref().unwrap_or("(unassigned)"));
+    println!("   Labels: {}", issue.labels.join(", "));
+    if let Some(parent_id) = issue.parent_id {
+        println!("   Parent: #{}", parent_id);
+    }
+    if let Some(tasks) = issue.description.as_deref().and_then(task_list_progress) {
+        println!("   Checklist: {} done", tasks);
+    }
+    println!("   Created: {}", issue.created_at.format("%Y-%m-%d %H:%M"));
+    println!("   Updated: {}", issue.updated_at.format("%Y-%m-%d %H:%M"));
+    if let Some(description) = &issue.description {
+        println!();
+        for line in description.lines() {
+            println!("   {}", line);
+        }
+    }
+}
+
+/// Ask on the terminal how to settle a sync conflict; end of input skips it
+fn ask_resolution(conflict: &SyncConflict) -> error::Result<Resolution> {
+    println!("\n⚔️  Conflict on #{} {}", conflict.number, conflict.title);
+    for field in &conflict.fields {
+        println!("   {}:", field.field);
+        println!("     local:  {}", field.local);
+        println!("     remote: {}", field.remote);
+    }
+
+    loop {
+        print!("   Keep [l]ocal, keep [r]emote or [s]kip? ");
+        std::io::stdout().flush().storage_context("Could not ask about the conflict")?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).storage_context("Could not read the answer")? == 0 {
+            return Ok(Resolution::Skip);
+        }
+        match answer.trim() {
+            "l" | "local" => return Ok(Resolution::KeepLocal),
+            "r" | "remote" => return Ok(Resolution::KeepRemote),
+            "s" | "skip" => return Ok(Resolution::Skip),
+            _ => continue,
+        }
+    }
+}
+
+/// Open `atask.db`, recording changes in the issue history as made by `$USER`
+async fn open_database() -> Result<TaskDatabase> {
+    let actor = std::env::var("USER")
+        .ok()
+        .filter(|user| !user.trim().is_empty())
+        .unwrap_or_else(|| "atask".to_string());
+    Ok(TaskDatabase::new("atask.db").await?.with_actor(actor))
+}
+
+/// GitHub client for the repository's `origin` remote
+fn origin_github() -> Result<git_ops::GitHubOps> {
+    let (owner, repo) = git_ops::GitOps::new()?.parse_github_repo("origin")?;
+    Ok(git_ops::GitHubOps::from_env(owner, repo)?)
+}
+
+/// Import issues from the repository's `origin` on GitHub, picking up where the last import stopped
+async fn import_github_issues(db: &TaskDatabase) -> Result<usize> {
+    let github = origin_github()?;
+    let since = db.get_github_import_mark(&github.repository()).await?;
+
+    Ok(db.import_github_issues(&github, since).await?)
+}
+
 async fn init_database() -> Result<()> {
     println!("🚀 Initializing ATask - Git Task Manager");
     
     // Initialize database
-    let db = TaskDatabase::new("atask.db").await?;
+    let db = open_database().await?;
     println!("✅ Database initialized");
     
     // Create default labels
@@ -161,9 +1002,9 @@ async fn init_database() -> Result<()> {
     let issues = db.get_all_issues().await?;
     println!("   Issues: {}", issues.len());
     
-    // Try to load GitHub issues using gh CLI
+    // Try to load GitHub issues through the GitHub API
     println!("\n🔍 Attempting to load GitHub issues...");
-    match db.load_github_issues_via_cli().await {
+    match import_github_issues(&db).await {
         Ok(count) => {
             if count > 0 {
                 println!("✅ Loaded {} GitHub issues", count);
@@ -172,8 +1013,8 @@ async fn init_database() -> Result<()> {
             }
         }
         Err(e) => {
-            println!("⚠️  Could not load GitHub issues via gh CLI: {}", e);
-            println!("   This is normal if 'gh' is not installed or not authenticated");
+            println!("⚠️  Could not load GitHub issues: {}", e);
+            println!("   This is normal if GITHUB_TOKEN is not set or 'origin' is not a GitHub remote");
             
             // Create a sample issue if none exist and gh loading failed
             let current_issues = db.get_all_issues().await?;
@@ -189,6 +1030,8 @@ async fn init_database() -> Result<()> {
                     updated_at: Utc::now(),
                     assignee: None,
                     labels: vec!["documentation".to_string(), "good first issue".to_string()],
+                    external_ref: None,
+                    parent_id: None,
                 };
                 
                 let issue_id = db.insert_issue(&sample_issue).await?;
@@ -204,7 +1047,7 @@ async fn init_database() -> Result<()> {
         println!("   - #{}: {} [{}] - Labels: {}", 
             issue.id.unwrap_or(0), 
             issue.title,
-            issue.status.to_string(),
+            issue.status,
             issue.labels.join(", ")
         );
     }
@@ -215,6 +1058,7 @@ async fn init_database() -> Result<()> {
     println!("\n🎉 ATask database is ready!");
     println!("\n💡 Next steps:");
     println!("   - Use 'atask list-issues' to see all issues");
+    println!("   - Use 'atask issue new --title <TITLE>' to create an issue");
     println!("   - Use 'atask web' to start the Kanban web interface");
     println!("   - Use 'atask commits' to see git history");
     
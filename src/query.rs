@@ -0,0 +1,284 @@
+//! Issue search queries, e.g. `label:bug status:open priority:>=high "login page" -label:wontfix`.
+//!
+//! `Query::parse` turns the text into a list of terms that must all match;
+//! `TaskDatabase::query_issues` compiles them to parameterized SQL.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::db::{IssuePriority, IssueStatus};
+use crate::error::{Error, Result};
+
+/// A parsed query: every term has to match
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+/// One condition, possibly negated with a leading `-`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub negated: bool,
+    pub filter: Filter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// `label:bug`; label names match case-insensitively
+    Label(String),
+    /// `status:open`
+    Status(IssueStatus),
+    /// `assignee:alice`, case-insensitive
+    Assignee(String),
+    /// `priority:high`, `priority:>=high`
+    Priority(Comparison, IssuePriority),
+    /// `created:>=2024-01-31`, compared by day
+    Created(Comparison, NaiveDate),
+    /// `updated:<2024-01-31`, compared by day
+    Updated(Comparison, NaiveDate),
+    /// A bare word or `"quoted phrase"`, found in the title or description
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// Split a leading `>=`, `<=`, `>`, `<` or `=` off a value
+    fn split(value: &str) -> (Self, &str) {
+        for (prefix, comparison) in [
+            (">=", Comparison::Ge),
+            ("<=", Comparison::Le),
+            (">", Comparison::Gt),
+            ("<", Comparison::Lt),
+            ("=", Comparison::Eq),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (comparison, rest);
+            }
+        }
+        (Comparison::Eq, value)
+    }
+
+    /// The SQL operator
+    pub fn operator(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+
+    pub fn holds<T: Ord>(self, left: &T, right: &T) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+impl Query {
+    /// Parse query text; an empty string matches every issue
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        let mut chars = input.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let negated = chars.next_if_eq(&'-').is_some();
+            let mut field: Option<String> = None;
+            let mut word = String::new();
+            let mut quoted = false;
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+                match c {
+                    '"' => {
+                        quoted = true;
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some(c) => word.push(c),
+                                None => return Err(Error::Validation("Unterminated quote in query".to_string())),
+                            }
+                        }
+                    }
+                    ':' if field.is_none() && !quoted => field = Some(std::mem::take(&mut word)),
+                    c => word.push(c),
+                }
+            }
+
+            let filter = match field {
+                Some(field) => Self::filter(&field, &word)?,
+                None if word.is_empty() && !quoted => {
+                    return Err(Error::Validation("Expected a term after '-'".to_string()));
+                }
+                None => Filter::Text(word),
+            };
+            terms.push(Term { negated, filter });
+        }
+
+        Ok(Self { terms })
+    }
+
+    fn filter(field: &str, value: &str) -> Result<Filter> {
+        if value.is_empty() {
+            return Err(Error::Validation(format!("Missing value for '{}:'", field)));
+        }
+        let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| Error::Validation(format!("Invalid date '{}' (use YYYY-MM-DD)", value)));
+
+        match field.to_lowercase().as_str() {
+            "label" => Ok(Filter::Label(value.to_string())),
+            "status" => Ok(Filter::Status(value.to_lowercase().parse()?)),
+            "assignee" => Ok(Filter::Assignee(value.to_string())),
+            "priority" => {
+                let (comparison, value) = Comparison::split(value);
+                Ok(Filter::Priority(comparison, value.to_lowercase().parse()?))
+            }
+            "created" => {
+                let (comparison, value) = Comparison::split(value);
+                Ok(Filter::Created(comparison, date(value)?))
+            }
+            "updated" => {
+                let (comparison, value) = Comparison::split(value);
+                Ok(Filter::Updated(comparison, date(value)?))
+            }
+            _ => Err(Error::Validation(format!(
+                "Unknown filter '{}' (use label, status, assignee, priority, created or updated)",
+                field
+            ))),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+/// Quote a value if it wouldn't survive parsing as a bare word
+fn quoted(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == ':') || value.starts_with('-') {
+        format!("\"{}\"", value.replace('"', ""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "-")?;
+        }
+        let comparison = |comparison: &Comparison| match comparison {
+            Comparison::Eq => "",
+            other => other.operator(),
+        };
+        match &self.filter {
+            Filter::Label(label) => write!(f, "label:{}", quoted(label)),
+            Filter::Status(status) => write!(f, "status:{}", status),
+            Filter::Assignee(assignee) => write!(f, "assignee:{}", quoted(assignee)),
+            Filter::Priority(c, priority) => write!(f, "priority:{}{}", comparison(c), priority),
+            Filter::Created(c, date) => write!(f, "created:{}{}", comparison(c), date),
+            Filter::Updated(c, date) => write!(f, "updated:{}{}", comparison(c), date),
+            Filter::Text(text) => write!(f, "{}", quoted(text)),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", term)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for Query {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Query> for String {
+    fn from(query: Query) -> Self {
+        query.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        let query = Query::parse(r#"label:bug status:open assignee:alice priority:>=high "free text" -label:wontfix login"#).unwrap();
+        let term = |negated, filter| Term { negated, filter };
+        assert_eq!(query.terms, vec![
+            term(false, Filter::Label("bug".to_string())),
+            term(false, Filter::Status(IssueStatus::Open)),
+            term(false, Filter::Assignee("alice".to_string())),
+            term(false, Filter::Priority(Comparison::Ge, IssuePriority::High)),
+            term(false, Filter::Text("free text".to_string())),
+            term(true, Filter::Label("wontfix".to_string())),
+            term(false, Filter::Text("login".to_string())),
+        ]);
+
+        let query = Query::parse(r#"  label:"needs review"  created:<2024-02-01 -"draft"  "#).unwrap();
+        assert_eq!(query.terms, vec![
+            term(false, Filter::Label("needs review".to_string())),
+            term(false, Filter::Created(Comparison::Lt, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap())),
+            term(true, Filter::Text("draft".to_string())),
+        ]);
+        assert!(Query::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for input in ["colour:red", "label:", "status:maybe", "priority:>=urgent", "created:yesterday", "\"open", "a - b"] {
+            let err = Query::parse(input).unwrap_err();
+            assert_eq!(err.kind(), "validation", "{}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let text = r#"label:"needs review" -assignee:bob priority:<medium updated:>=2024-01-31 "two words" plain"#;
+        let query = Query::parse(text).unwrap();
+        assert_eq!(query.to_string(), text);
+        assert_eq!(Query::parse(&query.to_string()).unwrap(), query);
+    }
+}
@@ -0,0 +1,653 @@
+//! Two-way synchronisation between local issues and GitHub.
+//!
+//! Each GitHub-linked issue has a snapshot of the fields both sides agreed on at
+//! the last sync. A side whose `updated_at` moved past the snapshot has changed;
+//! field by field, a change on one side is copied to the other, and a field that
+//! changed differently on both sides is a conflict settled by a `ConflictPolicy`.
+
+use chrono::{DateTime, Utc};
+use octocrab::models::issues::Issue as GitHubIssue;
+use octocrab::models::IssueState;
+use serde::Serialize;
+
+use crate::db::{Delivery, Issue, IssueStatus, SyncSnapshot, TaskDatabase};
+use crate::error::{Context, Error, Result};
+use crate::git_ops::{GitHubMutation, GitHubOps, IssueParams};
+
+/// How to settle a field that changed differently on both sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    LocalWins,
+    RemoteWins,
+    /// Ask for each conflicting issue
+    Interactive,
+}
+
+impl std::fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConflictPolicy::LocalWins => write!(f, "local-wins"),
+            ConflictPolicy::RemoteWins => write!(f, "remote-wins"),
+            ConflictPolicy::Interactive => write!(f, "interactive"),
+        }
+    }
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "local-wins" => Ok(ConflictPolicy::LocalWins),
+            "remote-wins" => Ok(ConflictPolicy::RemoteWins),
+            "interactive" => Ok(ConflictPolicy::Interactive),
+            _ => Err(Error::Validation(format!("Invalid conflict policy: {} (expected local-wins, remote-wins or interactive)", s))),
+        }
+    }
+}
+
+/// The outcome chosen for a conflicting issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    KeepLocal,
+    KeepRemote,
+    /// Leave both sides as they are; the issue stays in conflict until the next sync
+    Skip,
+}
+
+/// The issue fields that are kept in sync with GitHub
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncFields {
+    pub title: String,
+    pub description: Option<String>,
+    pub closed: bool,
+    /// Sorted, so label order never counts as a change
+    pub labels: Vec<String>,
+    pub assignee: Option<String>,
+}
+
+impl SyncFields {
+    pub fn from_local(issue: &Issue) -> Self {
+        Self::new(
+            issue.title.clone(),
+            issue.description.clone(),
+            matches!(issue.status, IssueStatus::Closed | IssueStatus::Resolved),
+            issue.labels.clone(),
+            issue.assignee.clone(),
+        )
+    }
+
+    pub fn from_remote(issue: &GitHubIssue) -> Self {
+        Self::new(
+            issue.title.clone(),
+            issue.body.clone(),
+            issue.state == IssueState::Closed,
+            issue.labels.iter().map(|label| label.name.clone()).collect(),
+            issue.assignees.first().or(issue.assignee.as_ref()).map(|author| author.login.clone()),
+        )
+    }
+
+    pub fn from_snapshot(snapshot: &SyncSnapshot) -> Self {
+        Self::new(
+            snapshot.title.clone(),
+            snapshot.description.clone(),
+            snapshot.closed,
+            snapshot.labels.clone(),
+            snapshot.assignee.clone(),
+        )
+    }
+
+    fn new(title: String, description: Option<String>, closed: bool, mut labels: Vec<String>, assignee: Option<String>) -> Self {
+        labels.sort();
+        labels.dedup();
+        Self {
+            title,
+            // GitHub reports a cleared body as either null or ""
+            description: description.filter(|d| !d.is_empty()),
+            closed,
+            labels,
+            assignee: assignee.filter(|a| !a.is_empty()),
+        }
+    }
+
+    /// Fields that changed on both sides since `self`, to different values
+    pub fn conflicts(&self, local: &SyncFields, remote: &SyncFields) -> Vec<FieldConflict> {
+        let mut conflicts = Vec::new();
+        let mut check = |field: &'static str, base: String, local: String, remote: String| {
+            if local != base && remote != base && local != remote {
+                conflicts.push(FieldConflict { field, local, remote });
+            }
+        };
+
+        check("title", self.title.clone(), local.title.clone(), remote.title.clone());
+        check(
+            "description",
+            self.description.clone().unwrap_or_default(),
+            local.description.clone().unwrap_or_default(),
+            remote.description.clone().unwrap_or_default(),
+        );
+        check("state", state_name(self.closed), state_name(local.closed), state_name(remote.closed));
+        check("labels", self.labels.join(", "), local.labels.join(", "), remote.labels.join(", "));
+        check(
+            "assignee",
+            self.assignee.clone().unwrap_or_default(),
+            local.assignee.clone().unwrap_or_default(),
+            remote.assignee.clone().unwrap_or_default(),
+        );
+
+        conflicts
+    }
+
+    /// Three-way merge against `self` as the common base: a field changed on one
+    /// side takes that side's value; a field changed on both takes the preferred side's
+    pub fn merge(&self, local: &SyncFields, remote: &SyncFields, prefer_local: bool) -> SyncFields {
+        fn pick<T: PartialEq + Clone>(base: &T, local: &T, remote: &T, prefer_local: bool) -> T {
+            if local == base {
+                remote.clone()
+            } else if remote == base || prefer_local {
+                local.clone()
+            } else {
+                remote.clone()
+            }
+        }
+
+        SyncFields {
+            title: pick(&self.title, &local.title, &remote.title, prefer_local),
+            description: pick(&self.description, &local.description, &remote.description, prefer_local),
+            closed: pick(&self.closed, &local.closed, &remote.closed, prefer_local),
+            labels: pick(&self.labels, &local.labels, &remote.labels, prefer_local),
+            assignee: pick(&self.assignee, &local.assignee, &remote.assignee, prefer_local),
+        }
+    }
+
+    /// Copy these fields onto a local issue, keeping its finer-grained status where it agrees
+    fn apply_to(&self, issue: &mut Issue) {
+        let locally_closed = matches!(issue.status, IssueStatus::Closed | IssueStatus::Resolved);
+        if self.closed && !locally_closed {
+            issue.status = IssueStatus::Closed;
+        } else if !self.closed && locally_closed {
+            issue.status = IssueStatus::Open;
+        }
+        issue.title = self.title.clone();
+        issue.description = self.description.clone();
+        issue.labels = self.labels.clone();
+        issue.assignee = self.assignee.clone();
+    }
+
+    fn to_params(&self) -> IssueParams {
+        IssueParams {
+            title: self.title.clone(),
+            body: self.description.clone().unwrap_or_default(),
+            labels: self.labels.clone(),
+            assignees: self.assignee.iter().cloned().collect(),
+        }
+    }
+
+    fn to_snapshot(&self, issue_id: i64, local_updated_at: DateTime<Utc>, remote_updated_at: DateTime<Utc>) -> SyncSnapshot {
+        SyncSnapshot {
+            issue_id,
+            title: self.title.clone(),
+            description: self.description.clone(),
+            closed: self.closed,
+            labels: self.labels.clone(),
+            assignee: self.assignee.clone(),
+            local_updated_at,
+            remote_updated_at,
+        }
+    }
+}
+
+fn state_name(closed: bool) -> String {
+    if closed { "closed" } else { "open" }.to_string()
+}
+
+/// One field that was edited differently on both sides
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldConflict {
+    pub field: &'static str,
+    pub local: String,
+    pub remote: String,
+}
+
+/// An issue with at least one conflicting field
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncConflict {
+    pub issue_id: i64,
+    pub number: u64,
+    pub title: String,
+    pub fields: Vec<FieldConflict>,
+    pub resolution: Resolution,
+}
+
+/// What a sync run did
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    /// Remote issues seen for the first time and created locally
+    pub created: usize,
+    /// Issues whose GitHub copy was updated
+    pub pushed: usize,
+    /// Issues whose GitHub update is waiting in the outbox until GitHub can be reached,
+    /// or that were skipped because earlier changes to them are still waiting there
+    pub queued: usize,
+    /// Changes from the outbox sent before syncing
+    pub outbox_sent: usize,
+    /// Issues whose local copy was updated
+    pub pulled: usize,
+    pub unchanged: usize,
+    /// Local comments posted to GitHub, or edited or deleted there
+    pub comments_pushed: usize,
+    /// GitHub comments added or updated locally
+    pub comments_pulled: usize,
+    pub conflicts: Vec<SyncConflict>,
+    /// GitHub issue numbers that could not be synced, with the error
+    pub failed: Vec<(u64, String)>,
+}
+
+/// Syncs the GitHub-linked issues of a database with one GitHub repository
+pub struct SyncService<'a> {
+    db: &'a TaskDatabase,
+    github: &'a GitHubOps,
+    policy: ConflictPolicy,
+}
+
+impl<'a> SyncService<'a> {
+    pub fn new(db: &'a TaskDatabase, github: &'a GitHubOps, policy: ConflictPolicy) -> Self {
+        Self { db, github, policy }
+    }
+
+    /// Sync every issue in the repository, after replaying the outbox. Issues with
+    /// changes still in the outbox are left for a later sync. `ask` is only called under
+    /// `ConflictPolicy::Interactive`, once per conflicting issue; the conflict's
+    /// `resolution` field is not yet decided when it is called.
+    pub async fn sync<F>(&self, mut ask: F) -> Result<SyncReport>
+    where
+        F: FnMut(&SyncConflict) -> Result<Resolution>,
+    {
+        let mut report = SyncReport {
+            outbox_sent: self.db.flush_outbox(self.github).await?.sent,
+            ..Default::default()
+        };
+        let remote_issues = self.github.list_issues_since(None).await?;
+        let repository = self.github.repository();
+
+        for remote in &remote_issues {
+            if self.db.has_queued_github_mutations(&repository, remote.number).await? {
+                report.queued += 1;
+                continue;
+            }
+            // Pulled changes are GitHub's; undoing them here would push them back
+            if let Err(e) = self.db.without_undo(self.sync_issue(remote, &mut ask, &mut report)).await {
+                report.failed.push((remote.number, e.to_string()));
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn sync_issue<F>(&self, remote: &GitHubIssue, ask: &mut F, report: &mut SyncReport) -> Result<()>
+    where
+        F: FnMut(&SyncConflict) -> Result<Resolution>,
+    {
+        let remote_fields = SyncFields::from_remote(remote);
+
+        let local = match self.db.get_issue_by_external_ref("github", &remote.number.to_string()).await? {
+            Some(local) => local,
+            // Deleted locally: leave it in the trash until it is restored or purged
+            None if self.db.is_external_issue_trashed("github", &remote.number.to_string()).await? => {
+                report.unchanged += 1;
+                return Ok(());
+            }
+            None => {
+                let issue = self.db.issue_from_github(remote).await?;
+                let id = self.db.insert_issue_by(&issue, "github").await?;
+                self.db.save_sync_snapshot(&remote_fields.to_snapshot(id, issue.updated_at, remote.updated_at)).await?;
+                report.created += 1;
+                if remote.comments > 0 {
+                    report.comments_pulled += self.db.import_github_comments(self.github, id, remote.number).await?;
+                }
+                return Ok(());
+            }
+        };
+        let id = local.id.storage_context("Stored issue has no id")?;
+        self.sync_comments(id, remote, report).await?;
+        let local_fields = SyncFields::from_local(&local);
+
+        let base = match self.db.get_sync_snapshot(id).await? {
+            Some(snapshot) => {
+                if local.updated_at <= snapshot.local_updated_at && remote.updated_at <= snapshot.remote_updated_at {
+                    report.unchanged += 1;
+                    return Ok(());
+                }
+                SyncFields::from_snapshot(&snapshot)
+            }
+            // Never synced (e.g. only imported): treat the side edited more recently as the changed one
+            None if local.updated_at > remote.updated_at => remote_fields.clone(),
+            None => local_fields.clone(),
+        };
+
+        let conflicting = base.conflicts(&local_fields, &remote_fields);
+        let prefer_local = if conflicting.is_empty() {
+            true
+        } else {
+            let mut conflict = SyncConflict {
+                issue_id: id,
+                number: remote.number,
+                title: local.title.clone(),
+                fields: conflicting,
+                resolution: Resolution::Skip,
+            };
+            conflict.resolution = match self.policy {
+                ConflictPolicy::LocalWins => Resolution::KeepLocal,
+                ConflictPolicy::RemoteWins => Resolution::KeepRemote,
+                ConflictPolicy::Interactive => ask(&conflict)?,
+            };
+            let resolution = conflict.resolution;
+            report.conflicts.push(conflict);
+
+            match resolution {
+                Resolution::KeepLocal => true,
+                Resolution::KeepRemote => false,
+                Resolution::Skip => return Ok(()),
+            }
+        };
+        let merged = base.merge(&local_fields, &remote_fields, prefer_local);
+
+        // GitHub's own `updated_at` moves when the push lands; the next sync then finds
+        // nothing new on GitHub's side, as the fields already match the snapshot
+        let remote_updated_at = remote.updated_at;
+        if merged != remote_fields {
+            let mutation = GitHubMutation::UpdateIssue {
+                issue_number: remote.number,
+                params: merged.to_params(),
+                closed: merged.closed,
+            };
+            match self.db.submit_github_mutation(self.github, &mutation).await? {
+                Delivery::Sent => report.pushed += 1,
+                Delivery::Queued(_) => report.queued += 1,
+            }
+        }
+
+        let mut local_updated_at = local.updated_at;
+        if merged != local_fields {
+            // Labels added on GitHub may not exist locally yet
+            self.db.ensure_github_labels(&remote.labels).await?;
+            let mut issue = local.clone();
+            merged.apply_to(&mut issue);
+            self.db.update_issue_by(&issue, "github").await?;
+            local_updated_at = self.db.get_issue_by_id(id).await?
+                .storage_context("Issue disappeared during sync")?
+                .updated_at;
+            report.pulled += 1;
+        }
+
+        if merged == remote_fields && merged == local_fields {
+            report.unchanged += 1;
+        }
+
+        self.db.save_sync_snapshot(&merged.to_snapshot(id, local_updated_at, remote_updated_at)).await?;
+        Ok(())
+    }
+
+    /// Send local comment changes first, so an edit made on both sides keeps the local text
+    async fn sync_comments(&self, id: i64, remote: &GitHubIssue, report: &mut SyncReport) -> Result<()> {
+        report.comments_pushed += self.db.export_github_comments(self.github, id, remote.number).await?;
+        // Every local comment has a GitHub copy now; fetch the list only if either side has any
+        if remote.comments > 0 || self.db.count_comments(id).await? > 0 {
+            report.comments_pulled += self.db.import_github_comments(self.github, id, remote.number).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::IssuePriority;
+    use crate::git_ops::github_mock::{issue_json, MockGitHub};
+
+    fn fields(title: &str, closed: bool, labels: &[&str]) -> SyncFields {
+        SyncFields::new(
+            title.to_string(),
+            None,
+            closed,
+            labels.iter().map(|l| l.to_string()).collect(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_conflict_policy_round_trip() {
+        for policy in [ConflictPolicy::LocalWins, ConflictPolicy::RemoteWins, ConflictPolicy::Interactive] {
+            assert_eq!(policy.to_string().parse::<ConflictPolicy>().unwrap(), policy);
+        }
+        assert_eq!("mine".parse::<ConflictPolicy>().unwrap_err().kind(), "validation");
+    }
+
+    #[test]
+    fn test_merge_takes_each_sides_own_changes() {
+        let base = fields("Title", false, &["bug"]);
+        let local = fields("Title", true, &["bug"]);
+        let remote = fields("Better title", false, &["bug", "help wanted"]);
+
+        assert!(base.conflicts(&local, &remote).is_empty());
+        assert_eq!(base.merge(&local, &remote, true), fields("Better title", true, &["bug", "help wanted"]));
+    }
+
+    #[test]
+    fn test_merge_conflicts_follow_preference() {
+        let base = fields("Title", false, &[]);
+        let local = fields("Local title", false, &[]);
+        let remote = fields("Remote title", true, &[]);
+
+        let conflicts = base.conflicts(&local, &remote);
+        assert_eq!(conflicts, vec![FieldConflict {
+            field: "title",
+            local: "Local title".to_string(),
+            remote: "Remote title".to_string(),
+        }]);
+        assert_eq!(base.merge(&local, &remote, true), fields("Local title", true, &[]));
+        assert_eq!(base.merge(&local, &remote, false), fields("Remote title", true, &[]));
+
+        // The same edit on both sides is not a conflict
+        assert!(base.conflicts(&local, &local).is_empty());
+    }
+
+    async fn synced_fixture() -> (TaskDatabase, MockGitHub, i64) {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        let server = MockGitHub::start().await;
+        server.add_issue(issue_json(1, "Crash on start", "open", "2024-01-01T00:00:00Z", &["bug"]));
+
+        let github = server.client();
+        let report = SyncService::new(&db, &github, ConflictPolicy::LocalWins)
+            .sync(|_| unreachable!())
+            .await
+            .unwrap();
+        assert_eq!(report.created, 1);
+
+        let id = db.get_issue_by_external_ref("github", "1").await.unwrap().unwrap().id.unwrap();
+        (db, server, id)
+    }
+
+    #[tokio::test]
+    async fn test_sync_pushes_local_changes() {
+        let (db, server, id) = synced_fixture().await;
+        let github = server.client();
+
+        let mut issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        issue.status = IssueStatus::Closed;
+        issue.labels = vec!["bug".to_string(), "wontfix".to_string()];
+        issue.assignee = Some("bob".to_string());
+        issue.priority = IssuePriority::High;
+        db.update_issue(&issue).await.unwrap();
+
+        let report = SyncService::new(&db, &github, ConflictPolicy::Interactive)
+            .sync(|_| unreachable!("No conflict expected"))
+            .await
+            .unwrap();
+        assert_eq!((report.pushed, report.pulled), (1, 0));
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+
+        let remote = server.issue(1);
+        assert_eq!(remote["state"], "closed");
+        assert_eq!(remote["assignee"]["login"], "bob");
+        assert_eq!(remote["labels"].as_array().unwrap().len(), 2);
+
+        // Nothing left to do on the next run
+        let report = SyncService::new(&db, &github, ConflictPolicy::Interactive)
+            .sync(|_| unreachable!())
+            .await
+            .unwrap();
+        assert_eq!((report.pushed, report.pulled, report.unchanged), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_sync_queues_pushes_github_cannot_take() {
+        let (db, server, id) = synced_fixture().await;
+        let github = server.client();
+        let service = SyncService::new(&db, &github, ConflictPolicy::LocalWins);
+        let sync = || service.sync(|_| unreachable!());
+
+        let mut issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        issue.title = "Crash on start with empty config".to_string();
+        db.update_issue(&issue).await.unwrap();
+
+        server.fail_writes(1, Some(503));
+        let report = sync().await.unwrap();
+        assert_eq!((report.pushed, report.queued), (0, 1));
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert_eq!(db.get_outbox(&github.repository()).await.unwrap().len(), 1);
+        assert_eq!(server.issue(1)["title"], "Crash on start");
+
+        // Still down: the issue is left alone until its queued change is sent
+        let report = sync().await.unwrap();
+        assert_eq!((report.outbox_sent, report.queued, report.unchanged), (0, 1, 0));
+
+        server.fail_writes(1, None);
+        let report = sync().await.unwrap();
+        assert_eq!((report.outbox_sent, report.pushed, report.queued, report.unchanged), (1, 0, 0, 1));
+        assert_eq!(server.issue(1)["title"], "Crash on start with empty config");
+        assert!(db.get_outbox(&github.repository()).await.unwrap().is_empty());
+
+        // A change GitHub refuses is reported, not queued
+        issue.title = "Crash".to_string();
+        db.update_issue(&issue).await.unwrap();
+        server.fail_writes(1, Some(422));
+        let report = sync().await.unwrap();
+        assert_eq!(report.queued, 0);
+        assert_eq!(report.failed.len(), 1);
+        assert!(db.get_outbox(&github.repository()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_pulls_remote_changes() {
+        let (db, server, id) = synced_fixture().await;
+        db.update_issue_status(id, IssueStatus::InProgress).await.unwrap();
+        server.edit_issue(1, "2030-01-01T00:00:00Z", |issue| {
+            issue["title"] = "Crash on start with empty config".into();
+        });
+
+        let report = SyncService::new(&db, &server.client(), ConflictPolicy::RemoteWins)
+            .sync(|_| unreachable!())
+            .await
+            .unwrap();
+        assert_eq!((report.pushed, report.pulled), (0, 1));
+        assert!(report.conflicts.is_empty());
+
+        let issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        assert_eq!(issue.title, "Crash on start with empty config");
+        assert_eq!(issue.status.to_string(), "in_progress", "Open on both sides keeps the local stage");
+
+        // Undo skips what the sync pulled and takes back the local change
+        let pulled = db.get_issue_events(id).await.unwrap().pop().unwrap();
+        assert_eq!((pulled.actor.as_str(), pulled.operation_id), ("github", None));
+        db.undo_last(1).await.unwrap();
+        let issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        assert_eq!(issue.title, "Crash on start with empty config");
+        assert_eq!(issue.status.to_string(), "open");
+    }
+
+    #[tokio::test]
+    async fn test_sync_conflicts_follow_policy() {
+        for (policy, expected_title) in [
+            (ConflictPolicy::LocalWins, "Local title"),
+            (ConflictPolicy::RemoteWins, "Remote title"),
+        ] {
+            let (db, server, id) = synced_fixture().await;
+            let mut issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+            issue.title = "Local title".to_string();
+            db.update_issue(&issue).await.unwrap();
+            server.edit_issue(1, "2030-01-01T00:00:00Z", |issue| {
+                issue["title"] = "Remote title".into();
+            });
+
+            let report = SyncService::new(&db, &server.client(), policy)
+                .sync(|_| unreachable!())
+                .await
+                .unwrap();
+            assert_eq!(report.conflicts.len(), 1);
+            assert_eq!(report.conflicts[0].fields[0].field, "title");
+
+            assert_eq!(db.get_issue_by_id(id).await.unwrap().unwrap().title, expected_title);
+            assert_eq!(server.issue(1)["title"], expected_title);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_interactive_skip_leaves_both_sides() {
+        let (db, server, id) = synced_fixture().await;
+        let mut issue = db.get_issue_by_id(id).await.unwrap().unwrap();
+        issue.title = "Local title".to_string();
+        db.update_issue(&issue).await.unwrap();
+        server.edit_issue(1, "2030-01-01T00:00:00Z", |issue| {
+            issue["title"] = "Remote title".into();
+        });
+
+        let mut asked = 0;
+        let report = SyncService::new(&db, &server.client(), ConflictPolicy::Interactive)
+            .sync(|conflict| {
+                asked += 1;
+                assert_eq!(conflict.number, 1);
+                Ok(Resolution::Skip)
+            })
+            .await
+            .unwrap();
+        assert_eq!(asked, 1);
+        assert_eq!(report.conflicts[0].resolution, Resolution::Skip);
+        assert_eq!(db.get_issue_by_id(id).await.unwrap().unwrap().title, "Local title");
+        assert_eq!(server.issue(1)["title"], "Remote title");
+    }
+
+    #[tokio::test]
+    async fn test_sync_round_trips_comments() {
+        let (db, server, id) = synced_fixture().await;
+        server.add_comment(1, "octocat", "Still crashing on 1.1");
+        let now = chrono::Utc::now();
+        db.insert_comment(&crate::db::Comment {
+            id: None,
+            issue_id: id,
+            author: "alice".to_string(),
+            body: "Looking into it".to_string(),
+            created_at: now,
+            updated_at: now,
+            external_ref: None,
+            synced_at: None,
+        }).await.unwrap();
+
+        let github = server.client();
+        let report = SyncService::new(&db, &github, ConflictPolicy::LocalWins)
+            .sync(|_| unreachable!())
+            .await
+            .unwrap();
+        assert_eq!((report.comments_pushed, report.comments_pulled), (1, 1));
+        assert_eq!(server.comments(1).len(), 2);
+        assert_eq!(db.count_comments(id).await.unwrap(), 2);
+
+        let report = SyncService::new(&db, &github, ConflictPolicy::LocalWins)
+            .sync(|_| unreachable!())
+            .await
+            .unwrap();
+        assert_eq!((report.comments_pushed, report.comments_pulled), (0, 0));
+    }
+}
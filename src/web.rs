@@ -1,22 +1,150 @@
 use axum::{
-    extract::{Path, State},
+    extract::{rejection::{JsonRejection, PathRejection, QueryRejection}, FromRequest, FromRequestParts, State},
     http::StatusCode,
-    response::{Html, IntoResponse, Response},
+    response::{sse::{Event, KeepAlive, Sse}, Html, IntoResponse, Response},
+    routing::get,
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
 use std::sync::Arc;
 use askama::Template;
 use chrono::Utc;
 use pulldown_cmark::{Parser, Options, html};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
 
-use crate::db::{TaskDatabase, IssueStatus, IssuePriority};
-use crate::kanban::{KanbanBoard, KanbanColumn, KanbanCard, Priority};
+use crate::db::{rank, CardRank, TaskDatabase, Issue, IssuePriority, IssueReferenceKind, Label};
+use crate::db::dependencies::DependencyKind;
+use crate::db::hierarchy::task_list_progress;
+use crate::error::{Context, Error, Result};
+use crate::query::Query;
+use crate::kanban::{BoardDefinition, KanbanBoard, KanbanCard, KanbanColumn, CardCommit, Priority, Swimlane};
+
+pub mod api;
 
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
     pub db: Arc<TaskDatabase>,
+    /// Issue changes, fanned out to every board connected to `/api/events`
+    pub events: broadcast::Sender<BoardEvent>,
+}
+
+/// Events a slow subscriber may fall behind by before it is told to resync
+const EVENT_BUFFER: usize = 64;
+
+impl AppState {
+    pub fn new(db: Arc<TaskDatabase>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self { db, events }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BoardEvent> {
+        self.events.subscribe()
+    }
+
+    /// Tell connected boards that an issue changed. The event carries the issue's
+    /// card as it looks now; failing to build it is logged, not returned, because
+    /// the change itself has already been saved. The cards of issues it blocks are
+    /// sent again too, since their blocked badge depends on it.
+    pub async fn publish(&self, kind: BoardEventKind, issue_id: i64) {
+        self.publish_all(kind, &[issue_id]).await;
+    }
+
+    /// `publish` for several issues that changed together, working out where the
+    /// cards sit on the board only once
+    pub async fn publish_all(&self, kind: BoardEventKind, issue_ids: &[i64]) {
+        if self.events.receiver_count() == 0 || issue_ids.is_empty() {
+            return;
+        }
+
+        let mut cards: Vec<(BoardEventKind, i64)> = issue_ids.iter().map(|id| (kind, *id)).collect();
+        for &issue_id in issue_ids {
+            match self.db.get_issue_links(issue_id).await {
+                Ok(links) => cards.extend(links.iter()
+                    .filter(|link| link.kind == DependencyKind::Blocks)
+                    .map(|link| (BoardEventKind::Updated, link.issue_id))),
+                Err(err) => log::warn!("Could not publish board events for issues blocked by #{}: {}", issue_id, err),
+            }
+            // Parents show their sub-tasks' progress
+            match self.db.get_ancestor_ids(issue_id).await {
+                Ok(ancestors) => cards.extend(ancestors.into_iter().map(|id| (BoardEventKind::Updated, id))),
+                Err(err) => log::warn!("Could not publish board events for the parents of #{}: {}", issue_id, err),
+            }
+        }
+
+        let places = match card_places(&self.db).await {
+            Ok(places) => places,
+            Err(err) => {
+                log::warn!("Could not publish board events: {}", err);
+                return;
+            }
+        };
+        let mut sent = HashSet::new();
+        for (kind, issue_id) in cards {
+            if sent.insert(issue_id) {
+                self.publish_card(kind, issue_id, &places).await;
+            }
+        }
+    }
+
+    async fn publish_card(&self, kind: BoardEventKind, issue_id: i64, places: &HashMap<i64, CardPlace>) {
+        let mut event = BoardEvent { kind, issue_number: issue_id as u64, card: None, column_id: None, position: None };
+        if kind != BoardEventKind::Deleted {
+            match placed_card(&self.db, issue_id, places).await {
+                Ok(Some((card, place))) => {
+                    event.card = Some(card);
+                    if let Some((column_id, position)) = place {
+                        event.column_id = Some(column_id);
+                        event.position = Some(position);
+                    }
+                }
+                Ok(None) => event.kind = BoardEventKind::Deleted,
+                Err(err) => {
+                    log::warn!("Could not publish board event for issue #{}: {}", issue_id, err);
+                    return;
+                }
+            }
+        }
+
+        // Sending only fails when nobody is listening, which is fine
+        let _ = self.events.send(event);
+    }
+}
+
+/// What happened to an issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardEventKind {
+    Created,
+    Updated,
+    Moved,
+    Deleted,
+}
+
+/// An issue change pushed to connected boards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardEvent {
+    pub kind: BoardEventKind,
+    pub issue_number: u64,
+    /// The issue's card as it looks now; `None` once deleted
+    pub card: Option<KanbanCard>,
+    /// Column now holding the card; `None` if the issue is deleted or shown in no column
+    pub column_id: Option<String>,
+    /// The card's index in that column, 0 being the top
+    pub position: Option<usize>,
+}
+
+/// Query parameters of the board page and `/api/board`
+#[derive(Debug, Default, Deserialize)]
+pub struct BoardQuery {
+    /// Split columns into lanes: `assignee`, `priority` or `label:<prefix>`
+    pub swimlane: Option<Swimlane>,
+    /// Only show issues matching this search query
+    pub q: Option<Query>,
 }
 
 /// Request body for moving issues between columns
@@ -25,6 +153,25 @@ pub struct MoveIssueRequest {
     pub issue_number: u64,
     pub from_column: String,
     pub to_column: String,
+    /// Put the card right above this issue's card in the target column
+    #[serde(default)]
+    pub before: Option<u64>,
+    /// Put the card right below this issue's card in the target column
+    #[serde(default)]
+    pub after: Option<u64>,
+    /// Where to put the card in the target column (0 is the top), when neither `before` nor `after` is given
+    #[serde(default)]
+    pub position: Option<usize>,
+    /// Move even if the target column is at its WIP limit
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Data of a response to a change the board offers to undo
+#[derive(Debug, Serialize)]
+pub struct UndoToken {
+    /// For `POST /api/operations/:id/undo`; `None` if nothing changed
+    pub operation_id: Option<i64>,
 }
 
 /// Response for API endpoints
@@ -35,11 +182,88 @@ pub struct ApiResponse<T> {
     pub message: Option<String>,
 }
 
+/// Body of every failed API call. `error` is the kind of failure
+/// (`not_found`, `conflict`, `validation`, `upstream` or `storage`) for clients to branch on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    pub success: bool,
+    pub data: Option<()>,
+    pub message: String,
+    pub error: String,
+}
+
+impl Error {
+    /// HTTP status the web API answers with for this error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Upstream(_) => StatusCode::BAD_GATEWAY,
+            Error::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let body = ApiErrorBody {
+            success: false,
+            data: None,
+            message: self.message().to_string(),
+            error: self.kind().to_string(),
+        };
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+impl From<JsonRejection> for Error {
+    fn from(rejection: JsonRejection) -> Self {
+        Error::Validation(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for Error {
+    fn from(rejection: QueryRejection) -> Self {
+        Error::Validation(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for Error {
+    fn from(rejection: PathRejection) -> Self {
+        Error::Validation(rejection.body_text())
+    }
+}
+
+/// `Json` extractor that rejects malformed bodies with an `ApiErrorBody`
+#[derive(Debug, FromRequest)]
+#[from_request(via(axum::Json), rejection(Error))]
+pub struct ApiJson<T>(pub T);
+
+/// `Query` extractor that rejects malformed query strings with an `ApiErrorBody`
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(Error))]
+pub struct ApiQuery<T>(pub T);
+
+/// `Path` extractor that rejects malformed path parameters with an `ApiErrorBody`
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(Error))]
+pub struct ApiPath<T>(pub T);
+
 /// Kanban board HTML template
 #[derive(Template)]
 #[template(path = "kanban.html")]
 pub struct KanbanTemplate {
     pub board: KanbanBoard,
+    /// The search query the board is narrowed to, or empty
+    pub search: String,
+}
+
+impl KanbanTemplate {
+    /// The board's swimlane as written in `?swimlane=`, or empty
+    fn swimlane(&self) -> String {
+        self.board.swimlane.as_ref().map(|swimlane| swimlane.to_string()).unwrap_or_default()
+    }
 }
 
 /// Web server struct
@@ -50,7 +274,7 @@ pub struct KanbanWebServer {
 impl KanbanWebServer {
     /// Create a new web server with database
     pub fn new(db: TaskDatabase) -> Self {
-        let app_state = AppState { db: Arc::new(db) };
+        let app_state = AppState::new(Arc::new(db));
         
         Self { app_state }
     }
@@ -62,6 +286,24 @@ impl KanbanWebServer {
             .route("/api/board", axum::routing::get(handlers::api_board))
             .route("/api/move", axum::routing::post(handlers::api_move_issue))
             .route("/api/refresh/:column_id", axum::routing::post(handlers::api_refresh_column))
+            .route("/api/events", get(handlers::api_events))
+            .route("/api/issues", get(api::list_issues).post(api::create_issue))
+            .route("/api/issues/:id", get(api::get_issue).patch(api::update_issue).delete(api::delete_issue))
+            .route("/api/issues/:id/comments", get(api::list_comments).post(api::create_comment))
+            .route("/api/issues/:id/events", get(api::list_issue_events))
+            .route("/api/issues/:id/links", get(api::list_links).post(api::create_link))
+            .route("/api/issues/:id/links/:kind/:other", axum::routing::delete(api::delete_link))
+            .route("/api/issues/:id/children", get(api::list_children))
+            .route("/api/ready", get(api::list_ready_issues))
+            .route("/api/comments/:id", axum::routing::patch(api::update_comment).delete(api::delete_comment))
+            .route("/api/labels", get(api::list_labels).post(api::create_label))
+            .route("/api/labels/:name", get(api::get_label).patch(api::update_label).delete(api::delete_label))
+            .route("/api/commits", get(api::list_commits))
+            .route("/api/search", get(api::search))
+            .route("/api/operations/:id/undo", axum::routing::post(api::undo_operation))
+            .route("/api/trash", get(api::list_trash).delete(api::purge_trash))
+            .route("/api/trash/:id", axum::routing::delete(api::purge_trashed_issue))
+            .route("/api/trash/:id/restore", axum::routing::post(api::restore_issue))
             .with_state(self.app_state.clone())
     }
 
@@ -79,7 +321,8 @@ impl KanbanWebServer {
     }
 }
 
-/// Helper function to convert markdown to HTML
+/// Helper function to convert markdown to HTML.
+/// The output is sanitized, so templates may insert it unescaped.
 pub fn markdown_to_html(markdown: &str) -> String {
     // First, unescape the newline characters if they're stored as literal \n
     let processed_markdown = markdown
@@ -97,7 +340,140 @@ pub fn markdown_to_html(markdown: &str) -> String {
     let parser = Parser::new_ext(&processed_markdown, options);
     let mut html_output = String::new();
     html::push_html(&mut html_output, parser);
-    html_output
+    sanitize_html(&html_output)
+}
+
+/// Link schemes allowed to survive sanitizing; relative links are kept as-is
+const SAFE_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Strip rendered HTML down to an allowlist of tags, attributes and link schemes
+fn sanitize_html(html: &str) -> String {
+    ammonia::Builder::default()
+        .url_schemes(HashSet::from(SAFE_URL_SCHEMES))
+        .link_rel(Some("noopener nofollow"))
+        // Task list items render as disabled checkboxes
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            ("input", "type") if value != "checkbox" => None,
+            _ => Some(value.into()),
+        })
+        .clean(html)
+        .to_string()
+}
+
+/// Build the board card for a stored issue, including the commits that reference it
+async fn card_from_issue(db: &TaskDatabase, issue: Issue) -> Result<KanbanCard> {
+    let priority = match issue.priority {
+        IssuePriority::Low => Priority::Low,
+        IssuePriority::Medium => Priority::Medium,
+        IssuePriority::High => Priority::High,
+        IssuePriority::Critical => Priority::Critical,
+    };
+    
+    // Render markdown to HTML
+    let body_html = issue.description.as_ref()
+        .map(|body| markdown_to_html(body))
+        .unwrap_or_else(|| "No description".to_string());
+    
+    // Commits that reference this issue ("Fixes #N", "#N")
+    let commits = match issue.id {
+        Some(id) => db.get_commits_for_issue(id).await?
+            .into_iter()
+            .map(|linked| CardCommit {
+                short_hash: linked.commit.hash.chars().take(8).collect(),
+                summary: linked.commit.message.lines().next().unwrap_or("").to_string(),
+                hash: linked.commit.hash,
+                author_name: linked.commit.author_name,
+                insertions: linked.commit.insertions,
+                deletions: linked.commit.deletions,
+                closes: linked.kind == IssueReferenceKind::Closes,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let comments_count = match issue.id {
+        Some(id) => db.count_comments(id).await?,
+        None => 0,
+    };
+    let blocked_by = match issue.id {
+        Some(id) => db.get_open_blockers(id).await?.into_iter().map(|id| id as u64).collect(),
+        None => Vec::new(),
+    };
+    let subtasks = match issue.id {
+        Some(id) => db.get_subtask_progress(id).await?,
+        None => None,
+    };
+    let tasks = issue.description.as_deref().and_then(task_list_progress);
+        
+    Ok(KanbanCard {
+        issue_number: issue.id.unwrap_or(0) as u64, // Use database ID as issue number
+        title: issue.title,
+        body: issue.description,
+        body_html,
+        assignee: issue.assignee,
+        labels: issue.labels.clone(),
+        priority,
+        created_at: issue.created_at,
+        updated_at: issue.updated_at,
+        comments_count,
+        commits,
+        blocked_by,
+        parent_id: issue.parent_id.map(|id| id as u64),
+        subtasks,
+        tasks,
+    })
+}
+
+/// Where a card sits on the board: its column id and index from the top
+type CardPlace = (String, usize);
+
+/// Where each of `issues` sits on the board, in the order the board shows them:
+/// cards placed by hand first, by rank, then the rest in the order given
+fn place_issues(definition: &BoardDefinition, issues: &[Issue], ranks: &[CardRank]) -> HashMap<i64, CardPlace> {
+    let ranks: HashMap<i64, &CardRank> = ranks.iter().map(|rank| (rank.issue_id, rank)).collect();
+    let mut columns: HashMap<&str, Vec<i64>> = HashMap::new();
+    for issue in issues {
+        if let (Some(id), Some(column)) = (issue.id, definition.column_for(&issue.labels, &issue.status)) {
+            columns.entry(column.id.as_str()).or_default().push(id);
+        }
+    }
+
+    let mut places = HashMap::new();
+    for (column_id, mut ids) in columns {
+        ids.sort_by_key(|id| {
+            let rank = ranks.get(id)
+                .filter(|rank| rank.column_id == column_id)
+                .map(|rank| rank.rank.as_str());
+            (rank.is_none(), rank)
+        });
+        for (index, id) in ids.into_iter().enumerate() {
+            places.insert(id, (column_id.to_string(), index));
+        }
+    }
+    places
+}
+
+/// Where every card sits on the full board, without building the cards
+async fn card_places(db: &TaskDatabase) -> Result<HashMap<i64, CardPlace>> {
+    let definition = BoardDefinition::load(db).await?;
+    let issues = db.get_all_issues().await?;
+    Ok(place_issues(&definition, &issues, &db.get_card_ranks().await?))
+}
+
+/// An issue's card and its place among `places` (`None` if it is in no column),
+/// or `None` if the issue doesn't exist
+async fn placed_card(
+    db: &TaskDatabase,
+    issue_id: i64,
+    places: &HashMap<i64, CardPlace>,
+) -> Result<Option<(KanbanCard, Option<CardPlace>)>> {
+    let issue = match db.get_issue_by_id(issue_id).await? {
+        Some(issue) => issue,
+        None => return Ok(None),
+    };
+
+    Ok(Some((card_from_issue(db, issue).await?, places.get(&issue_id).cloned())))
 }
 
 /// Route handlers
@@ -105,219 +481,259 @@ pub mod handlers {
     use super::*;
 
     /// Helper function to create a kanban board from database issues
-    async fn create_board_from_db(db: &TaskDatabase) -> Result<KanbanBoard, anyhow::Error> {
-        let all_issues = db.get_all_issues().await?;
-        
-        // Only show open issues
-        let open_issues: Vec<_> = all_issues.into_iter()
-            .filter(|issue| matches!(issue.status, IssueStatus::Open))
-            .collect();
-        
-        // Create columns
-        let mut evaluating_cards = Vec::new();
-        let mut preparing_cards = Vec::new();
-        let mut progressing_cards = Vec::new();
-        let mut done_cards = Vec::new();
+    pub(super) async fn create_board_from_db(db: &TaskDatabase) -> Result<KanbanBoard> {
+        let issues = db.get_all_issues().await?;
+        create_board_from_issues(db, issues).await
+    }
+
+    /// A board showing only `issues`
+    async fn create_board_from_issues(db: &TaskDatabase, issues: Vec<Issue>) -> Result<KanbanBoard> {
+        let definition = BoardDefinition::load(db).await?;
+        let places = place_issues(&definition, &issues, &db.get_card_ranks().await?);
+        let mut board = definition.empty_board("Task Board".to_string());
         
-        // Convert database issues to kanban cards and organize by labels
-        for issue in open_issues {
-            let priority = match issue.priority {
-                IssuePriority::Low => Priority::Low,
-                IssuePriority::Medium => Priority::Medium,
-                IssuePriority::High => Priority::High,
-                IssuePriority::Critical => Priority::Critical,
-            };
-            
-            // Render markdown to HTML
-            let body_html = issue.description.as_ref()
-                .map(|body| markdown_to_html(body))
-                .unwrap_or_else(|| "No description".to_string());
-                
-            let card = KanbanCard {
-                issue_number: issue.id.unwrap_or(0) as u64, // Use database ID as issue number
-                title: issue.title,
-                body: issue.description,
-                body_html,
-                assignee: issue.assignee,
-                labels: issue.labels.clone(),
-                priority,
-                created_at: issue.created_at,
-                updated_at: issue.updated_at,
-                comments_count: 0, // Default to 0 for now
+        // Convert database issues to kanban cards and place them by the board definition
+        for issue in issues {
+            let column_id = match issue.id.and_then(|id| places.get(&id)) {
+                Some((column_id, _)) => column_id.clone(),
+                None => continue,
             };
+            let card = card_from_issue(db, issue).await?;
             
-            // Organize by labels - check for workflow labels
-            let has_done = issue.labels.iter().any(|label| label.eq_ignore_ascii_case("done"));
-            let has_progressing = issue.labels.iter().any(|label| label.eq_ignore_ascii_case("progressing"));
-            let has_preparing = issue.labels.iter().any(|label| label.eq_ignore_ascii_case("preparing"));
-            let has_evaluating = issue.labels.iter().any(|label| label.eq_ignore_ascii_case("evaluating"));
-            
-            if has_done {
-                done_cards.push(card);
-            } else if has_progressing {
-                progressing_cards.push(card);
-            } else if has_preparing {
-                preparing_cards.push(card);
-            } else if has_evaluating {
-                evaluating_cards.push(card);
-            } else {
-                // Default to evaluating if no workflow label is found
-                evaluating_cards.push(card);
+            if let Some(column) = board.columns.iter_mut().find(|column| column.id == column_id) {
+                column.add_card(card);
             }
         }
+
+        // Cards placed by hand come first, by rank; the rest stay newest first
+        for column in &mut board.columns {
+            column.cards.sort_by_key(|card| places.get(&(card.issue_number as i64)).map(|(_, index)| *index));
+        }
         
-        Ok(KanbanBoard {
-            title: "Task Board".to_string(),
-            last_updated: Utc::now(),
-            columns: vec![
-                KanbanColumn {
-                    id: "evaluating".to_string(),
-                    title: "Evaluating".to_string(),
-                    label_name: "Evaluating".to_string(),
-                    color: "#fef2c0".to_string(),
-                    cards: evaluating_cards,
-                },
-                KanbanColumn {
-                    id: "preparing".to_string(),
-                    title: "Preparing".to_string(),
-                    label_name: "Preparing".to_string(),
-                    color: "#fef3c7".to_string(),
-                    cards: preparing_cards,
-                },
-                KanbanColumn {
-                    id: "progressing".to_string(),
-                    title: "Progressing".to_string(),
-                    label_name: "Progressing".to_string(),
-                    color: "#bfdbfe".to_string(),
-                    cards: progressing_cards,
-                },
-                KanbanColumn {
-                    id: "done".to_string(),
-                    title: "Done".to_string(),
-                    label_name: "Done".to_string(),
-                    color: "#bbf7d0".to_string(),
-                    cards: done_cards,
-                },
-            ],
-        })
+        Ok(board)
     }
 
-    /// Serve the main Kanban board page
-    pub async fn kanban_board(State(state): State<AppState>) -> Result<Response, StatusCode> {
-        match create_board_from_db(&state.db).await {
-            Ok(board) => {
-                let template = KanbanTemplate { board };
-                match template.render() {
-                    Ok(html) => Ok(Html(html).into_response()),
-                    Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    /// A rank that puts a card at `index` among a column's `cards`, top first.
+    /// Unranked cards sort last, so those above the drop point get ranks first
+    /// to keep their place; ranked cards are never rewritten.
+    async fn rank_at(db: &TaskDatabase, column_id: &str, cards: &[i64], index: usize) -> Result<String> {
+        let ranks: HashMap<i64, String> = db.get_card_ranks().await?
+            .into_iter()
+            .filter(|rank| rank.column_id == column_id)
+            .map(|rank| (rank.issue_id, rank.rank))
+            .collect();
+
+        let mut above: Option<String> = None;
+        for id in &cards[..index] {
+            let rank = match ranks.get(id) {
+                Some(rank) => rank.clone(),
+                None => {
+                    let rank = rank::between(above.as_deref(), None);
+                    db.set_card_rank(*id, column_id, &rank).await?;
+                    rank
                 }
-            }
-            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+            };
+            above = Some(rank);
         }
+        let below = cards.get(index).and_then(|id| ranks.get(id));
+        Ok(rank::between(above.as_deref(), below.map(String::as_str)))
     }
 
-    /// API endpoint to get board data as JSON
-    pub async fn api_board(State(state): State<AppState>) -> Json<ApiResponse<KanbanBoard>> {
-        match create_board_from_db(&state.db).await {
-            Ok(board) => Json(ApiResponse {
-                success: true,
-                data: Some(board),
-                message: None,
-            }),
-            Err(err) => Json(ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Failed to fetch board: {}", err)),
-            }),
+    /// The board, narrowed to matching issues and split into swimlanes if the query asks
+    async fn board_for_query(db: &TaskDatabase, query: BoardQuery) -> Result<KanbanBoard> {
+        let mut board = match query.q.filter(|q| !q.is_empty()) {
+            Some(q) => create_board_from_issues(db, db.query_issues(&q).await?).await?,
+            None => create_board_from_db(db).await?,
+        };
+        if let Some(swimlane) = query.swimlane {
+            board.split_into_lanes(swimlane);
         }
+        Ok(board)
+    }
+
+    /// Serve the main Kanban board page
+    pub async fn kanban_board(
+        State(state): State<AppState>,
+        ApiQuery(query): ApiQuery<BoardQuery>,
+    ) -> Result<Response> {
+        let search = query.q.as_ref().map(|q| q.to_string()).unwrap_or_default();
+        let board = board_for_query(&state.db, query).await
+            .map_err(|err| err.context("Failed to fetch board"))?;
+        let html = KanbanTemplate { board, search }.render()
+            .storage_context("Failed to render board")?;
+        Ok(Html(html).into_response())
+    }
+
+    /// API endpoint to get board data as JSON
+    pub async fn api_board(
+        State(state): State<AppState>,
+        ApiQuery(query): ApiQuery<BoardQuery>,
+    ) -> Result<Json<ApiResponse<KanbanBoard>>> {
+        let board = board_for_query(&state.db, query).await
+            .map_err(|err| err.context("Failed to fetch board"))?;
+        Ok(Json(ApiResponse {
+            success: true,
+            data: Some(board),
+            message: None,
+        }))
     }
 
-    /// API endpoint to move an issue between columns
+    /// API endpoint to move an issue between columns.
+    /// The target column's workflow label replaces the issue's other workflow
+    /// labels, and its status (if it has one) becomes the issue's status.
     pub async fn api_move_issue(
         State(state): State<AppState>,
-        Json(request): Json<MoveIssueRequest>,
-    ) -> Json<ApiResponse<()>> {
-        // Map column names to issue statuses
-        let new_status = match request.to_column.as_str() {
-            "preparing" => IssueStatus::Open,
-            "progressing" => IssueStatus::InProgress,
-            "completed" => IssueStatus::Closed,
-            _ => {
-                return Json(ApiResponse {
-                    success: false,
-                    data: None,
-                    message: Some(format!("Invalid column: {}", request.to_column)),
-                });
-            }
-        };
+        ApiJson(request): ApiJson<MoveIssueRequest>,
+    ) -> Result<Json<ApiResponse<UndoToken>>> {
+        let definition = BoardDefinition::load(&state.db).await?;
+        let column = definition.column(&request.to_column)
+            .cloned()
+            .ok_or_else(|| Error::Validation(format!("Invalid column: {}", request.to_column)))?;
         
         // First check if the issue exists
-        match state.db.get_issue_by_id(request.issue_number as i64).await {
-            Ok(Some(_)) => {
-                // Issue exists, proceed with status update
-                match state.db.update_issue_status(request.issue_number as i64, new_status).await {
-                    Ok(_) => Json(ApiResponse {
-                        success: true,
-                        data: Some(()),
-                        message: Some(format!(
-                            "Successfully moved issue #{} from {} to {}",
-                            request.issue_number, request.from_column, request.to_column
-                        )),
-                    }),
-                    Err(err) => Json(ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some(format!("Failed to move issue: {}", err)),
-                    }),
+        let issue_id = request.issue_number as i64;
+        let mut issue = state.db.get_issue_by_id(issue_id).await?
+            .ok_or_else(|| Error::NotFound(format!("Issue #{} not found", request.issue_number)))?;
+
+        // Coming from another column counts against the target's WIP limit
+        let current = definition.column_for(&issue.labels, &issue.status).map(|c| c.id.clone());
+        let wip_limit = column.wip_limit.filter(|_| !request.force && current.as_ref() != Some(&column.id));
+
+        // Where the card lands among the target column's other cards, if the caller said
+        let placement = match (request.before, request.after, request.position) {
+            (Some(_), Some(_), _) => {
+                return Err(Error::Validation("Give either before or after, not both".to_string()));
+            }
+            (None, None, None) => None,
+            (before, after, position) => {
+                let board = create_board_from_db(&state.db).await?;
+                let others: Vec<i64> = board.columns.iter()
+                    .find(|c| c.id == column.id)
+                    .map(|c| c.cards.iter().map(|card| card.issue_number as i64).filter(|id| *id != issue_id).collect())
+                    .unwrap_or_default();
+                let index_of = |neighbour: u64| others.iter()
+                    .position(|id| *id == neighbour as i64)
+                    .ok_or_else(|| Error::Validation(format!("Issue #{} is not in column {}", neighbour, column.id)));
+                let index = match (before, after) {
+                    (Some(neighbour), _) => index_of(neighbour)?,
+                    (_, Some(neighbour)) => index_of(neighbour)? + 1,
+                    _ => position.unwrap_or(0).min(others.len()),
+                };
+                Some((others, index))
+            }
+        };
+
+        let labels = definition.labels_for_move(&issue.labels, &column);
+        let status = column.status.clone().unwrap_or_else(|| issue.status.clone());
+        // Reordering within a column leaves the issue itself untouched
+        let changed = labels != issue.labels || status != issue.status;
+        issue.labels = labels;
+        issue.status = status;
+
+        // One operation, so the board can offer to undo the whole move including the
+        // card's place, and so no other move can fill the column between counting its
+        // cards and moving in
+        let result = state.db.operation(async {
+            if let Some(limit) = wip_limit {
+                if state.db.count_issues_in_column(&definition, &column.id).await? >= limit as usize {
+                    return Err(Error::Conflict(format!(
+                        "{} is at its WIP limit of {}; force the move to go over it",
+                        column.title, limit
+                    )));
                 }
-            },
-            Ok(None) => {
-                // Issue doesn't exist
-                Json(ApiResponse {
-                    success: false,
-                    data: None,
-                    message: Some(format!("Issue #{} not found", request.issue_number)),
-                })
-            },
-            Err(err) => {
-                // Database error
-                Json(ApiResponse {
-                    success: false,
-                    data: None,
-                    message: Some(format!("Database error: {}", err)),
-                })
             }
-        }
+            if changed {
+                if let Some(label) = &column.label {
+                    if state.db.get_label_by_name(label).await?.is_none() {
+                        state.db.insert_label(&Label {
+                            id: None,
+                            name: label.clone(),
+                            color: column.color.clone(),
+                            description: Some(format!("Issues in the {} column", column.title)),
+                            created_at: Utc::now(),
+                        }).await?;
+                    }
+                }
+                state.db.update_issue(&issue).await?;
+            }
+            if let Some((others, index)) = &placement {
+                let rank = rank_at(&state.db, &column.id, others, *index).await?;
+                state.db.set_card_rank(issue_id, &column.id, &rank).await?;
+            }
+            Ok(())
+        }).await;
+        let ((), operation_id) = result.map_err(|err| err.context("Failed to move issue"))?;
+
+        state.publish(BoardEventKind::Moved, issue_id).await;
+
+        Ok(Json(ApiResponse {
+            success: true,
+            data: Some(UndoToken { operation_id }),
+            message: Some(format!(
+                "Successfully moved issue #{} from {} to {}",
+                request.issue_number, request.from_column, request.to_column
+            )),
+        }))
     }
 
-    /// API endpoint to refresh a specific column
+    /// API endpoint to refresh a specific column: returns the column with its current cards
     pub async fn api_refresh_column(
-        State(_state): State<AppState>,
-        Path(column_id): Path<String>,
-    ) -> Json<ApiResponse<()>> {
+        State(state): State<AppState>,
+        ApiPath(column_id): ApiPath<String>,
+    ) -> Result<Json<ApiResponse<KanbanColumn>>> {
         // Validate that the column exists
-        let valid_columns = ["preparing", "progressing", "completed"];
-        if !valid_columns.contains(&column_id.as_str()) {
-            return Json(ApiResponse {
-                success: false,
-                data: None,
-                message: Some(format!("Column '{}' not found", column_id)),
-            });
+        if BoardDefinition::load(&state.db).await?.column(&column_id).is_none() {
+            return Err(Error::NotFound(format!("Column '{}' not found", column_id)));
         }
         
-        // For database-based implementation, we can always refresh successfully
-        // since the data comes directly from the database
-        Json(ApiResponse {
+        let board = create_board_from_db(&state.db).await?;
+        let column = board.columns.into_iter()
+            .find(|column| column.id == column_id)
+            .ok_or_else(|| Error::NotFound(format!("Column '{}' not found", column_id)))?;
+        Ok(Json(ApiResponse {
             success: true,
-            data: Some(()),
+            data: Some(column),
             message: Some(format!("Successfully refreshed column '{}'", column_id)),
-        })
+        }))
+    }
+
+    /// Server-Sent Events stream of issue changes. Each `issue` event carries a
+    /// `BoardEvent` as JSON; a `resync` event means events were dropped and the
+    /// client should reload the board.
+    pub async fn api_events(
+        State(state): State<AppState>,
+    ) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+        let stream = BroadcastStream::new(state.subscribe()).filter_map(|event| match event {
+            Ok(event) => Event::default().event("issue").json_data(&event).ok().map(Ok),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                Some(Ok(Event::default().event("resync").data(missed.to_string())))
+            }
+        });
+
+        Sse::new(stream).keep_alive(KeepAlive::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::{BoardColumn, IssueStatus};
+
+    fn test_issue(title: &str) -> Issue {
+        Issue {
+            id: None,
+            title: title.to_string(),
+            description: None,
+            status: IssueStatus::Open,
+            priority: IssuePriority::Medium,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            assignee: None,
+            labels: vec![],
+            external_ref: None,
+            parent_id: None,
+        }
+    }
 
     async fn create_test_server() -> KanbanWebServer {
         let db = TaskDatabase::in_memory().await.unwrap();
@@ -328,7 +744,6 @@ mod tests {
     async fn test_web_server_creation() {
         let _server = create_test_server().await;
         // Server should be created successfully
-        assert!(true);
     }
 
     // Web route tests (GREEN phase - should work)
@@ -338,7 +753,6 @@ mod tests {
         // Router creation should work now
         let _router = server.create_router();
         // If we get here, router creation succeeded
-        assert!(true);
     }
 
     // Note: We can't easily test serve() since it would bind to a port and run indefinitely
@@ -351,7 +765,7 @@ mod tests {
         let state = State(server.app_state.clone());
         
         // Handler should work with empty database
-        let result = handlers::kanban_board(state).await;
+        let result = handlers::kanban_board(state, ApiQuery(BoardQuery::default())).await;
         // Should succeed with empty board
         assert!(result.is_ok());
     }
@@ -362,7 +776,7 @@ mod tests {
         let state = State(server.app_state.clone());
         
         // Handler should work with empty database
-        let result = handlers::api_board(state).await;
+        let result = handlers::api_board(state, ApiQuery(BoardQuery::default())).await.unwrap();
         // Should return a JSON response with success=true and empty board
         assert!(result.0.success);
         assert!(result.0.data.is_some());
@@ -371,30 +785,343 @@ mod tests {
         assert_eq!(board.columns.len(), 4);
     }
 
+    #[tokio::test]
+    async fn test_board_cards_include_linked_commits() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        let issue_id = db.insert_issue(&test_issue("Linked")).await.unwrap();
+        let commit = crate::db::GitCommit {
+            id: None,
+            hash: "0123456789abcdef".to_string(),
+            author_name: "Test".to_string(),
+            author_email: "test@example.com".to_string(),
+            commit_date: Utc::now(),
+            message: format!("Touch things\n\nRefs #{}", issue_id),
+            parent_hashes: vec![],
+            files_changed: vec!["a.rs".to_string()],
+            file_stats: vec![],
+            insertions: 3,
+            deletions: 1,
+        };
+        db.insert_commit(&commit).await.unwrap();
+        db.link_commit_to_issues(&commit).await.unwrap();
+
+        let server = KanbanWebServer::new(db);
+        let result = handlers::api_board(State(server.app_state.clone()), ApiQuery(BoardQuery::default())).await.unwrap();
+        let board = result.0.data.unwrap();
+        let card = board.columns.iter().flat_map(|c| c.cards.iter()).next().unwrap();
+        assert_eq!(card.commits.len(), 1);
+        assert_eq!(card.commits[0].short_hash, "01234567");
+        assert_eq!(card.commits[0].summary, "Touch things");
+        assert_eq!((card.commits[0].insertions, card.commits[0].deletions), (3, 1));
+        assert!(!card.commits[0].closes);
+
+        // The HTML page renders the commit list for the modal
+        let page = handlers::kanban_board(State(server.app_state.clone()), ApiQuery(BoardQuery::default())).await.unwrap();
+        let body = axum::body::to_bytes(page.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("Touch things"));
+    }
+
     #[tokio::test]
     async fn test_api_move_issue_handler_fails_for_nonexistent_issue() {
         let server = create_test_server().await;
         let state = State(server.app_state.clone());
-        let request = Json(MoveIssueRequest {
+        let request = ApiJson(MoveIssueRequest {
             issue_number: 999, // Non-existent issue
             from_column: "preparing".to_string(),
             to_column: "progressing".to_string(),
+            before: None,
+            after: None,
+            position: None,
+            force: false,
         });
         
         // Should fail since issue doesn't exist
         let result = handlers::api_move_issue(state, request).await;
-        assert!(!result.0.success);
-        assert!(result.0.message.is_some());
+        assert_eq!(result.unwrap_err(), Error::NotFound("Issue #999 not found".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_api_move_issue_follows_board_definition() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        let issue_id = db.insert_issue(&test_issue("Movable")).await.unwrap();
+        db.save_board_column(&BoardColumn {
+            id: "review".to_string(),
+            title: "In Review".to_string(),
+            color: "#e9d5ff".to_string(),
+            label: Some("Review".to_string()),
+            status: None,
+            position: 2,
+            wip_limit: None,
+        }).await.unwrap();
+        let server = KanbanWebServer::new(db);
+        let state = server.app_state.clone();
+
+        let moved = handlers::api_move_issue(State(state.clone()), ApiJson(MoveIssueRequest {
+            issue_number: issue_id as u64,
+            from_column: "evaluating".to_string(),
+            to_column: "progressing".to_string(),
+            before: None,
+            after: None,
+            position: None,
+            force: false,
+        })).await.unwrap();
+        assert!(moved.0.success, "{:?}", moved.0.message);
+        let issue = state.db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(issue.status, IssueStatus::InProgress);
+        assert_eq!(issue.labels, vec!["Progressing".to_string()]);
+
+        // Moving on swaps the workflow label and keeps the status of a status-less column
+        let moved = handlers::api_move_issue(State(state.clone()), ApiJson(MoveIssueRequest {
+            issue_number: issue_id as u64,
+            from_column: "progressing".to_string(),
+            to_column: "review".to_string(),
+            before: None,
+            after: None,
+            position: None,
+            force: false,
+        })).await.unwrap();
+        assert!(moved.0.success, "{:?}", moved.0.message);
+        let issue = state.db.get_issue_by_id(issue_id).await.unwrap().unwrap();
+        assert_eq!(issue.status, IssueStatus::InProgress);
+        assert_eq!(issue.labels, vec!["Review".to_string()]);
+
+        let board = handlers::api_board(State(state.clone()), ApiQuery(BoardQuery::default())).await.unwrap().0.data.unwrap();
+        let ids: Vec<&str> = board.columns.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["evaluating", "preparing", "progressing", "review", "done"]);
+        assert_eq!(board.columns[3].cards.len(), 1);
+        assert_eq!(board.columns[3].title, "In Review");
+
+        // Only ids from the definition are accepted
+        let rejected = handlers::api_move_issue(State(state.clone()), ApiJson(MoveIssueRequest {
+            issue_number: issue_id as u64,
+            from_column: "review".to_string(),
+            to_column: "completed".to_string(),
+            before: None,
+            after: None,
+            position: None,
+            force: false,
+        })).await;
+        assert!(matches!(rejected, Err(Error::Validation(_))));
+        let refreshed = handlers::api_refresh_column(State(state), ApiPath("review".to_string())).await;
+        let column = refreshed.unwrap().0.data.unwrap();
+        assert_eq!(column.cards.len(), 1);
+        assert_eq!(column.cards[0].title, "Movable");
+    }
+
+    #[tokio::test]
+    async fn test_api_move_issue_keeps_card_positions() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        let mut ids = Vec::new();
+        for title in ["First", "Second", "Third"] {
+            ids.push(db.insert_issue(&test_issue(title)).await.unwrap());
+        }
+        let server = KanbanWebServer::new(db);
+        let state = server.app_state.clone();
+        let titles = |board: &KanbanBoard, column: usize| -> Vec<String> {
+            board.columns[column].cards.iter().map(|card| card.title.clone()).collect()
+        };
+
+        // Reorder within the column: move "First" to the top
+        let moved = handlers::api_move_issue(State(state.clone()), ApiJson(MoveIssueRequest {
+            issue_number: ids[0] as u64,
+            from_column: "evaluating".to_string(),
+            to_column: "evaluating".to_string(),
+            before: None,
+            after: None,
+            position: Some(0),
+            force: false,
+        })).await.unwrap();
+        assert!(moved.0.success, "{:?}", moved.0.message);
+        let board = handlers::create_board_from_db(&state.db).await.unwrap();
+        assert_eq!(titles(&board, 0), vec!["First", "Third", "Second"]);
+
+        // Across columns, into the middle of the target
+        for id in [ids[1], ids[2]] {
+            let moved = handlers::api_move_issue(State(state.clone()), ApiJson(MoveIssueRequest {
+                issue_number: id as u64,
+                from_column: "evaluating".to_string(),
+                to_column: "progressing".to_string(),
+                before: None,
+                after: None,
+                position: Some(0),
+                force: false,
+            })).await.unwrap();
+            assert!(moved.0.success, "{:?}", moved.0.message);
+        }
+        let moved = handlers::api_move_issue(State(state.clone()), ApiJson(MoveIssueRequest {
+            issue_number: ids[0] as u64,
+            from_column: "evaluating".to_string(),
+            to_column: "progressing".to_string(),
+            before: None,
+            after: None,
+            position: Some(1),
+            force: false,
+        })).await.unwrap();
+        assert!(moved.0.success, "{:?}", moved.0.message);
+        let board = handlers::create_board_from_db(&state.db).await.unwrap();
+        assert!(board.columns[0].cards.is_empty());
+        assert_eq!(titles(&board, 2), vec!["Third", "First", "Second"]);
+        let issue = state.db.get_issue_by_id(ids[0]).await.unwrap().unwrap();
+        assert_eq!(issue.status, IssueStatus::InProgress);
+
+        // Events carry the card's place so other boards can insert it in the same spot
+        let places = card_places(&state.db).await.unwrap();
+        let (card, place) = placed_card(&state.db, ids[0], &places).await.unwrap().unwrap();
+        assert_eq!(card.title, "First");
+        assert_eq!(place, Some(("progressing".to_string(), 1)));
+    }
+
+    #[tokio::test]
+    async fn test_api_move_issue_before_and_after_cards() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        let mut ids = Vec::new();
+        for title in ["A", "B", "C", "D"] {
+            ids.push(db.insert_issue(&test_issue(title)).await.unwrap());
+        }
+        let server = KanbanWebServer::new(db);
+        let state = server.app_state.clone();
+        let place = |id: i64, before: Option<i64>, after: Option<i64>| MoveIssueRequest {
+            issue_number: id as u64,
+            from_column: "evaluating".to_string(),
+            to_column: "evaluating".to_string(),
+            before: before.map(|id| id as u64),
+            after: after.map(|id| id as u64),
+            position: None,
+            force: false,
+        };
+        let titles = |board: KanbanBoard| -> Vec<String> {
+            board.columns[0].cards.iter().map(|card| card.title.clone()).collect()
+        };
+
+        // Newest first until someone places a card
+        let board = handlers::create_board_from_db(&state.db).await.unwrap();
+        assert_eq!(titles(board), vec!["D", "C", "B", "A"]);
+
+        assert!(handlers::api_move_issue(State(state.clone()), ApiJson(place(ids[0], None, Some(ids[3])))).await.unwrap().0.success);
+        assert!(handlers::api_move_issue(State(state.clone()), ApiJson(place(ids[1], Some(ids[3]), None))).await.unwrap().0.success);
+        let board = handlers::create_board_from_db(&state.db).await.unwrap();
+        assert_eq!(titles(board), vec!["B", "D", "A", "C"]);
+
+        // Placing one card writes its own rank and leaves the ranked ones alone
+        let before = state.db.get_card_ranks().await.unwrap();
+        let moved = handlers::api_move_issue(State(state.clone()), ApiJson(place(ids[2], Some(ids[0]), None))).await.unwrap().0;
+        let after = state.db.get_card_ranks().await.unwrap();
+        assert!(before.iter().all(|rank| after.contains(rank)));
+        assert_eq!(after.len(), before.len() + 1);
+        let board = handlers::create_board_from_db(&state.db).await.unwrap();
+        assert_eq!(titles(board), vec!["B", "D", "C", "A"]);
+
+        // A reorder is an operation of its own, and undoing it puts the card back
+        let operation_id = moved.data.unwrap().operation_id.expect("A reorder can be undone");
+        state.db.undo(operation_id).await.unwrap();
+        assert_eq!(state.db.get_card_ranks().await.unwrap(), before);
+        let board = handlers::create_board_from_db(&state.db).await.unwrap();
+        assert_eq!(titles(board), vec!["B", "D", "A", "C"]);
+
+        // Neighbours must be other cards in the target column
+        for request in [
+            place(ids[0], Some(ids[1]), Some(ids[2])),
+            place(ids[0], Some(ids[0]), None),
+            place(ids[0], None, Some(999)),
+        ] {
+            let rejected = handlers::api_move_issue(State(state.clone()), ApiJson(request)).await;
+            assert!(matches!(rejected, Err(Error::Validation(_))), "{:?}", rejected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_move_issue_respects_wip_limits() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        let mut ids = Vec::new();
+        for title in ["Doing", "Waiting", "Urgent"] {
+            ids.push(db.insert_issue(&test_issue(title)).await.unwrap());
+        }
+        let mut progressing = db.get_board_columns().await.unwrap().remove(2);
+        progressing.wip_limit = Some(1);
+        db.save_board_column(&progressing).await.unwrap();
+        let server = KanbanWebServer::new(db);
+        let state = server.app_state.clone();
+        let request = |id: i64, force: bool| ApiJson(MoveIssueRequest {
+            issue_number: id as u64,
+            from_column: "evaluating".to_string(),
+            to_column: "progressing".to_string(),
+            before: None,
+            after: None,
+            position: None,
+            force,
+        });
+
+        assert!(handlers::api_move_issue(State(state.clone()), request(ids[0], false)).await.unwrap().0.success);
+        let rejected = handlers::api_move_issue(State(state.clone()), request(ids[1], false)).await;
+        assert!(matches!(rejected, Err(Error::Conflict(_))), "{:?}", rejected);
+        let issue = state.db.get_issue_by_id(ids[1]).await.unwrap().unwrap();
+        assert_eq!(issue.status, IssueStatus::Open, "A refused move changes nothing");
+
+        // Reordering inside a full column is fine; forcing goes over the limit
+        assert!(handlers::api_move_issue(State(state.clone()), request(ids[0], false)).await.unwrap().0.success);
+        assert!(handlers::api_move_issue(State(state.clone()), request(ids[2], true)).await.unwrap().0.success);
+
+        let board = handlers::api_board(State(state.clone()), ApiQuery(BoardQuery::default())).await.unwrap().0.data.unwrap();
+        let column = &board.columns[2];
+        assert_eq!(column.wip_limit, Some(1));
+        assert_eq!(column.cards.len(), 2);
+        assert!(column.is_over_limit());
+        assert!(!board.columns[0].is_over_limit());
+
+        // The page flags the column and shows the limit next to the count
+        let page = handlers::kanban_board(State(state), ApiQuery(BoardQuery::default())).await.unwrap();
+        let body = axum::body::to_bytes(page.into_body(), usize::MAX).await.unwrap();
+        let html = String::from_utf8_lossy(&body);
+        assert!(html.contains("column-progressing over-limit"));
+        assert!(html.contains("2 / 1"));
+    }
+
+
+    #[tokio::test]
+    async fn test_board_swimlanes() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        let server = axum_test::TestServer::new(KanbanWebServer::new(db).create_router()).unwrap();
+        for (title, assignee) in [("Mine", Some("alice")), ("Nobody's", None)] {
+            server.post("/api/issues").json(&serde_json::json!({ "title": title, "assignee": assignee })).await.assert_status(StatusCode::CREATED);
+        }
+
+        let flat: serde_json::Value = server.get("/api/board").await.json();
+        assert!(flat["data"]["columns"][0].get("lanes").is_none());
+
+        let board: serde_json::Value = server.get("/api/board").add_query_param("swimlane", "assignee").await.json();
+        assert_eq!(board["data"]["swimlane"], "assignee");
+        let lanes = board["data"]["columns"][0]["lanes"].as_array().unwrap();
+        assert_eq!(lanes.len(), 2);
+        assert_eq!(lanes[0]["value"], "alice");
+        assert_eq!(lanes[0]["cards"][0]["title"], "Mine");
+        assert_eq!(lanes[1]["value"], serde_json::Value::Null);
+        assert_eq!(lanes[1]["title"], "Unassigned");
+
+        let page = server.get("/").add_query_param("swimlane", "assignee").await.text();
+        assert!(page.contains(r#"data-lane="alice""#));
+        assert!(page.contains("with-lanes"));
+
+        let rejected = server.get("/api/board").add_query_param("swimlane", "people").await;
+        rejected.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        // A search narrows the board and the page keeps the query in its search box
+        let board: serde_json::Value = server.get("/api/board").add_query_param("q", "assignee:alice").await.json();
+        let cards = board["data"]["columns"][0]["cards"].as_array().unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0]["title"], "Mine");
+        let page = server.get("/").add_query_param("q", "-assignee:alice").await.text();
+        assert!(page.contains("Nobody&#x27;s") && !page.contains(">Mine<"), "{}", page.len());
+        assert!(page.contains(r#"value="-assignee:alice""#));
     }
 
     #[tokio::test]
     async fn test_api_refresh_column_handler_works() {
         let server = create_test_server().await;
         let state = State(server.app_state.clone());
-        let column_id = Path("preparing".to_string());
+        let column_id = ApiPath("preparing".to_string());
         
         // Should succeed for valid column
-        let result = handlers::api_refresh_column(state, column_id).await;
+        let result = handlers::api_refresh_column(state, column_id).await.unwrap();
         assert!(result.0.success);
         assert!(result.0.message.is_some());
     }
@@ -403,12 +1130,52 @@ mod tests {
     async fn test_api_refresh_column_handler_fails_for_invalid_column() {
         let server = create_test_server().await;
         let state = State(server.app_state.clone());
-        let column_id = Path("invalid_column".to_string());
+        let column_id = ApiPath("invalid_column".to_string());
         
         // Should fail for invalid column
         let result = handlers::api_refresh_column(state, column_id).await;
-        assert!(!result.0.success);
-        assert!(result.0.message.is_some());
+        assert!(matches!(result, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_api_events_streams_published_changes() {
+        let server = create_test_server().await;
+        let state = server.app_state.clone();
+        let issue_id = state.db.insert_issue(&Issue { priority: IssuePriority::High, ..test_issue("Streamed") }).await.unwrap();
+
+        let sse = handlers::api_events(State(state.clone())).await;
+        let mut body = sse.into_response().into_body().into_data_stream();
+        state.publish(BoardEventKind::Created, issue_id).await;
+
+        let frame = body.next().await.unwrap().unwrap();
+        let frame = String::from_utf8_lossy(&frame);
+        assert!(frame.starts_with("event: issue\n"), "{}", frame);
+        let data = frame.lines().find_map(|line| line.strip_prefix("data: ")).unwrap();
+        let event: BoardEvent = serde_json::from_str(data).unwrap();
+        assert_eq!(event.kind, BoardEventKind::Created);
+        assert_eq!(event.card.unwrap().priority, Priority::High);
+    }
+
+    #[tokio::test]
+    async fn test_errors_map_to_status_codes() {
+        let cases = [
+            (Error::NotFound("gone".to_string()), StatusCode::NOT_FOUND, "not_found"),
+            (Error::Conflict("taken".to_string()), StatusCode::CONFLICT, "conflict"),
+            (Error::Validation("bad".to_string()), StatusCode::UNPROCESSABLE_ENTITY, "validation"),
+            (Error::Upstream("down".to_string()), StatusCode::BAD_GATEWAY, "upstream"),
+            (Error::Storage("broken".to_string()), StatusCode::INTERNAL_SERVER_ERROR, "storage"),
+        ];
+
+        for (error, status, kind) in cases {
+            let message = error.message().to_string();
+            let response = error.into_response();
+            assert_eq!(response.status(), status);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: ApiErrorBody = serde_json::from_slice(&bytes).unwrap();
+            assert!(!body.success);
+            assert_eq!(body.error, kind);
+            assert_eq!(body.message, message);
+        }
     }
 
     #[test]
@@ -426,4 +1193,58 @@ mod tests {
         assert!(result.contains("This is line 1"), "Should contain first line");
         assert!(result.contains("This is line 2"), "Should contain second line");
     }
+
+    #[test]
+    fn test_markdown_to_html_strips_malicious_payloads() {
+        let payloads = [
+            "<script>alert(1)</script>",
+            "<img src=x onerror=alert(1)>",
+            "<svg onload=alert(1)><circle r=1 /></svg>",
+            "<iframe src=\"https://evil.example\"></iframe>",
+            "<style>body { display: none }</style>",
+            "<div style=\"background: url(javascript:alert(1))\">styled</div>",
+            "<a href=\"javascript:alert(1)\">click</a>",
+            "<a href=\"https://example.com\" onclick=\"alert(1)\">click</a>",
+            "<a href=\"data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==\">click</a>",
+            "<form action=\"https://evil.example\"><input type=\"text\" name=\"q\"></form>",
+            "<math><mi xlink:href=\"javascript:alert(1)\">x</mi></math>",
+            "[click](javascript:alert(1))",
+            "[click](JaVaScRiPt:alert(1))",
+            "[click](&#106;avascript:alert(1))",
+            "[click](vbscript:msgbox(1))",
+            "![img](javascript:alert(1))",
+            "[click](data:text/html,<script>alert(1)</script>)",
+            "<p>**bold**</p><object data=\"evil.swf\"></object>",
+        ];
+
+        for payload in payloads {
+            let html = super::markdown_to_html(payload).to_lowercase();
+            for forbidden in [
+                "<script", "onerror", "onload", "onclick", "<svg", "<iframe", "<style",
+                "style=", "javascript:", "vbscript:", "data:", "<form", "type=\"text\"",
+                "<math", "<object",
+            ] {
+                assert!(
+                    !html.contains(forbidden),
+                    "payload {:?} rendered {:?} containing {:?}",
+                    payload, html, forbidden
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_markdown_to_html_keeps_safe_markup() {
+        let html = super::markdown_to_html(
+            "# Title\n\n[docs](https://example.com/docs) and [mail](mailto:team@example.com)\n\n- [x] done\n- [ ] todo\n\n| a | b |\n|---|---|\n| 1 | 2 |",
+        );
+
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("href=\"https://example.com/docs\""));
+        assert!(html.contains("href=\"mailto:team@example.com\""));
+        assert_eq!(html.matches("rel=\"noopener nofollow\"").count(), 2);
+        assert_eq!(html.matches("type=\"checkbox\"").count(), 2);
+        assert!(html.contains("checked"));
+        assert!(html.contains("<table>"));
+    }
 }
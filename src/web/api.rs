@@ -0,0 +1,1034 @@
+//! Resource endpoints for issues, comments, issue history, links and sub-tasks, undo and the
+//! trash, labels and commits, and full-text search.
+//!
+//! Successful calls answer with the same `ApiResponse` envelope as the board
+//! endpoints (201 for created resources). Failures answer with an
+//! `ApiErrorBody` and the status of the `Error` kind.
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{markdown_to_html, ApiJson, ApiPath, ApiQuery, ApiResponse, AppState, BoardEventKind, UndoToken};
+use crate::db::dependencies::{DependencyKind, IssueLink};
+use crate::db::events::{IssueEvent, Operation};
+use crate::db::search::Snippet;
+use crate::db::{Comment, CommitFilter, GitCommit, Issue, IssuePriority, IssueStatus, Label, TaskDatabase, TrashedIssue};
+use crate::error::{Error, Result};
+use crate::query::{Comparison, Filter, Query, Term};
+
+pub type ApiResult<T> = Result<(StatusCode, Json<ApiResponse<T>>)>;
+
+fn respond<T>(status: StatusCode, data: T, message: Option<String>) -> ApiResult<T> {
+    Ok((status, Json(ApiResponse { success: true, data: Some(data), message })))
+}
+
+/// Query parameters of `GET /api/issues`; unset fields match every issue
+#[derive(Debug, Default, Deserialize)]
+pub struct IssueQuery {
+    /// Search query, e.g. `label:bug priority:>=high "login"`; see `crate::query`
+    pub q: Option<Query>,
+    pub status: Option<IssueStatus>,
+    pub priority: Option<IssuePriority>,
+    pub assignee: Option<String>,
+    pub label: Option<String>,
+}
+
+impl IssueQuery {
+    /// `q` with the other parameters added as terms, so they mean what the same
+    /// terms mean in a search query
+    fn into_query(self) -> Query {
+        let mut query = self.q.unwrap_or_default();
+        let filters = [
+            self.status.map(Filter::Status),
+            self.priority.map(|priority| Filter::Priority(Comparison::Eq, priority)),
+            self.assignee.map(Filter::Assignee),
+            self.label.map(Filter::Label),
+        ];
+        query.terms.extend(filters.into_iter().flatten().map(|filter| Term { negated: false, filter }));
+        query
+    }
+}
+
+/// Body of `POST /api/issues`
+#[derive(Debug, Deserialize)]
+pub struct CreateIssueRequest {
+    pub title: String,
+    pub description: Option<String>,
+    pub status: Option<IssueStatus>,
+    pub priority: Option<IssuePriority>,
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Make the issue a sub-task of this one
+    pub parent_id: Option<i64>,
+}
+
+/// Body of `PATCH /api/issues/:id`. Omitted fields are left unchanged;
+/// an empty description or assignee, or a null `parent_id`, clears it.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateIssueRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<IssueStatus>,
+    pub priority: Option<IssuePriority>,
+    pub assignee: Option<String>,
+    /// Replaces all of the issue's labels
+    pub labels: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub parent_id: Option<Option<i64>>,
+}
+
+/// Tell a field set to null (`Some(None)`) apart from an omitted one (`None`)
+fn nullable<'de, D, T>(deserializer: D) -> std::result::Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Body of `POST /api/issues/:id/comments`
+#[derive(Debug, Deserialize)]
+pub struct CreateCommentRequest {
+    pub author: String,
+    pub body: String,
+}
+
+/// Body of `POST /api/issues/:id/links`, e.g. `{"kind": "blocks", "issue": 5}`
+#[derive(Debug, Deserialize)]
+pub struct CreateLinkRequest {
+    pub kind: DependencyKind,
+    /// The other issue's id
+    pub issue: i64,
+}
+
+/// Body of `PATCH /api/comments/:id`
+#[derive(Debug, Deserialize)]
+pub struct UpdateCommentRequest {
+    pub body: String,
+}
+
+/// A comment as returned by the API, with its Markdown rendered to sanitized HTML
+#[derive(Debug, Serialize)]
+pub struct CommentView {
+    #[serde(flatten)]
+    pub comment: Comment,
+    pub body_html: String,
+}
+
+impl From<Comment> for CommentView {
+    fn from(comment: Comment) -> Self {
+        Self { body_html: markdown_to_html(&comment.body), comment }
+    }
+}
+
+/// Body of `POST /api/labels`
+#[derive(Debug, Deserialize)]
+pub struct CreateLabelRequest {
+    pub name: String,
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Body of `PATCH /api/labels/:name`. Omitted fields are left unchanged;
+/// an empty description clears it.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateLabelRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Query parameters of `GET /api/search`
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    #[serde(default = "default_search_limit")]
+    pub limit: usize,
+}
+
+fn default_search_limit() -> usize {
+    20
+}
+
+/// Body of a `GET /api/search` response; each kind is ordered best match first
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub issues: Vec<SearchHit<Issue>>,
+    pub commits: Vec<SearchHit<GitCommit>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit<T> {
+    #[serde(flatten)]
+    pub item: T,
+    /// HTML-escaped text around the match, with matched words in `<mark>`
+    pub snippet: String,
+    /// Relevance; higher is better
+    pub score: f64,
+}
+
+fn snippet_html(snippet: &Snippet) -> String {
+    snippet.highlight("<mark>", "</mark>", |text| {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    })
+}
+
+async fn fetch_issue(db: &TaskDatabase, id: i64) -> Result<Issue> {
+    db.get_issue_by_id(id).await?
+        .ok_or_else(|| Error::NotFound(format!("Issue #{} not found", id)))
+}
+
+async fn fetch_comment(db: &TaskDatabase, id: i64) -> Result<Comment> {
+    db.get_comment(id).await?
+        .ok_or_else(|| Error::NotFound(format!("Comment {} not found", id)))
+}
+
+async fn fetch_label(db: &TaskDatabase, name: &str) -> Result<Label> {
+    db.get_label_by_name(name).await?
+        .ok_or_else(|| Error::NotFound(format!("Label '{}' not found", name)))
+}
+
+fn validate_title(title: &str) -> Result<()> {
+    if title.trim().is_empty() {
+        return Err(Error::Validation("Issue title must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// Trim label names, drop empty ones and reject labels that don't exist
+async fn checked_labels(db: &TaskDatabase, labels: Vec<String>) -> Result<Vec<String>> {
+    let mut checked = Vec::new();
+    for label in labels.into_iter().map(|label| label.trim().to_string()) {
+        if label.is_empty() || checked.contains(&label) {
+            continue;
+        }
+        if db.get_label_by_name(&label).await?.is_none() {
+            return Err(Error::Validation(format!("Label '{}' does not exist", label)));
+        }
+        checked.push(label);
+    }
+    Ok(checked)
+}
+
+/// `GET /api/issues`
+pub async fn list_issues(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<IssueQuery>,
+) -> ApiResult<Vec<Issue>> {
+    let issues = state.db.query_issues(&query.into_query()).await?;
+    respond(StatusCode::OK, issues, None)
+}
+
+/// `POST /api/issues`
+pub async fn create_issue(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<CreateIssueRequest>,
+) -> ApiResult<Issue> {
+    validate_title(&request.title)?;
+    let labels = checked_labels(&state.db, request.labels).await?;
+
+    let id = state.db.insert_issue(&Issue {
+        id: None,
+        title: request.title,
+        description: request.description.filter(|d| !d.is_empty()),
+        status: request.status.unwrap_or(IssueStatus::Open),
+        priority: request.priority.unwrap_or(IssuePriority::Medium),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        assignee: request.assignee.filter(|a| !a.is_empty()),
+        labels,
+        external_ref: None,
+        parent_id: request.parent_id,
+    }).await?;
+
+    let issue = fetch_issue(&state.db, id).await?;
+    state.publish(BoardEventKind::Created, id).await;
+    respond(StatusCode::CREATED, issue, Some(format!("Created issue #{}", id)))
+}
+
+/// `GET /api/issues/:id`
+pub async fn get_issue(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Issue> {
+    let issue = fetch_issue(&state.db, id).await?;
+    respond(StatusCode::OK, issue, None)
+}
+
+/// `PATCH /api/issues/:id`
+pub async fn update_issue(
+    State(state): State<AppState>,
+    ApiPath(id): ApiPath<i64>,
+    ApiJson(request): ApiJson<UpdateIssueRequest>,
+) -> ApiResult<Issue> {
+    let mut issue = fetch_issue(&state.db, id).await?;
+    let old_parent = issue.parent_id;
+
+    if let Some(title) = request.title {
+        validate_title(&title)?;
+        issue.title = title;
+    }
+    if let Some(description) = request.description {
+        issue.description = Some(description).filter(|d| !d.is_empty());
+    }
+    if let Some(status) = request.status {
+        issue.status = status;
+    }
+    if let Some(priority) = request.priority {
+        issue.priority = priority;
+    }
+    if let Some(assignee) = request.assignee {
+        issue.assignee = Some(assignee).filter(|a| !a.is_empty());
+    }
+    if let Some(labels) = request.labels {
+        issue.labels = checked_labels(&state.db, labels).await?;
+    }
+    if let Some(parent_id) = request.parent_id {
+        issue.parent_id = parent_id;
+    }
+
+    state.db.update_issue(&issue).await?;
+    let issue = fetch_issue(&state.db, id).await?;
+    state.publish(BoardEventKind::Updated, id).await;
+    if let Some(old_parent) = old_parent.filter(|old| issue.parent_id != Some(*old)) {
+        state.publish(BoardEventKind::Updated, old_parent).await;
+    }
+    respond(StatusCode::OK, issue, Some(format!("Updated issue #{}", id)))
+}
+
+/// `DELETE /api/issues/:id`; the issue goes to the trash
+pub async fn delete_issue(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<UndoToken> {
+    fetch_issue(&state.db, id).await?;
+    let ((), operation_id) = state.db.operation(state.db.delete_issue(id)).await?;
+    state.publish(BoardEventKind::Deleted, id).await;
+    respond(StatusCode::OK, UndoToken { operation_id }, Some(format!("Moved issue #{} to the trash", id)))
+}
+
+/// `POST /api/operations/:id/undo`; answers with the operation that reversed it
+pub async fn undo_operation(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Operation> {
+    let undo = state.db.undo(id).await?;
+    let mut issue_ids: Vec<i64> = undo.events.iter().map(|event| event.issue_id).collect();
+    issue_ids.sort_unstable();
+    issue_ids.dedup();
+    for issue_id in issue_ids {
+        state.publish(BoardEventKind::Updated, issue_id).await;
+    }
+    respond(StatusCode::OK, undo, Some(format!("Undid operation {}", id)))
+}
+
+/// `GET /api/trash`
+pub async fn list_trash(State(state): State<AppState>) -> ApiResult<Vec<TrashedIssue>> {
+    respond(StatusCode::OK, state.db.get_trashed_issues().await?, None)
+}
+
+/// `POST /api/trash/:id/restore`
+pub async fn restore_issue(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Issue> {
+    if !state.db.restore_issue(id).await? {
+        return Err(Error::NotFound(format!("Issue #{} is not in the trash", id)));
+    }
+    state.publish(BoardEventKind::Created, id).await;
+    respond(StatusCode::OK, fetch_issue(&state.db, id).await?, Some(format!("Restored issue #{}", id)))
+}
+
+/// `DELETE /api/trash`: purge every issue in the trash
+pub async fn purge_trash(State(state): State<AppState>) -> ApiResult<usize> {
+    let purged = state.db.purge_trash(None).await?;
+    respond(StatusCode::OK, purged, Some(format!("Purged {} issues", purged)))
+}
+
+/// `DELETE /api/trash/:id`
+pub async fn purge_trashed_issue(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<usize> {
+    let purged = state.db.purge_trash(Some(&[id])).await?;
+    respond(StatusCode::OK, purged, Some(format!("Purged issue #{}", id)))
+}
+
+/// `GET /api/issues/:id/comments`
+pub async fn list_comments(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Vec<CommentView>> {
+    fetch_issue(&state.db, id).await?;
+    let comments = state.db.get_comments_for_issue(id).await?;
+    respond(StatusCode::OK, comments.into_iter().map(CommentView::from).collect(), None)
+}
+
+/// `GET /api/issues/:id/events`; deleted issues keep their history
+pub async fn list_issue_events(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Vec<IssueEvent>> {
+    let events = state.db.get_issue_events(id).await?;
+    if events.is_empty() {
+        fetch_issue(&state.db, id).await?;
+    }
+    respond(StatusCode::OK, events, None)
+}
+
+/// `GET /api/issues/:id/children`: the issue's direct sub-tasks
+pub async fn list_children(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Vec<Issue>> {
+    fetch_issue(&state.db, id).await?;
+    respond(StatusCode::OK, state.db.get_children(id).await?, None)
+}
+
+/// `GET /api/issues/:id/links`
+pub async fn list_links(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<Vec<IssueLink>> {
+    fetch_issue(&state.db, id).await?;
+    respond(StatusCode::OK, state.db.get_issue_links(id).await?, None)
+}
+
+/// `POST /api/issues/:id/links`; answers with the issue's links
+pub async fn create_link(
+    State(state): State<AppState>,
+    ApiPath(id): ApiPath<i64>,
+    ApiJson(request): ApiJson<CreateLinkRequest>,
+) -> ApiResult<Vec<IssueLink>> {
+    let created = state.db.link_issues(id, request.kind, request.issue).await?;
+    if created {
+        state.publish_all(BoardEventKind::Updated, &[id, request.issue]).await;
+    }
+    let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+    respond(status, state.db.get_issue_links(id).await?, Some(format!("#{} {} #{}", id, request.kind, request.issue)))
+}
+
+/// `DELETE /api/issues/:id/links/:kind/:other`
+pub async fn delete_link(
+    State(state): State<AppState>,
+    ApiPath((id, kind, other)): ApiPath<(i64, DependencyKind, i64)>,
+) -> ApiResult<()> {
+    if !state.db.unlink_issues(id, kind, other).await? {
+        return Err(Error::NotFound(format!("Issue #{} is not linked as {} #{}", id, kind, other)));
+    }
+    state.publish_all(BoardEventKind::Updated, &[id, other]).await;
+    respond(StatusCode::OK, (), Some(format!("#{} no longer {} #{}", id, kind, other)))
+}
+
+/// `GET /api/ready`: open issues nothing open is blocking, most urgent first
+pub async fn list_ready_issues(State(state): State<AppState>) -> ApiResult<Vec<Issue>> {
+    respond(StatusCode::OK, state.db.get_ready_issues().await?, None)
+}
+
+/// `POST /api/issues/:id/comments`
+pub async fn create_comment(
+    State(state): State<AppState>,
+    ApiPath(id): ApiPath<i64>,
+    ApiJson(request): ApiJson<CreateCommentRequest>,
+) -> ApiResult<CommentView> {
+    fetch_issue(&state.db, id).await?;
+    let author = request.author.trim().to_string();
+    if author.is_empty() {
+        return Err(Error::Validation("Comment author must not be empty".to_string()));
+    }
+
+    let now = Utc::now();
+    let comment_id = state.db.insert_comment(&Comment {
+        id: None,
+        issue_id: id,
+        author,
+        body: request.body,
+        created_at: now,
+        updated_at: now,
+        external_ref: None,
+        synced_at: None,
+    }).await?;
+
+    let comment = fetch_comment(&state.db, comment_id).await?;
+    state.publish(BoardEventKind::Updated, id).await;
+    respond(StatusCode::CREATED, comment.into(), Some(format!("Commented on issue #{}", id)))
+}
+
+/// `PATCH /api/comments/:id`
+pub async fn update_comment(
+    State(state): State<AppState>,
+    ApiPath(id): ApiPath<i64>,
+    ApiJson(request): ApiJson<UpdateCommentRequest>,
+) -> ApiResult<CommentView> {
+    let mut comment = fetch_comment(&state.db, id).await?;
+    comment.body = request.body;
+    comment.updated_at = Utc::now();
+    state.db.update_comment(&comment).await?;
+
+    let comment = fetch_comment(&state.db, id).await?;
+    respond(StatusCode::OK, comment.into(), Some(format!("Updated comment {}", id)))
+}
+
+/// `DELETE /api/comments/:id`
+pub async fn delete_comment(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> ApiResult<()> {
+    let comment = fetch_comment(&state.db, id).await?;
+    state.db.delete_comment(id).await?;
+    state.publish(BoardEventKind::Updated, comment.issue_id).await;
+    respond(StatusCode::OK, (), Some(format!("Deleted comment {}", id)))
+}
+
+/// `GET /api/labels`
+pub async fn list_labels(State(state): State<AppState>) -> ApiResult<Vec<Label>> {
+    let labels = state.db.get_all_labels().await?;
+    respond(StatusCode::OK, labels, None)
+}
+
+/// `POST /api/labels`
+pub async fn create_label(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<CreateLabelRequest>,
+) -> ApiResult<Label> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::Validation("Label name must not be empty".to_string()));
+    }
+    if state.db.get_label_by_name(&name).await?.is_some() {
+        return Err(Error::Conflict(format!("Label '{}' already exists", name)));
+    }
+
+    state.db.insert_label(&Label {
+        id: None,
+        name: name.clone(),
+        color: request.color.unwrap_or_else(|| "#808080".to_string()),
+        description: request.description.filter(|d| !d.is_empty()),
+        created_at: Utc::now(),
+    }).await?;
+
+    let label = fetch_label(&state.db, &name).await?;
+    respond(StatusCode::CREATED, label, Some(format!("Created label '{}'", name)))
+}
+
+/// `GET /api/labels/:name`
+pub async fn get_label(State(state): State<AppState>, ApiPath(name): ApiPath<String>) -> ApiResult<Label> {
+    let label = fetch_label(&state.db, &name).await?;
+    respond(StatusCode::OK, label, None)
+}
+
+/// `PATCH /api/labels/:name`
+pub async fn update_label(
+    State(state): State<AppState>,
+    ApiPath(name): ApiPath<String>,
+    ApiJson(request): ApiJson<UpdateLabelRequest>,
+) -> ApiResult<Label> {
+    let mut label = fetch_label(&state.db, &name).await?;
+
+    if let Some(new_name) = request.name {
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() {
+            return Err(Error::Validation("Label name must not be empty".to_string()));
+        }
+        if new_name != label.name && state.db.get_label_by_name(&new_name).await?.is_some() {
+            return Err(Error::Conflict(format!("Label '{}' already exists", new_name)));
+        }
+        label.name = new_name;
+    }
+    if let Some(color) = request.color {
+        label.color = color;
+    }
+    if let Some(description) = request.description {
+        label.description = Some(description).filter(|d| !d.is_empty());
+    }
+
+    let affected = state.db.get_issue_ids_with_label(&name).await?;
+    state.db.update_label(&label).await?;
+    let label = fetch_label(&state.db, &label.name).await?;
+    state.publish_all(BoardEventKind::Updated, &affected).await;
+    respond(StatusCode::OK, label, Some(format!("Updated label '{}'", name)))
+}
+
+/// `DELETE /api/labels/:name`
+pub async fn delete_label(State(state): State<AppState>, ApiPath(name): ApiPath<String>) -> ApiResult<()> {
+    let affected = state.db.get_issue_ids_with_label(&name).await?;
+    if !state.db.delete_label(&name).await? {
+        return Err(Error::NotFound(format!("Label '{}' not found", name)));
+    }
+    state.publish_all(BoardEventKind::Updated, &affected).await;
+    respond(StatusCode::OK, (), Some(format!("Deleted label '{}'", name)))
+}
+
+/// `GET /api/commits?author=&since=&until=&path=&issue=&limit=`
+pub async fn list_commits(
+    State(state): State<AppState>,
+    ApiQuery(filter): ApiQuery<CommitFilter>,
+) -> ApiResult<Vec<GitCommit>> {
+    let commits = state.db.get_commits(&filter).await?;
+    respond(StatusCode::OK, commits, None)
+}
+
+/// `GET /api/search?q=&limit=`
+pub async fn search(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<SearchQuery>,
+) -> ApiResult<SearchResponse> {
+    let results = state.db.search(&query.q, query.limit).await?;
+    let response = SearchResponse {
+        issues: results.issues.into_iter()
+            .map(|hit| SearchHit { snippet: snippet_html(&hit.snippet), item: hit.issue, score: hit.score })
+            .collect(),
+        commits: results.commits.into_iter()
+            .map(|hit| SearchHit { snippet: snippet_html(&hit.snippet), item: hit.commit, score: hit.score })
+            .collect(),
+    };
+    respond(StatusCode::OK, response, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::KanbanWebServer;
+    use axum_test::TestServer;
+    use serde_json::{json, Value};
+
+    async fn test_server() -> TestServer {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        TestServer::new(KanbanWebServer::new(db).create_router()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_issue_crud() {
+        let server = test_server().await;
+
+        let created = server.post("/api/issues")
+            .json(&json!({ "title": "Fix login", "priority": "high", "labels": ["bug"] }))
+            .await;
+        created.assert_status(StatusCode::CREATED);
+        let issue: Value = created.json();
+        let id = issue["data"]["id"].as_i64().unwrap();
+        assert_eq!(issue["data"]["status"], "Open");
+        assert_eq!(issue["data"]["labels"], json!(["bug"]));
+
+        let fetched: Value = server.get(&format!("/api/issues/{}", id)).await.json();
+        assert_eq!(fetched["data"]["title"], "Fix login");
+
+        let patched = server.patch(&format!("/api/issues/{}", id))
+            .json(&json!({ "status": "in_progress", "assignee": "alice", "labels": ["enhancement"] }))
+            .await;
+        patched.assert_status_ok();
+        let patched: Value = patched.json();
+        assert_eq!(patched["data"]["status"], "InProgress");
+        assert_eq!(patched["data"]["assignee"], "alice");
+        assert_eq!(patched["data"]["labels"], json!(["enhancement"]));
+        assert_eq!(patched["data"]["priority"], "High", "Omitted fields are kept");
+
+        server.post("/api/issues").json(&json!({ "title": "Write docs" })).await;
+        let listed: Value = server.get("/api/issues").await.json();
+        assert_eq!(listed["data"].as_array().unwrap().len(), 2);
+        let filtered: Value = server.get("/api/issues")
+            .add_query_param("status", "in_progress")
+            .add_query_param("assignee", "alice")
+            .await
+            .json();
+        assert_eq!(filtered["data"].as_array().unwrap().len(), 1);
+        assert_eq!(filtered["data"][0]["id"], id);
+
+        server.delete(&format!("/api/issues/{}", id)).await.assert_status_ok();
+        server.get(&format!("/api/issues/{}", id)).await.assert_status(StatusCode::NOT_FOUND);
+        server.delete(&format!("/api/issues/{}", id)).await.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_issue_changes_are_published() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        db.create_default_labels().await.unwrap();
+        let web = KanbanWebServer::new(db);
+        let mut events = web.app_state.subscribe();
+        let server = TestServer::new(web.create_router()).unwrap();
+
+        let created: Value = server.post("/api/issues").json(&json!({ "title": "Live" })).await.json();
+        let id = created["data"]["id"].as_i64().unwrap();
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.kind, BoardEventKind::Created);
+        assert_eq!(event.issue_number, id as u64);
+        assert_eq!(event.card.unwrap().title, "Live");
+        assert_eq!(event.column_id.as_deref(), Some("evaluating"));
+
+        server.post("/api/move")
+            .json(&json!({ "issue_number": id, "from_column": "evaluating", "to_column": "progressing" }))
+            .await
+            .assert_status_ok();
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.kind, BoardEventKind::Moved);
+        assert_eq!(event.column_id.as_deref(), Some("progressing"));
+
+        server.patch(&format!("/api/issues/{}", id)).json(&json!({ "labels": ["bug"] })).await;
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.kind, BoardEventKind::Updated);
+        assert_eq!(event.card.unwrap().labels, vec!["bug".to_string()]);
+        assert_eq!(event.column_id.as_deref(), Some("progressing"), "The status still places it");
+
+        let other: Value = server.post("/api/issues").json(&json!({ "title": "Also", "labels": ["bug"] })).await.json();
+        let other = other["data"]["id"].as_i64().unwrap();
+        events.recv().await.unwrap();
+
+        // One event per labeled card, each placed where the board shows it
+        server.patch("/api/labels/bug").json(&json!({ "name": "defect" })).await;
+        let board: Value = server.get("/api/board").await.json();
+        for expected in [id, other] {
+            let event = events.recv().await.unwrap();
+            assert_eq!(event.issue_number, expected as u64);
+            assert_eq!(event.card.unwrap().labels, vec!["defect".to_string()]);
+            let column = board["data"]["columns"].as_array().unwrap().iter()
+                .find(|column| column["id"] == event.column_id.as_deref().unwrap())
+                .unwrap();
+            assert_eq!(column["cards"][event.position.unwrap()]["issue_number"], expected);
+        }
+        assert!(events.try_recv().is_err());
+
+        server.delete(&format!("/api/issues/{}", id)).await;
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.kind, BoardEventKind::Deleted);
+        assert!(event.card.is_none() && event.column_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_issues_with_query() {
+        let server = test_server().await;
+        for (title, priority, labels) in [
+            ("Login broken", "high", vec!["bug"]),
+            ("Old crash", "critical", vec!["bug", "wontfix"]),
+            ("Write docs", "low", vec![]),
+        ] {
+            server.post("/api/issues")
+                .json(&json!({ "title": title, "priority": priority, "labels": labels }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+
+        let titles = |body: Value| body["data"].as_array().unwrap().iter()
+            .map(|issue| issue["title"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        let found: Value = server.get("/api/issues")
+            .add_query_param("q", "label:bug priority:>=high -label:wontfix")
+            .await
+            .json();
+        assert_eq!(titles(found), vec!["Login broken"]);
+
+        // The other filters still apply on top
+        let found: Value = server.get("/api/issues")
+            .add_query_param("q", "label:bug")
+            .add_query_param("priority", "critical")
+            .await
+            .json();
+        assert_eq!(titles(found), vec!["Old crash"]);
+
+        // and match like the same terms in `q`
+        let found: Value = server.get("/api/issues").add_query_param("label", "BUG").await.json();
+        assert_eq!(titles(found), vec!["Old crash", "Login broken"]);
+
+        let found: Value = server.get("/api/issues").add_query_param("q", "\"write docs\"").await.json();
+        assert_eq!(titles(found), vec!["Write docs"]);
+
+        let rejected = server.get("/api/issues").add_query_param("q", "colour:red").await;
+        rejected.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = rejected.json();
+        assert_eq!(body["error"], "validation");
+        assert!(body["message"].as_str().unwrap().contains("Unknown filter 'colour'"));
+    }
+
+    #[tokio::test]
+    async fn test_issue_validation() {
+        let server = test_server().await;
+
+        let empty = server.post("/api/issues").json(&json!({ "title": "  " })).await;
+        empty.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = empty.json();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "validation");
+        assert_eq!(body["message"], "Issue title must not be empty");
+
+        let unknown = server.post("/api/issues")
+            .json(&json!({ "title": "Labeled", "labels": ["nope"] }))
+            .await;
+        unknown.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        let missing = server.patch("/api/issues/42").json(&json!({ "title": "Ghost" })).await;
+        missing.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(missing.json::<Value>()["error"], "not_found");
+
+        // Malformed input gets the same JSON error body as handler failures
+        let malformed = server.post("/api/issues").text("{ not json").content_type("application/json").await;
+        malformed.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(malformed.json::<Value>()["error"], "validation");
+        let bad_status = server.get("/api/issues").add_query_param("status", "sleeping").await;
+        bad_status.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        let bad_id = server.get("/api/issues/abc").await;
+        bad_id.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(bad_id.json::<Value>()["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_label_crud() {
+        let server = test_server().await;
+
+        let created = server.post("/api/labels")
+            .json(&json!({ "name": "triage", "color": "#123456" }))
+            .await;
+        created.assert_status(StatusCode::CREATED);
+        let duplicate = server.post("/api/labels").json(&json!({ "name": "triage" })).await;
+        duplicate.assert_status(StatusCode::CONFLICT);
+        assert_eq!(duplicate.json::<Value>()["error"], "conflict");
+
+        let issue: Value = server.post("/api/issues")
+            .json(&json!({ "title": "Sort me", "labels": ["triage"] }))
+            .await
+            .json();
+        let issue_id = issue["data"]["id"].as_i64().unwrap();
+
+        let renamed: Value = server.patch("/api/labels/triage")
+            .json(&json!({ "name": "needs-triage", "description": "Not looked at yet" }))
+            .await
+            .json();
+        assert_eq!(renamed["data"]["name"], "needs-triage");
+        assert_eq!(renamed["data"]["color"], "#123456");
+        server.patch("/api/labels/needs-triage").json(&json!({ "name": "bug" })).await
+            .assert_status(StatusCode::CONFLICT);
+
+        let fetched: Value = server.get("/api/labels/needs-triage").await.json();
+        assert_eq!(fetched["data"]["description"], "Not looked at yet");
+        let issue: Value = server.get(&format!("/api/issues/{}", issue_id)).await.json();
+        assert_eq!(issue["data"]["labels"], json!(["needs-triage"]));
+
+        server.delete("/api/labels/needs-triage").await.assert_status_ok();
+        server.get("/api/labels/needs-triage").await.assert_status(StatusCode::NOT_FOUND);
+        let labels: Value = server.get("/api/labels").await.json();
+        assert!(labels["data"].as_array().unwrap().iter().all(|l| l["name"] != "needs-triage"));
+        let issue: Value = server.get(&format!("/api/issues/{}", issue_id)).await.json();
+        assert_eq!(issue["data"]["labels"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_list_commits_with_filters() {
+        let db = TaskDatabase::in_memory().await.unwrap();
+        for (hash, author, date) in [("aaa", "Alice", "2024-01-01T00:00:00Z"), ("bbb", "Bob", "2024-02-01T00:00:00Z")] {
+            db.insert_commit(&GitCommit {
+                id: None,
+                hash: hash.to_string(),
+                author_name: author.to_string(),
+                author_email: format!("{}@example.com", author.to_lowercase()),
+                commit_date: date.parse().unwrap(),
+                message: "Change".to_string(),
+                parent_hashes: vec![],
+                files_changed: vec!["src/db.rs".to_string()],
+                file_stats: vec![],
+                insertions: 1,
+                deletions: 0,
+            }).await.unwrap();
+        }
+        let server = TestServer::new(KanbanWebServer::new(db).create_router()).unwrap();
+
+        let all: Value = server.get("/api/commits").await.json();
+        assert_eq!(all["data"].as_array().unwrap().len(), 2);
+        assert_eq!(all["data"][0]["hash"], "bbb", "Newest first");
+
+        let filtered: Value = server.get("/api/commits")
+            .add_query_param("author", "alice")
+            .add_query_param("path", "src")
+            .add_query_param("until", "2024-01-15T00:00:00Z")
+            .await
+            .json();
+        assert_eq!(filtered["data"].as_array().unwrap().len(), 1);
+        assert_eq!(filtered["data"][0]["hash"], "aaa");
+
+        let limited: Value = server.get("/api/commits").add_query_param("limit", 1).await.json();
+        assert_eq!(limited["data"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let server = test_server().await;
+        for (title, description) in [("Login <button> broken", "Clicking does nothing"), ("Docs", "Explain the login flow")] {
+            server.post("/api/issues")
+                .json(&json!({ "title": title, "description": description }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+
+        let found: Value = server.get("/api/search").add_query_param("q", "login").await.json();
+        let issues = found["data"]["issues"].as_array().unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0]["title"], "Login <button> broken", "Title matches rank first");
+        assert_eq!(issues[0]["snippet"], "<mark>Login</mark> &lt;button&gt; broken");
+        assert!(issues[0]["score"].as_f64().unwrap() > issues[1]["score"].as_f64().unwrap());
+        assert_eq!(found["data"]["commits"], json!([]));
+
+        let limited: Value = server.get("/api/search").add_query_param("q", "login").add_query_param("limit", 1).await.json();
+        assert_eq!(limited["data"]["issues"].as_array().unwrap().len(), 1);
+
+        server.get("/api/search").add_query_param("q", "  ").await.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        server.get("/api/search").await.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_comment_crud() {
+        let server = test_server().await;
+        let issue: Value = server.post("/api/issues").json(&json!({ "title": "Fix login" })).await.json();
+        let id = issue["data"]["id"].as_i64().unwrap();
+
+        let created = server.post(&format!("/api/issues/{}/comments", id))
+            .json(&json!({ "author": "alice", "body": "Fails with **SSO** only" }))
+            .await;
+        created.assert_status(StatusCode::CREATED);
+        let comment: Value = created.json();
+        let comment_id = comment["data"]["id"].as_i64().unwrap();
+        assert_eq!(comment["data"]["body_html"], "<p>Fails with <strong>SSO</strong> only</p>\n");
+
+        let updated: Value = server.patch(&format!("/api/comments/{}", comment_id))
+            .json(&json!({ "body": "Fails with SAML only" }))
+            .await
+            .json();
+        assert_eq!(updated["data"]["body"], "Fails with SAML only");
+
+        let listed: Value = server.get(&format!("/api/issues/{}/comments", id)).await.json();
+        assert_eq!(listed["data"].as_array().unwrap().len(), 1);
+        assert_eq!(listed["data"][0]["author"], "alice");
+
+        for (author, body) in [("", "Hi"), ("bob", " ")] {
+            server.post(&format!("/api/issues/{}/comments", id))
+                .json(&json!({ "author": author, "body": body }))
+                .await
+                .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        server.get("/api/issues/999/comments").await.assert_status(StatusCode::NOT_FOUND);
+        server.post("/api/issues/999/comments")
+            .json(&json!({ "author": "bob", "body": "Hi" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        server.delete(&format!("/api/comments/{}", comment_id)).await.assert_status_ok();
+        server.delete(&format!("/api/comments/{}", comment_id)).await.assert_status(StatusCode::NOT_FOUND);
+        server.patch(&format!("/api/comments/{}", comment_id))
+            .json(&json!({ "body": "Gone" }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_issue_events() {
+        let server = test_server().await;
+        let issue: Value = server.post("/api/issues").json(&json!({ "title": "Fix login" })).await.json();
+        let id = issue["data"]["id"].as_i64().unwrap();
+        server.patch(&format!("/api/issues/{}", id))
+            .json(&json!({ "status": "in_progress", "labels": ["bug"] }))
+            .await
+            .assert_status_ok();
+        server.delete(&format!("/api/issues/{}", id)).await.assert_status_ok();
+
+        let events: Value = server.get(&format!("/api/issues/{}/events", id)).await.json();
+        let fields: Vec<&str> = events["data"].as_array().unwrap().iter().map(|e| e["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, ["created", "status", "label", "deleted"]);
+        assert_eq!(events["data"][1]["old_value"], "open");
+        assert_eq!(events["data"][1]["new_value"], "in_progress");
+        assert_eq!(events["data"][2]["new_value"], "bug");
+
+        server.get("/api/issues/999/events").await.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_undo_and_trash() {
+        let server = test_server().await;
+        let issue: Value = server.post("/api/issues").json(&json!({ "title": "Fix login" })).await.json();
+        let id = issue["data"]["id"].as_i64().unwrap();
+
+        let moved: Value = server.post("/api/move")
+            .json(&json!({ "issue_number": id, "from_column": "evaluating", "to_column": "progressing" }))
+            .await
+            .json();
+        let operation = moved["data"]["operation_id"].as_i64().unwrap();
+        let undone: Value = server.post(&format!("/api/operations/{}/undo", operation)).await.json();
+        assert_eq!(undone["data"]["undoes"], operation);
+        let fetched: Value = server.get(&format!("/api/issues/{}", id)).await.json();
+        assert_eq!(fetched["data"]["status"], "Open");
+        server.post(&format!("/api/operations/{}/undo", operation)).await.assert_status(StatusCode::CONFLICT);
+        server.post("/api/operations/999/undo").await.assert_status(StatusCode::NOT_FOUND);
+
+        let deleted: Value = server.delete(&format!("/api/issues/{}", id)).await.json();
+        let operation = deleted["data"]["operation_id"].as_i64().unwrap();
+        let trash: Value = server.get("/api/trash").await.json();
+        assert_eq!(trash["data"][0]["id"], id);
+        assert!(trash["data"][0]["deleted_at"].is_string());
+        server.post(&format!("/api/operations/{}/undo", operation)).await.assert_status_ok();
+        server.get(&format!("/api/issues/{}", id)).await.assert_status_ok();
+
+        server.delete(&format!("/api/issues/{}", id)).await.assert_status_ok();
+        server.post(&format!("/api/trash/{}/restore", id)).await.assert_status_ok();
+        server.post(&format!("/api/trash/{}/restore", id)).await.assert_status(StatusCode::NOT_FOUND);
+
+        server.delete(&format!("/api/trash/{}", id)).await.assert_status(StatusCode::NOT_FOUND);
+        server.delete(&format!("/api/issues/{}", id)).await.assert_status_ok();
+        server.delete(&format!("/api/trash/{}", id)).await.assert_status_ok();
+        server.delete("/api/trash").await.assert_status_ok();
+        let trash: Value = server.get("/api/trash").await.json();
+        assert_eq!(trash["data"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_issue_links() {
+        let server = test_server().await;
+        let mut ids = Vec::new();
+        for title in ["Design schema", "Build API"] {
+            let issue: Value = server.post("/api/issues").json(&json!({ "title": title })).await.json();
+            ids.push(issue["data"]["id"].as_i64().unwrap());
+        }
+        let (schema, api) = (ids[0], ids[1]);
+
+        let created = server.post(&format!("/api/issues/{}/links", api))
+            .json(&json!({ "kind": "blocked-by", "issue": schema }))
+            .await;
+        created.assert_status(StatusCode::CREATED);
+        let links: Value = created.json();
+        assert_eq!(links["data"], json!([{ "kind": "blocked-by", "issue_id": schema, "title": "Design schema", "status": "Open" }]));
+        server.post(&format!("/api/issues/{}/links", api))
+            .json(&json!({ "kind": "blocks", "issue": schema }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server.post(&format!("/api/issues/{}/links", api))
+            .json(&json!({ "kind": "parent-of", "issue": schema }))
+            .await
+            .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        let links: Value = server.get(&format!("/api/issues/{}/links", schema)).await.json();
+        assert_eq!(links["data"][0]["kind"], "blocks");
+        let ready: Value = server.get("/api/ready").await.json();
+        assert_eq!(ready["data"].as_array().unwrap().len(), 1);
+        assert_eq!(ready["data"][0]["id"], schema);
+        let board: Value = server.get("/api/board").await.json();
+        let cards = board["data"]["columns"][0]["cards"].as_array().unwrap();
+        let blocked = cards.iter().find(|card| card["issue_number"] == api).unwrap();
+        assert_eq!(blocked["blocked_by"], json!([schema]));
+
+        server.delete(&format!("/api/issues/{}/links/blocks/{}", schema, api)).await.assert_status_ok();
+        server.delete(&format!("/api/issues/{}/links/blocks/{}", schema, api)).await.assert_status(StatusCode::NOT_FOUND);
+        let ready: Value = server.get("/api/ready").await.json();
+        assert_eq!(ready["data"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_subtasks() {
+        let server = test_server().await;
+        let epic: Value = server.post("/api/issues")
+            .json(&json!({ "title": "Epic", "description": "- [x] Plan\n- [ ] Ship" }))
+            .await
+            .json();
+        let epic = epic["data"]["id"].as_i64().unwrap();
+        let mut tasks = Vec::new();
+        for title in ["Task 1", "Task 2"] {
+            let task: Value = server.post("/api/issues").json(&json!({ "title": title, "parent_id": epic })).await.json();
+            assert_eq!(task["data"]["parent_id"], epic);
+            tasks.push(task["data"]["id"].as_i64().unwrap());
+        }
+        server.post("/api/issues")
+            .json(&json!({ "title": "Orphan", "parent_id": 999 }))
+            .await
+            .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        server.patch(&format!("/api/issues/{}", epic))
+            .json(&json!({ "parent_id": tasks[0] }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+        server.patch(&format!("/api/issues/{}", tasks[0])).json(&json!({ "status": "resolved" })).await.assert_status_ok();
+
+        let children: Value = server.get(&format!("/api/issues/{}/children", epic)).await.json();
+        assert_eq!(children["data"].as_array().unwrap().len(), 2);
+        let board: Value = server.get("/api/board").await.json();
+        let cards: Vec<&Value> = board["data"]["columns"].as_array().unwrap().iter()
+            .flat_map(|column| column["cards"].as_array().unwrap())
+            .collect();
+        let epic_card = cards.iter().find(|card| card["issue_number"] == epic).unwrap();
+        assert_eq!(epic_card["subtasks"], json!({ "done": 1, "total": 2 }));
+        assert_eq!(epic_card["tasks"], json!({ "done": 1, "total": 2 }));
+
+        // null clears the parent, leaving other fields alone
+        let task: Value = server.patch(&format!("/api/issues/{}", tasks[1]))
+            .json(&json!({ "parent_id": null }))
+            .await
+            .json();
+        assert_eq!(task["data"]["parent_id"], Value::Null);
+        let task: Value = server.patch(&format!("/api/issues/{}", tasks[0])).json(&json!({ "title": "Task one" })).await.json();
+        assert_eq!(task["data"]["parent_id"], epic);
+    }
+}